@@ -91,7 +91,7 @@ async fn main() -> Result<()> {
 
     // Wait for vim to exit
     match term.wait_exit().await {
-        Ok(code) => println!("Vim exited with code: {}", code),
+        Ok(status) => println!("Vim {}", status),
         Err(e) => println!("Error waiting for exit: {}", e),
     }
 
@@ -0,0 +1,260 @@
+//! Asciicast v2 recordings.
+//!
+//! [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/) is the
+//! format asciinema records and plays: a JSON header line followed by one
+//! JSON array per event, `[seconds, code, data]`. A terminal records its
+//! session with [`TerminalBuilder::record_to`](crate::terminal::TerminalBuilder::record_to)
+//! or [`Terminal::record_start`](crate::Terminal::record_start).
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+/// The first line of a recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Header {
+    /// Format version, always 2.
+    pub version: u8,
+    /// Terminal width in columns.
+    pub width: u16,
+    /// Terminal height in rows.
+    pub height: u16,
+    /// Unix time the recording started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    /// Environment of the recorded session, such as `TERM`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Title of the recording.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+impl Header {
+    /// Create a version 2 header for a terminal of the given size, stamped
+    /// with the current time.
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            version: 2,
+            width,
+            height,
+            timestamp: Some(chrono::Utc::now().timestamp()),
+            env: BTreeMap::new(),
+            title: None,
+        }
+    }
+}
+
+/// What an event records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// Output written by the application (`o`).
+    Output,
+    /// Input sent to the application (`i`).
+    Input,
+    /// The terminal was resized to `COLSxROWS` (`r`).
+    Resize,
+    /// A named marker (`m`).
+    Marker,
+}
+
+impl EventKind {
+    /// The event code used in the file.
+    pub fn code(self) -> &'static str {
+        match self {
+            EventKind::Output => "o",
+            EventKind::Input => "i",
+            EventKind::Resize => "r",
+            EventKind::Marker => "m",
+        }
+    }
+
+    /// Parse an event code.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "o" => Some(EventKind::Output),
+            "i" => Some(EventKind::Input),
+            "r" => Some(EventKind::Resize),
+            "m" => Some(EventKind::Marker),
+            _ => None,
+        }
+    }
+}
+
+/// One line of a recording after the header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    /// Seconds since the recording started.
+    pub time: f64,
+    /// What the event records.
+    pub kind: EventKind,
+    /// Output or input text, the new size, or the marker label.
+    pub data: String,
+}
+
+impl Serialize for Event {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        (self.time, self.kind.code(), &self.data).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Event {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let (time, code, data) = <(f64, String, String)>::deserialize(deserializer)?;
+        let kind = EventKind::from_code(&code)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown event code {code:?}")))?;
+        Ok(Self { time, kind, data })
+    }
+}
+
+/// Writes a recording event by event.
+///
+/// Each event is flushed as it is written, so a recording cut short by a
+/// crash is still playable. Output and input may arrive split in the middle
+/// of a UTF-8 character; the incomplete tail is kept until the rest arrives.
+pub struct CastWriter<W: Write> {
+    writer: W,
+    started: Instant,
+    pending_output: Vec<u8>,
+    pending_input: Vec<u8>,
+}
+
+impl<W: Write> CastWriter<W> {
+    /// Write the header and start the clock.
+    pub fn new(mut writer: W, header: &Header) -> Result<Self> {
+        serde_json::to_writer(&mut writer, header)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        Ok(Self {
+            writer,
+            started: Instant::now(),
+            pending_output: Vec::new(),
+            pending_input: Vec::new(),
+        })
+    }
+
+    /// Record output written by the application.
+    pub fn output(&mut self, bytes: &[u8]) -> Result<()> {
+        let data = take_utf8(&mut self.pending_output, bytes);
+        self.event(EventKind::Output, data)
+    }
+
+    /// Record input sent to the application.
+    pub fn input(&mut self, bytes: &[u8]) -> Result<()> {
+        let data = take_utf8(&mut self.pending_input, bytes);
+        self.event(EventKind::Input, data)
+    }
+
+    /// Record a resize.
+    pub fn resize(&mut self, cols: u16, rows: u16) -> Result<()> {
+        self.event(EventKind::Resize, format!("{cols}x{rows}"))
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn event(&mut self, kind: EventKind, data: String) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let event = Event {
+            time: (self.started.elapsed().as_secs_f64() * 1e6).round() / 1e6,
+            kind,
+            data,
+        };
+        serde_json::to_writer(&mut self.writer, &event)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Decode `bytes` after whatever was left over last time, keeping an
+/// incomplete character at the end for the next call.
+fn take_utf8(pending: &mut Vec<u8>, bytes: &[u8]) -> String {
+    pending.extend_from_slice(bytes);
+    let keep = match std::str::from_utf8(pending) {
+        Ok(_) => 0,
+        Err(e) if e.error_len().is_none() => pending.len() - e.valid_up_to(),
+        Err(_) => incomplete_tail(pending),
+    };
+    let tail = pending.split_off(pending.len() - keep);
+    let text = String::from_utf8_lossy(pending).into_owned();
+    *pending = tail;
+    text
+}
+
+/// Length of an incomplete UTF-8 sequence at the end of `bytes`, which
+/// also contains invalid sequences further back.
+fn incomplete_tail(bytes: &[u8]) -> usize {
+    let start = bytes.len().saturating_sub(3);
+    (start..bytes.len())
+        .find(|&i| {
+            std::str::from_utf8(&bytes[i..])
+                .is_err_and(|e| e.error_len().is_none() && e.valid_up_to() == 0)
+        })
+        .map_or(0, |i| bytes.len() - i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_header_and_events() {
+        let mut header = Header::new(80, 24);
+        header.timestamp = None;
+        header
+            .env
+            .insert("TERM".to_string(), "xterm-256color".to_string());
+
+        let mut writer = CastWriter::new(Vec::new(), &header).unwrap();
+        writer.output(b"hi\x1b[1m").unwrap();
+        writer.input(b"q").unwrap();
+        writer.resize(100, 30).unwrap();
+        let recording = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        let mut lines = recording.lines();
+        assert_eq!(
+            lines.next(),
+            Some(r#"{"version":2,"width":80,"height":24,"env":{"TERM":"xterm-256color"}}"#)
+        );
+        let events: Vec<Event> = lines
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let kinds: Vec<_> = events.iter().map(|e| (e.kind, e.data.as_str())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (EventKind::Output, "hi\x1b[1m"),
+                (EventKind::Input, "q"),
+                (EventKind::Resize, "100x30"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_characters_split_across_chunks_together() {
+        let mut pending = Vec::new();
+        let euro = "€".as_bytes();
+        assert_eq!(take_utf8(&mut pending, &[b'a', euro[0]]), "a");
+        assert_eq!(take_utf8(&mut pending, &euro[1..]), "€");
+        assert_eq!(
+            take_utf8(&mut pending, &[0xff, b'b', euro[0], euro[1]]),
+            "\u{fffd}b"
+        );
+        assert_eq!(take_utf8(&mut pending, &euro[2..]), "€");
+    }
+}
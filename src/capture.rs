@@ -0,0 +1,596 @@
+//! Raw output capture.
+//!
+//! The terminal keeps the bytes the application wrote, exactly as they came
+//! out of the PTY, in a bounded buffer. This makes it possible to assert on
+//! escape sequences that leave no trace on the screen, such as resetting
+//! attributes on exit or switching to the alternate screen. [`annotate`]
+//! turns captured bytes into a readable listing of text and escape sequences.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// A position in the output stream: the number of bytes written before it.
+///
+/// Read one with [`Terminal::output_mark`](crate::Terminal::output_mark)
+/// before an action, then look at what was written after it.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct OutputMark(pub u64);
+
+/// Bytes read from the PTY in one go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputChunk {
+    /// Position of the first byte in the output stream.
+    pub offset: u64,
+    /// Time since the terminal was spawned.
+    pub elapsed: Duration,
+    /// The raw bytes.
+    pub bytes: Vec<u8>,
+}
+
+/// Output captured after a mark.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapturedOutput {
+    /// Captured chunks, oldest first.
+    pub chunks: Vec<OutputChunk>,
+    /// Part of the output after the mark has already been dropped from the
+    /// buffer.
+    pub truncated: bool,
+    /// Mark at the end of the captured output.
+    pub end: OutputMark,
+}
+
+impl CapturedOutput {
+    /// All captured bytes in order.
+    pub fn bytes(&self) -> Vec<u8> {
+        self.chunks
+            .iter()
+            .flat_map(|chunk| chunk.bytes.iter().copied())
+            .collect()
+    }
+
+    /// Returns true if the captured bytes contain `needle`.
+    pub fn contains(&self, needle: impl AsRef<[u8]>) -> bool {
+        let needle = needle.as_ref();
+        needle.is_empty() || self.bytes().windows(needle.len()).any(|w| w == needle)
+    }
+
+    /// The captured bytes listed with [`annotate`].
+    pub fn annotated(&self) -> String {
+        annotate(&self.bytes())
+    }
+}
+
+/// Bounded buffer of raw output, dropping the oldest bytes once full.
+#[derive(Debug)]
+pub(crate) struct OutputBuffer {
+    chunks: VecDeque<OutputChunk>,
+    len: usize,
+    capacity: usize,
+    total: u64,
+    started: Instant,
+}
+
+impl Default for OutputBuffer {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl OutputBuffer {
+    /// Create a buffer keeping up to `capacity` bytes; zero keeps nothing.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            len: 0,
+            capacity,
+            total: 0,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn mark(&self) -> OutputMark {
+        OutputMark(self.total)
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.push_at(bytes, self.started.elapsed());
+    }
+
+    /// Append output written `elapsed` after the buffer was created.
+    pub fn push_at(&mut self, bytes: &[u8], elapsed: Duration) {
+        let offset = self.total;
+        self.total += bytes.len() as u64;
+        if self.capacity == 0 || bytes.is_empty() {
+            return;
+        }
+
+        let skip = bytes.len().saturating_sub(self.capacity);
+        self.chunks.push_back(OutputChunk {
+            offset: offset + skip as u64,
+            elapsed,
+            bytes: bytes[skip..].to_vec(),
+        });
+        self.len += bytes.len() - skip;
+
+        while self.len > self.capacity {
+            let excess = self.len - self.capacity;
+            let Some(front) = self.chunks.front_mut() else {
+                break;
+            };
+            if front.bytes.len() <= excess {
+                self.len -= front.bytes.len();
+                self.chunks.pop_front();
+            } else {
+                front.bytes.drain(..excess);
+                front.offset += excess as u64;
+                self.len -= excess;
+            }
+        }
+    }
+
+    /// Buffered chunks after `mark`, each with the part before the mark cut
+    /// off.
+    fn chunks_since(&self, mark: OutputMark) -> impl Iterator<Item = (&OutputChunk, usize)> {
+        self.chunks
+            .iter()
+            .filter(move |chunk| chunk.offset + chunk.bytes.len() as u64 > mark.0)
+            .map(move |chunk| (chunk, mark.0.saturating_sub(chunk.offset) as usize))
+    }
+
+    /// Output written after `mark` that is still buffered.
+    pub fn since(&self, mark: OutputMark) -> CapturedOutput {
+        let retained = self.total - self.len as u64;
+        let chunks = self
+            .chunks_since(mark)
+            .map(|(chunk, skip)| OutputChunk {
+                offset: chunk.offset + skip as u64,
+                elapsed: chunk.elapsed,
+                bytes: chunk.bytes[skip..].to_vec(),
+            })
+            .collect();
+        CapturedOutput {
+            chunks,
+            truncated: mark.0 < retained,
+            end: self.mark(),
+        }
+    }
+
+    /// Buffered bytes written after `mark`, in one piece.
+    pub fn bytes_since(&self, mark: OutputMark) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (chunk, skip) in self.chunks_since(mark) {
+            bytes.extend_from_slice(&chunk.bytes[skip..]);
+        }
+        bytes
+    }
+
+    /// Returns true if the bytes buffered after `mark` contain `needle`,
+    /// searching the chunks in place.
+    pub fn contains_since(&self, mark: OutputMark, needle: &[u8]) -> bool {
+        if needle.is_empty() {
+            return true;
+        }
+        // A match across a chunk edge starts in the last `keep` bytes before it.
+        let keep = needle.len() - 1;
+        let mut tail = Vec::with_capacity(keep * 2);
+        for (chunk, skip) in self.chunks_since(mark) {
+            let bytes = &chunk.bytes[skip..];
+            let edge = !tail.is_empty();
+            tail.extend_from_slice(&bytes[..bytes.len().min(keep)]);
+            if (edge && contains(&tail, needle)) || contains(bytes, needle) {
+                return true;
+            }
+            if bytes.len() >= keep {
+                tail.clear();
+                tail.extend_from_slice(&bytes[bytes.len() - keep..]);
+            } else {
+                tail.drain(..tail.len().saturating_sub(keep));
+            }
+        }
+        false
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+/// List text and escape sequences in `bytes`, one per line.
+///
+/// Text is quoted, control characters are named and escape sequences are
+/// shown with `\e` for ESC followed by what they do, e.g.
+/// `\e[?1049h  set mode 1049 (alternate screen, saving cursor)`.
+pub fn annotate(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let (len, description) = match bytes[i] {
+            0x1b => escape_sequence(&bytes[i..]),
+            byte if byte < 0x20 || byte == 0x7f => (1, control_name(byte)),
+            _ => {
+                let len = bytes[i..]
+                    .iter()
+                    .position(|&b| b < 0x20 || b == 0x7f)
+                    .unwrap_or(bytes.len() - i);
+                let text = String::from_utf8_lossy(&bytes[i..i + len]);
+                let _ = writeln!(out, "{text:?}");
+                i += len;
+                continue;
+            }
+        };
+
+        let raw = escape_raw(&bytes[i..i + len]);
+        if bytes[i] == 0x1b {
+            let _ = writeln!(out, "{raw}  {description}");
+        } else {
+            let _ = writeln!(out, "{description}");
+        }
+        i += len;
+    }
+    out
+}
+
+fn control_name(byte: u8) -> String {
+    let name = match byte {
+        0x00 => "NUL",
+        0x07 => "BEL",
+        0x08 => "BS",
+        0x09 => "HT",
+        0x0a => "LF",
+        0x0b => "VT",
+        0x0c => "FF",
+        0x0d => "CR",
+        0x0e => "SO",
+        0x0f => "SI",
+        0x7f => "DEL",
+        _ => return format!("^{}", (byte + 0x40) as char),
+    };
+    name.to_string()
+}
+
+/// Show a sequence with `\e` for ESC and other control bytes escaped.
+fn escape_raw(bytes: &[u8]) -> String {
+    let mut raw = String::new();
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\x1b' => raw.push_str("\\e"),
+                '\x07' => raw.push_str("\\a"),
+                c if c.is_control() => {
+                    let _ = write!(raw, "\\x{:02x}", c as u32);
+                }
+                c => raw.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            let _ = write!(raw, "\\x{byte:02x}");
+        }
+    }
+    raw
+}
+
+/// Measure the escape sequence at the start of `bytes` and describe it.
+fn escape_sequence(bytes: &[u8]) -> (usize, String) {
+    match bytes.get(1) {
+        Some(b'[') => {
+            let end = bytes[2..].iter().position(|b| (0x40..=0x7e).contains(b));
+            match end {
+                Some(end) => {
+                    let params = String::from_utf8_lossy(&bytes[2..2 + end]);
+                    (end + 3, describe_csi(&params, bytes[2 + end]))
+                }
+                None => (bytes.len(), "incomplete CSI".to_string()),
+            }
+        }
+        Some(&kind @ (b']' | b'P' | b'X' | b'^' | b'_')) => {
+            // Strings end with BEL (OSC only) or ST.
+            let body = &bytes[2..];
+            let end = body.iter().enumerate().find_map(|(i, &b)| match b {
+                0x07 if kind == b']' => Some((i, 1)),
+                0x1b if body.get(i + 1) == Some(&b'\\') => Some((i, 2)),
+                _ => None,
+            });
+            let (body_len, terminator) = end.unwrap_or((body.len(), 0));
+            let payload = String::from_utf8_lossy(&body[..body_len]);
+            let description = match kind {
+                b']' => describe_osc(&payload),
+                b'P' => format!("DCS {}", describe_dcs(&payload)),
+                b'_' => "APC".to_string(),
+                b'^' => "PM".to_string(),
+                _ => "SOS".to_string(),
+            };
+            (2 + body_len + terminator, description)
+        }
+        Some(0x1b) | None => (1, "ESC".to_string()),
+        Some(_) => {
+            // ESC, intermediates, then a final byte.
+            let end = bytes[1..].iter().position(|b| !(0x20..=0x2f).contains(b));
+            let len = end.map_or(bytes.len(), |end| end + 2);
+            let description = match &bytes[1..len] {
+                b"7" => "save cursor (DECSC)",
+                b"8" => "restore cursor (DECRC)",
+                b"=" => "application keypad (DECKPAM)",
+                b">" => "numeric keypad (DECKPNM)",
+                b"D" => "index (IND)",
+                b"E" => "next line (NEL)",
+                b"M" => "reverse index (RI)",
+                b"c" => "full reset (RIS)",
+                b"\\" => "string terminator (ST)",
+                [b'(', ..] => "designate G0 charset",
+                [b')', ..] => "designate G1 charset",
+                _ => "escape sequence",
+            };
+            (len, description.to_string())
+        }
+    }
+}
+
+fn describe_csi(params: &str, final_byte: u8) -> String {
+    let n = |default: u32| params.parse::<u32>().unwrap_or(default);
+    match (final_byte, params) {
+        (b'A', _) => format!("cursor up {} (CUU)", n(1)),
+        (b'B', _) => format!("cursor down {} (CUD)", n(1)),
+        (b'C', _) => format!("cursor forward {} (CUF)", n(1)),
+        (b'D', _) => format!("cursor back {} (CUB)", n(1)),
+        (b'E', _) => format!("cursor to start of line {} down (CNL)", n(1)),
+        (b'F', _) => format!("cursor to start of line {} up (CPL)", n(1)),
+        (b'G', _) => format!("cursor to column {} (CHA)", n(1)),
+        (b'd', _) => format!("cursor to row {} (VPA)", n(1)),
+        (b'H' | b'f', _) => {
+            let mut parts = params.split(';').map(|p| p.parse::<u32>().unwrap_or(1));
+            let row = parts.next().unwrap_or(1);
+            let col = parts.next().unwrap_or(1);
+            format!("cursor to row {row}, column {col} (CUP)")
+        }
+        (b'J', _) => match n(0) {
+            0 => "erase below cursor (ED)".to_string(),
+            1 => "erase above cursor (ED)".to_string(),
+            2 => "erase screen (ED)".to_string(),
+            3 => "erase scrollback (ED)".to_string(),
+            _ => "erase in display (ED)".to_string(),
+        },
+        (b'K', _) => match n(0) {
+            0 => "erase to end of line (EL)".to_string(),
+            1 => "erase to start of line (EL)".to_string(),
+            _ => "erase line (EL)".to_string(),
+        },
+        (b'@', _) => format!("insert {} characters (ICH)", n(1)),
+        (b'P', _) => format!("delete {} characters (DCH)", n(1)),
+        (b'X', _) => format!("erase {} characters (ECH)", n(1)),
+        (b'L', _) => format!("insert {} lines (IL)", n(1)),
+        (b'M', _) => format!("delete {} lines (DL)", n(1)),
+        (b'S', _) => format!("scroll up {} (SU)", n(1)),
+        (b'T', _) => format!("scroll down {} (SD)", n(1)),
+        (b'r', _) => format!("set scroll region {} (DECSTBM)", display_params(params)),
+        (b'm', p) if p.starts_with('>') => format!("set key modifier options {p} (XTMODKEYS)"),
+        (b'm', _) => format!("SGR {}", describe_sgr(params)),
+        (b'h' | b'l', p) => {
+            let action = if final_byte == b'h' { "set" } else { "reset" };
+            match p.strip_prefix('?') {
+                Some(modes) => {
+                    let modes: Vec<_> = modes.split(';').map(describe_private_mode).collect();
+                    format!("{action} mode {}", modes.join(", "))
+                }
+                None => format!("{action} ANSI mode {p}"),
+            }
+        }
+        (b'p', p) if p.ends_with('$') => format!("request mode {} (DECRQM)", &p[..p.len() - 1]),
+        (b'q', p) if p.ends_with(' ') => format!("set cursor style {} (DECSCUSR)", p.trim()),
+        (b'q', ">" | ">0") => "request terminal version (XTVERSION)".to_string(),
+        (b'n', "6" | "?6") => "request cursor position (DSR)".to_string(),
+        (b'n', _) => "device status report (DSR)".to_string(),
+        (b'c', p) if p.starts_with('>') => "request secondary device attributes (DA2)".to_string(),
+        (b'c', _) => "request primary device attributes (DA1)".to_string(),
+        (b'u', p) if p.starts_with('>') => format!("push kitty keyboard flags {}", &p[1..]),
+        (b'u', p) if p.starts_with('<') => "pop kitty keyboard flags".to_string(),
+        (b'u', p) if p.starts_with('=') => format!("set kitty keyboard flags {}", &p[1..]),
+        (b'u', "?") => "request kitty keyboard flags".to_string(),
+        (b's', "") => "save cursor".to_string(),
+        (b'u', "") => "restore cursor".to_string(),
+        (b't', _) => format!("window operation {}", display_params(params)),
+        _ => "CSI".to_string(),
+    }
+}
+
+fn display_params(params: &str) -> &str {
+    if params.is_empty() {
+        "(defaults)"
+    } else {
+        params
+    }
+}
+
+fn describe_private_mode(mode: &str) -> String {
+    let name = match mode {
+        "1" => "application cursor keys",
+        "6" => "origin mode",
+        "7" => "autowrap",
+        "12" => "blinking cursor",
+        "25" => "cursor visible",
+        "47" | "1047" => "alternate screen",
+        "1049" => "alternate screen, saving cursor",
+        "9" => "X10 mouse reporting",
+        "1000" => "mouse press and release reporting",
+        "1002" => "mouse button motion reporting",
+        "1003" => "mouse any motion reporting",
+        "1004" => "focus events",
+        "1005" => "UTF-8 mouse encoding",
+        "1006" => "SGR mouse encoding",
+        "1015" => "urxvt mouse encoding",
+        "1016" => "SGR-pixels mouse encoding",
+        "2004" => "bracketed paste",
+        "2026" => "synchronized output",
+        _ => return mode.to_string(),
+    };
+    format!("{mode} ({name})")
+}
+
+fn describe_sgr(params: &str) -> String {
+    if params.is_empty() {
+        return "reset".to_string();
+    }
+
+    const COLORS: [&str; 8] = [
+        "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+    ];
+    let codes: Vec<&str> = params.split([';', ':']).collect();
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < codes.len() {
+        let code = codes[i].parse::<u32>().unwrap_or(0);
+        let part = match code {
+            0 => "reset".to_string(),
+            1 => "bold".to_string(),
+            2 => "dim".to_string(),
+            3 => "italic".to_string(),
+            4 => "underline".to_string(),
+            5 => "blink".to_string(),
+            7 => "inverse".to_string(),
+            8 => "hidden".to_string(),
+            9 => "strikethrough".to_string(),
+            22 => "normal intensity".to_string(),
+            23 => "not italic".to_string(),
+            24 => "not underlined".to_string(),
+            25 => "not blinking".to_string(),
+            27 => "not inverse".to_string(),
+            28 => "not hidden".to_string(),
+            29 => "not strikethrough".to_string(),
+            30..=37 => format!("{} foreground", COLORS[(code - 30) as usize]),
+            39 => "default foreground".to_string(),
+            40..=47 => format!("{} background", COLORS[(code - 40) as usize]),
+            49 => "default background".to_string(),
+            90..=97 => format!("bright {} foreground", COLORS[(code - 90) as usize]),
+            100..=107 => format!("bright {} background", COLORS[(code - 100) as usize]),
+            38 | 48 | 58 => {
+                let target = match code {
+                    38 => "foreground",
+                    48 => "background",
+                    _ => "underline color",
+                };
+                match codes.get(i + 1) {
+                    Some(&"5") => {
+                        let index = codes.get(i + 2).copied().unwrap_or("0");
+                        i += 2;
+                        format!("{target} color {index}")
+                    }
+                    Some(&"2") => {
+                        let rgb: Vec<_> = codes.iter().skip(i + 2).take(3).copied().collect();
+                        i += 1 + rgb.len();
+                        format!("{target} rgb({})", rgb.join(","))
+                    }
+                    _ => target.to_string(),
+                }
+            }
+            59 => "default underline color".to_string(),
+            _ => code.to_string(),
+        };
+        parts.push(part);
+        i += 1;
+    }
+    parts.join(", ")
+}
+
+fn describe_osc(payload: &str) -> String {
+    let (command, rest) = payload.split_once(';').unwrap_or((payload, ""));
+    match command {
+        "0" => format!("set window title and icon name {rest:?}"),
+        "1" => format!("set icon name {rest:?}"),
+        "2" => format!("set window title {rest:?}"),
+        "7" => format!("report working directory {rest:?}"),
+        "8" => match rest.split_once(';') {
+            Some((_, "")) | None => "end hyperlink".to_string(),
+            Some((_, uri)) => format!("start hyperlink to {uri:?}"),
+        },
+        "10" | "11" | "12" => {
+            let target = match command {
+                "10" => "foreground",
+                "11" => "background",
+                _ => "cursor",
+            };
+            if rest == "?" {
+                format!("request {target} color")
+            } else {
+                format!("set {target} color {rest:?}")
+            }
+        }
+        "52" => match rest.split_once(';') {
+            Some((_, "?")) => "request clipboard".to_string(),
+            _ => "set clipboard".to_string(),
+        },
+        "133" => format!("shell integration mark {rest}"),
+        _ => format!("OSC {command}"),
+    }
+}
+
+fn describe_dcs(payload: &str) -> String {
+    if payload.starts_with("+q") {
+        "request terminfo capabilities (XTGETTCAP)".to_string()
+    } else if payload.starts_with("$q") {
+        "request setting (DECRQSS)".to_string()
+    } else {
+        "device control string".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_keeps_the_newest_bytes() {
+        let mut buffer = OutputBuffer::new(6);
+        buffer.push(b"abcd");
+        let mark = buffer.mark();
+        buffer.push(b"efgh");
+
+        assert_eq!(buffer.since(mark).bytes(), b"efgh");
+        let all = buffer.since(OutputMark::default());
+        assert!(all.truncated);
+        assert_eq!(all.bytes(), b"cdefgh");
+        assert_eq!(all.chunks[0].offset, 2);
+        assert_eq!(all.end, OutputMark(8));
+
+        buffer.push(b"0123456789");
+        assert_eq!(buffer.since(mark).bytes(), b"456789");
+    }
+
+    #[test]
+    fn searches_across_chunk_edges() {
+        let mut buffer = OutputBuffer::new(64);
+        buffer.push(b"ab");
+        let mark = buffer.mark();
+        for chunk in [&b"\x1b"[..], b"[", b"?10", b"49hx"] {
+            buffer.push(chunk);
+        }
+
+        assert!(buffer.contains_since(mark, b"\x1b[?1049h"));
+        assert!(buffer.contains_since(mark, b"hx"));
+        assert!(!buffer.contains_since(mark, b"b\x1b"));
+        assert!(!buffer.contains_since(mark, b"\x1b[?1049hxy"));
+        assert_eq!(buffer.bytes_since(mark), b"\x1b[?1049hx");
+    }
+
+    #[test]
+    fn annotates_text_and_escape_sequences() {
+        let listing = annotate(b"\x1b[?1049h\x1b[1;31mhi\x1b[0m\r\n\x1b]0;t\x07\x1b7");
+        assert_eq!(
+            listing,
+            "\\e[?1049h  set mode 1049 (alternate screen, saving cursor)\n\
+             \\e[1;31m  SGR bold, red foreground\n\
+             \"hi\"\n\
+             \\e[0m  SGR reset\n\
+             CR\n\
+             LF\n\
+             \\e]0;t\\a  set window title and icon name \"t\"\n\
+             \\e7  save cursor (DECSC)\n"
+        );
+    }
+}
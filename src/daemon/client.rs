@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
@@ -9,10 +9,13 @@ use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
 use tokio::sync::Mutex;
 
+use crate::capture::{CapturedOutput, OutputChunk, OutputMark};
 use crate::daemon::protocol::*;
 use crate::error::{Result, TermwrightError};
-use crate::input::MouseButton;
-use crate::screen::Screen;
+use crate::input::{MouseButton, ScrollDirection};
+use crate::screen::{HyperlinkSpan, Position, Screen, TextMatch};
+use crate::shell::CommandOutput;
+use crate::terminal::{ClipboardSelection, ExitStatus};
 
 pub struct DaemonClient {
     next_id: AtomicU64,
@@ -45,6 +48,10 @@ impl DaemonClient {
         self.call("handshake", serde_json::Value::Null).await
     }
 
+    pub async fn status(&self) -> Result<StatusResult> {
+        self.call("status", serde_json::Value::Null).await
+    }
+
     pub async fn screen_text(&self) -> Result<String> {
         self.call(
             "screen",
@@ -55,17 +62,232 @@ impl DaemonClient {
         .await
     }
 
+    pub async fn screen(&self) -> Result<Screen> {
+        self.call(
+            "screen",
+            ScreenParams {
+                format: ScreenFormat::Json,
+            },
+        )
+        .await
+    }
+
     pub async fn screen_json(&self) -> Result<String> {
-        let screen: Screen = self
+        let screen = self.screen().await?;
+        screen.to_json().map_err(TermwrightError::Json)
+    }
+
+    pub async fn scrollback_text(&self) -> Result<String> {
+        self.call(
+            "scrollback",
+            ScreenParams {
+                format: ScreenFormat::Text,
+            },
+        )
+        .await
+    }
+
+    pub async fn find_text(
+        &self,
+        text: impl Into<String>,
+        scrollback: bool,
+    ) -> Result<Vec<TextMatch>> {
+        self.call(
+            "find_text",
+            FindTextParams {
+                text: text.into(),
+                scrollback,
+            },
+        )
+        .await
+    }
+
+    /// Get the text printed inside OSC 8 hyperlinks.
+    pub async fn hyperlinks(&self, scrollback: bool) -> Result<Vec<HyperlinkSpan>> {
+        self.call("hyperlinks", HyperlinksParams { scrollback })
+            .await
+    }
+
+    /// Wait for `text` to appear inside a hyperlink to `uri`.
+    pub async fn wait_for_link(
+        &self,
+        text: impl Into<String>,
+        uri: impl Into<String>,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        self.call::<_, serde_json::Value>(
+            "wait_for_link",
+            WaitForLinkParams {
+                text: text.into(),
+                uri: uri.into(),
+                timeout_ms: timeout.map(|d| d.as_millis() as u64),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Get the window title and icon name.
+    pub async fn title(&self) -> Result<TitleResult> {
+        self.call("title", serde_json::Value::Null).await
+    }
+
+    /// Get the number of bells received so far.
+    pub async fn bell_count(&self) -> Result<usize> {
+        let res: BellCountResult = self.call("bell_count", serde_json::Value::Null).await?;
+        Ok(res.count)
+    }
+
+    /// Get a mark at the end of the output written so far.
+    pub async fn output_mark(&self) -> Result<OutputMark> {
+        let res: OutputMarkResult = self.call("output_mark", serde_json::Value::Null).await?;
+        Ok(res.mark)
+    }
+
+    /// Get the raw output written after `since`, or everything still
+    /// buffered.
+    pub async fn output_read(&self, since: Option<OutputMark>) -> Result<CapturedOutput> {
+        let res: OutputReadResult = self.call("output_read", OutputReadParams { since }).await?;
+        let chunks = res
+            .chunks
+            .into_iter()
+            .map(|chunk| {
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(chunk.bytes_base64)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+                Ok(OutputChunk {
+                    offset: chunk.offset,
+                    elapsed: Duration::from_millis(chunk.elapsed_ms),
+                    bytes,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(CapturedOutput {
+            chunks,
+            truncated: res.truncated,
+            end: res.mark,
+        })
+    }
+
+    /// Wait for the title to equal `title`.
+    pub async fn wait_for_title(
+        &self,
+        title: impl Into<String>,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        self.call::<_, serde_json::Value>(
+            "wait_for_title",
+            WaitForTitleParams {
+                title: Some(title.into()),
+                pattern: None,
+                timeout_ms: timeout.map(|d| d.as_millis() as u64),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Wait for the title to match a regex pattern.
+    pub async fn wait_for_title_pattern(
+        &self,
+        pattern: impl Into<String>,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        self.call::<_, serde_json::Value>(
+            "wait_for_title",
+            WaitForTitleParams {
+                title: None,
+                pattern: Some(pattern.into()),
+                timeout_ms: timeout.map(|d| d.as_millis() as u64),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Wait for the bell count to exceed `since`, returning the new count.
+    ///
+    /// With `since` unset the daemon uses the count when the request arrives.
+    pub async fn wait_for_bell(
+        &self,
+        since: Option<usize>,
+        timeout: Option<Duration>,
+    ) -> Result<usize> {
+        let res: BellCountResult = self
             .call(
-                "screen",
-                ScreenParams {
-                    format: ScreenFormat::Json,
+                "wait_for_bell",
+                WaitForBellParams {
+                    since,
+                    timeout_ms: timeout.map(|d| d.as_millis() as u64),
                 },
             )
             .await?;
+        Ok(res.count)
+    }
 
-        screen.to_json().map_err(TermwrightError::Json)
+    /// Get the contents of a clipboard selection.
+    pub async fn clipboard(&self, selection: ClipboardSelection) -> Result<Option<String>> {
+        let res: ClipboardResult = self
+            .call(
+                "clipboard_get",
+                ClipboardGetParams {
+                    selection: Some(selection.to_string()),
+                },
+            )
+            .await?;
+        Ok(res.text)
+    }
+
+    /// Seed a clipboard selection for applications that read it.
+    pub async fn set_clipboard(
+        &self,
+        selection: ClipboardSelection,
+        text: impl Into<String>,
+    ) -> Result<()> {
+        self.call::<_, serde_json::Value>(
+            "clipboard_set",
+            ClipboardSetParams {
+                text: text.into(),
+                selection: Some(selection.to_string()),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Wait for a clipboard selection to contain `text`.
+    pub async fn wait_for_clipboard(
+        &self,
+        selection: ClipboardSelection,
+        text: impl Into<String>,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        self.call::<_, serde_json::Value>(
+            "wait_for_clipboard",
+            WaitForClipboardParams {
+                text: text.into(),
+                selection: Some(selection.to_string()),
+                timeout_ms: timeout.map(|d| d.as_millis() as u64),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Run a command in a shell with OSC 133 prompt integration.
+    pub async fn shell_run(
+        &self,
+        command: impl Into<String>,
+        timeout: Option<Duration>,
+    ) -> Result<CommandOutput> {
+        self.call(
+            "shell_run",
+            ShellRunParams {
+                command: command.into(),
+                timeout_ms: timeout.map(|d| d.as_millis() as u64),
+            },
+        )
+        .await
     }
 
     pub async fn screenshot_png(&self) -> Result<Vec<u8>> {
@@ -91,6 +313,13 @@ impl DaemonClient {
         Ok(())
     }
 
+    /// Paste text, bracketed if the application enabled bracketed paste.
+    pub async fn paste(&self, text: impl Into<String>) -> Result<()> {
+        self.call::<_, serde_json::Value>("paste", PasteParams { text: text.into() })
+            .await?;
+        Ok(())
+    }
+
     pub async fn press(&self, key: impl Into<String>) -> Result<()> {
         self.call::<_, serde_json::Value>("press", PressParams { key: key.into() })
             .await?;
@@ -130,6 +359,7 @@ impl DaemonClient {
                 row,
                 col,
                 button: Some(button.to_string()),
+                modifiers: None,
             },
         )
         .await?;
@@ -143,6 +373,76 @@ impl DaemonClient {
                 row,
                 col,
                 buttons: None,
+                modifiers: None,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn mouse_down(&self, row: u16, col: u16, button: MouseButton) -> Result<()> {
+        self.call::<_, serde_json::Value>(
+            "mouse_down",
+            MouseDownParams {
+                row,
+                col,
+                button: Some(button.to_string()),
+                modifiers: None,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn mouse_up(&self, row: u16, col: u16) -> Result<()> {
+        self.call::<_, serde_json::Value>(
+            "mouse_up",
+            MouseUpParams {
+                row,
+                col,
+                modifiers: None,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn mouse_scroll(
+        &self,
+        row: u16,
+        col: u16,
+        direction: ScrollDirection,
+        amount: u16,
+    ) -> Result<()> {
+        self.call::<_, serde_json::Value>(
+            "mouse_scroll",
+            MouseScrollParams {
+                row,
+                col,
+                direction: direction.to_string(),
+                amount: Some(amount),
+                modifiers: None,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn mouse_drag(
+        &self,
+        from: Position,
+        to: Position,
+        button: MouseButton,
+        steps: u16,
+    ) -> Result<()> {
+        self.call::<_, serde_json::Value>(
+            "mouse_drag",
+            MouseDragParams {
+                from,
+                to,
+                button: Some(button.to_string()),
+                steps: Some(steps),
+                modifiers: None,
             },
         )
         .await?;
@@ -159,6 +459,24 @@ impl DaemonClient {
             WaitForTextParams {
                 text: text.into(),
                 timeout_ms: timeout.map(|d| d.as_millis() as u64),
+                scrollback: false,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn wait_for_text_in_scrollback(
+        &self,
+        text: impl Into<String>,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        self.call::<_, serde_json::Value>(
+            "wait_for_text",
+            WaitForTextParams {
+                text: text.into(),
+                timeout_ms: timeout.map(|d| d.as_millis() as u64),
+                scrollback: true,
             },
         )
         .await?;
@@ -175,6 +493,24 @@ impl DaemonClient {
             WaitForPatternParams {
                 pattern: pattern.into(),
                 timeout_ms: timeout.map(|d| d.as_millis() as u64),
+                scrollback: false,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn wait_for_pattern_in_scrollback(
+        &self,
+        pattern: impl Into<String>,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        self.call::<_, serde_json::Value>(
+            "wait_for_pattern",
+            WaitForPatternParams {
+                pattern: pattern.into(),
+                timeout_ms: timeout.map(|d| d.as_millis() as u64),
+                scrollback: true,
             },
         )
         .await?;
@@ -225,6 +561,18 @@ impl DaemonClient {
         Ok(())
     }
 
+    pub async fn wait_for_exit(&self, timeout: Option<Duration>) -> Result<ExitStatus> {
+        let res: WaitForExitResult = self
+            .call(
+                "wait_for_exit",
+                WaitForExitParams {
+                    timeout_ms: timeout.map(|d| d.as_millis() as u64),
+                },
+            )
+            .await?;
+        Ok(res.into())
+    }
+
     pub async fn not_expect_text(&self, text: impl Into<String>) -> Result<()> {
         self.call::<_, serde_json::Value>(
             "not_expect_text",
@@ -245,6 +593,26 @@ impl DaemonClient {
         Ok(())
     }
 
+    /// Start recording the session as asciicast v2 to `path`, a file on the
+    /// daemon's side. Input is recorded too when `input` is true.
+    pub async fn record_start(&self, path: impl AsRef<Path>, input: bool) -> Result<()> {
+        self.call::<_, serde_json::Value>(
+            "record_start",
+            RecordStartParams {
+                path: path.as_ref().to_path_buf(),
+                input,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stop recording, returning the finished file if one was in progress.
+    pub async fn record_stop(&self) -> Result<Option<PathBuf>> {
+        let res: RecordStopResult = self.call("record_stop", serde_json::Value::Null).await?;
+        Ok(res.path)
+    }
+
     pub async fn close(&self) -> Result<()> {
         let _ = self
             .call::<_, serde_json::Value>("close", serde_json::Value::Null)
@@ -300,6 +668,19 @@ impl DaemonClient {
         }
 
         if let Some(err) = response.error {
+            // Rebuild timeouts so callers get the same error as in-process waits.
+            if err.code == "timeout" {
+                if let Some(data) = err
+                    .data
+                    .and_then(|data| serde_json::from_value::<TimeoutErrorData>(data).ok())
+                {
+                    return Err(TermwrightError::Timeout {
+                        condition: data.condition,
+                        timeout: Duration::from_millis(data.timeout_ms),
+                        context: data.context.map(Box::new),
+                    });
+                }
+            }
             return Err(TermwrightError::Protocol(format!(
                 "{}: {}",
                 err.code, err.message
@@ -1,5 +1,12 @@
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
 
+use crate::capture::OutputMark;
+use crate::screen::Position;
+use crate::terminal::ExitStatus;
+use crate::wait::TimeoutContext;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Request {
     pub id: u64,
@@ -43,18 +50,36 @@ impl Response {
     }
 
     pub fn err(id: u64, code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::err_with_data(id, code, message, None)
+    }
+
+    pub fn err_with_data(
+        id: u64,
+        code: impl Into<String>,
+        message: impl Into<String>,
+        data: Option<serde_json::Value>,
+    ) -> Self {
         Self {
             id,
             result: serde_json::Value::Null,
             error: Some(ResponseError {
                 code: code.into(),
                 message: message.into(),
-                data: None,
+                data,
             }),
         }
     }
 }
 
+/// Structured `data` for errors with code `timeout`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeoutErrorData {
+    pub condition: String,
+    pub timeout_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<TimeoutContext>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HandshakeResult {
     pub protocol_version: u32,
@@ -68,6 +93,8 @@ pub enum ScreenFormat {
     Text,
     Json,
     JsonCompact,
+    Svg,
+    Html,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -99,6 +126,11 @@ pub struct TypeParams {
     pub text: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasteParams {
+    pub text: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PressParams {
     pub key: String,
@@ -121,6 +153,8 @@ pub struct MouseMoveParams {
     pub row: u16,
     pub col: u16,
     pub buttons: Option<Vec<String>>,
+    #[serde(default)]
+    pub modifiers: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -128,18 +162,173 @@ pub struct MouseClickParams {
     pub row: u16,
     pub col: u16,
     pub button: Option<String>,
+    #[serde(default)]
+    pub modifiers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MouseDownParams {
+    pub row: u16,
+    pub col: u16,
+    pub button: Option<String>,
+    #[serde(default)]
+    pub modifiers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MouseUpParams {
+    pub row: u16,
+    pub col: u16,
+    #[serde(default)]
+    pub modifiers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MouseScrollParams {
+    pub row: u16,
+    pub col: u16,
+    pub direction: String,
+    /// Number of wheel notches (default: 1).
+    pub amount: Option<u16>,
+    #[serde(default)]
+    pub modifiers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MouseDragParams {
+    pub from: Position,
+    pub to: Position,
+    pub button: Option<String>,
+    /// Number of motion events between the two cells (default: 5).
+    pub steps: Option<u16>,
+    #[serde(default)]
+    pub modifiers: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WaitForTextParams {
     pub text: String,
     pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub scrollback: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WaitForPatternParams {
     pub pattern: String,
     pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub scrollback: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FindTextParams {
+    pub text: String,
+    #[serde(default)]
+    pub scrollback: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HyperlinksParams {
+    #[serde(default)]
+    pub scrollback: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WaitForLinkParams {
+    /// Text that must appear inside the link.
+    pub text: String,
+    pub uri: String,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TitleResult {
+    pub title: String,
+    pub icon_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BellCountResult {
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutputMarkResult {
+    pub mark: OutputMark,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OutputReadParams {
+    /// Mark to read from; everything still buffered if omitted.
+    #[serde(default)]
+    pub since: Option<OutputMark>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutputChunkResult {
+    pub offset: u64,
+    /// Milliseconds since the terminal was spawned.
+    pub elapsed_ms: u64,
+    pub bytes_base64: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutputReadResult {
+    pub chunks: Vec<OutputChunkResult>,
+    /// Part of the requested output has been dropped from the buffer.
+    pub truncated: bool,
+    /// Mark at the end of the output, to pass as `since` next time.
+    pub mark: OutputMark,
+    /// The output listed with escape sequences described.
+    pub annotated: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WaitForTitleParams {
+    /// Exact title to wait for.
+    pub title: Option<String>,
+    /// Regex the title must match.
+    pub pattern: Option<String>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WaitForBellParams {
+    /// Bell count to exceed (default: the count when the request arrives).
+    pub since: Option<usize>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClipboardGetParams {
+    /// `clipboard` (default), `primary` or `secondary`.
+    #[serde(default)]
+    pub selection: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClipboardResult {
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClipboardSetParams {
+    pub text: String,
+    pub selection: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WaitForClipboardParams {
+    pub text: String,
+    pub selection: Option<String>,
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShellRunParams {
+    pub command: String,
+    pub timeout_ms: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -177,7 +366,30 @@ pub struct NotExpectPatternParams {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct WaitForExitResult {
-    pub exit_code: i32,
+    pub exit_code: Option<i32>,
+    pub signal: Option<String>,
+    #[serde(default)]
+    pub core_dumped: bool,
+}
+
+impl From<ExitStatus> for WaitForExitResult {
+    fn from(status: ExitStatus) -> Self {
+        Self {
+            exit_code: status.code,
+            signal: status.signal,
+            core_dumped: status.core_dumped,
+        }
+    }
+}
+
+impl From<WaitForExitResult> for ExitStatus {
+    fn from(result: WaitForExitResult) -> Self {
+        Self {
+            code: result.exit_code,
+            signal: result.signal,
+            core_dumped: result.core_dumped,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -186,8 +398,29 @@ pub struct ResizeParams {
     pub rows: u16,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordStartParams {
+    /// Asciicast file to write, relative to the daemon's working directory.
+    pub path: PathBuf,
+    /// Record input sent to the application as well.
+    #[serde(default)]
+    pub input: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordStopResult {
+    /// The finished recording, if one was in progress.
+    pub path: Option<PathBuf>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StatusResult {
     pub exited: bool,
     pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub signal: Option<String>,
+    #[serde(default)]
+    pub core_dumped: bool,
+    #[serde(default)]
+    pub frames: u64,
 }
@@ -7,10 +7,15 @@ use tokio::net::{UnixListener, UnixStream};
 
 use crate::daemon::protocol::*;
 use crate::error::{Result, TermwrightError};
-use crate::input::{Key, MouseButton};
-use crate::terminal::Terminal;
+use crate::input::{
+    Key, Modifiers, MouseAction, MouseButton, MouseEvent, ScrollDirection, drag_events,
+};
+use crate::terminal::{ClipboardSelection, Terminal};
+use crate::wait::{TimeoutContext, WaitCondition};
 
-const PROTOCOL_VERSION: u32 = 1;
+const PROTOCOL_VERSION: u32 = 2;
+/// Motion events sent by `mouse_drag` when no step count is given.
+const DEFAULT_DRAG_STEPS: u16 = 5;
 
 /// Result from serving a client connection.
 enum ClientResult {
@@ -145,7 +150,7 @@ async fn write_response(
 async fn handle_request(terminal: &Terminal, req: Request) -> Response {
     let id = req.id;
 
-    let result: Result<Response> = (|| async {
+    let result: Result<Response> = async {
         match req.method.as_str() {
             "handshake" => {
                 let value = HandshakeResult {
@@ -156,13 +161,19 @@ async fn handle_request(terminal: &Terminal, req: Request) -> Response {
                 Ok(Response::ok(id, value)?)
             }
             "status" => {
-                let exited = terminal.has_exited().await;
-                let exit_code = if exited {
-                    Some(terminal.wait_exit().await.unwrap_or(-1))
-                } else {
-                    None
-                };
-                Ok(Response::ok(id, StatusResult { exited, exit_code })?)
+                let status = terminal.exit_status().await;
+                let exited = status.is_some();
+                let status = status.unwrap_or_default();
+                Ok(Response::ok(
+                    id,
+                    StatusResult {
+                        exited,
+                        exit_code: status.code,
+                        signal: status.signal,
+                        core_dumped: status.core_dumped,
+                        frames: terminal.frame_count(),
+                    },
+                )?)
             }
             "screen" => {
                 let params: ScreenParams = serde_json::from_value(req.params)
@@ -174,8 +185,102 @@ async fn handle_request(terminal: &Terminal, req: Request) -> Response {
                     ScreenFormat::Text => Ok(Response::ok(id, screen.text())?),
                     ScreenFormat::Json => Ok(Response::ok(id, screen)?),
                     ScreenFormat::JsonCompact => Ok(Response::ok(id, screen.to_json_compact()?)?),
+                    ScreenFormat::Svg => Ok(Response::ok(id, screen.to_svg())?),
+                    ScreenFormat::Html => Ok(Response::ok(id, screen.to_html())?),
+                }
+            }
+            "scrollback" => {
+                let params: ScreenParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+
+                let screen = terminal.scrollback().await;
+
+                match params.format {
+                    ScreenFormat::Text => Ok(Response::ok(id, screen.text())?),
+                    ScreenFormat::Json => Ok(Response::ok(id, screen)?),
+                    ScreenFormat::JsonCompact => Ok(Response::ok(id, screen.to_json_compact()?)?),
+                    ScreenFormat::Svg => Ok(Response::ok(id, screen.to_svg())?),
+                    ScreenFormat::Html => Ok(Response::ok(id, screen.to_html())?),
                 }
             }
+            "find_text" => {
+                let params: FindTextParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+
+                let screen = if params.scrollback {
+                    terminal.scrollback().await
+                } else {
+                    terminal.screen().await
+                };
+                Ok(Response::ok(id, screen.find_text(&params.text))?)
+            }
+            "hyperlinks" => {
+                let params: HyperlinksParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+
+                let screen = if params.scrollback {
+                    terminal.scrollback().await
+                } else {
+                    terminal.screen().await
+                };
+                Ok(Response::ok(id, screen.hyperlinks())?)
+            }
+            "title" => {
+                let screen = terminal.screen().await;
+                Ok(Response::ok(
+                    id,
+                    TitleResult {
+                        title: screen.title,
+                        icon_name: screen.icon_name,
+                    },
+                )?)
+            }
+            "bell_count" => {
+                let count = terminal.bell_count().await;
+                Ok(Response::ok(id, BellCountResult { count })?)
+            }
+            "output_mark" => {
+                let mark = terminal.output_mark();
+                Ok(Response::ok(id, OutputMarkResult { mark })?)
+            }
+            "output_read" => {
+                let params: OutputReadParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+                let output = terminal.output_since(params.since.unwrap_or_default());
+                let annotated = output.annotated();
+                let chunks = output
+                    .chunks
+                    .into_iter()
+                    .map(|chunk| OutputChunkResult {
+                        offset: chunk.offset,
+                        elapsed_ms: chunk.elapsed.as_millis() as u64,
+                        bytes_base64: base64::engine::general_purpose::STANDARD.encode(chunk.bytes),
+                    })
+                    .collect();
+                Ok(Response::ok(
+                    id,
+                    OutputReadResult {
+                        chunks,
+                        truncated: output.truncated,
+                        mark: output.end,
+                        annotated,
+                    },
+                )?)
+            }
+            "clipboard_get" => {
+                let params: ClipboardGetParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+                let selection = parse_selection(params.selection.as_deref())?;
+                let text = terminal.clipboard(selection);
+                Ok(Response::ok(id, ClipboardResult { text })?)
+            }
+            "clipboard_set" => {
+                let params: ClipboardSetParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+                let selection = parse_selection(params.selection.as_deref())?;
+                terminal.set_clipboard(selection, &params.text);
+                Ok(Response::ok_empty(id))
+            }
             "screenshot" => {
                 let params: ScreenshotParams = serde_json::from_value(req.params)
                     .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
@@ -198,6 +303,12 @@ async fn handle_request(terminal: &Terminal, req: Request) -> Response {
                 terminal.type_str(&params.text).await?;
                 Ok(Response::ok_empty(id))
             }
+            "paste" => {
+                let params: PasteParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+                terminal.paste(&params.text).await?;
+                Ok(Response::ok_empty(id))
+            }
             "press" => {
                 let params: PressParams = serde_json::from_value(req.params)
                     .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
@@ -233,21 +344,69 @@ async fn handle_request(terminal: &Terminal, req: Request) -> Response {
                     .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
 
                 let held = parse_mouse_buttons(params.buttons.as_deref())?;
-                terminal.mouse_move(params.row, params.col, held).await?;
+                let modifiers = parse_modifiers(params.modifiers.as_deref())?;
+                let action = MouseAction::Motion(held.first().copied());
+                let event = MouseEvent::new(action, params.row, params.col);
+                terminal.send_mouse(event.with_modifiers(modifiers)).await?;
                 Ok(Response::ok_empty(id))
             }
             "mouse_click" => {
                 let params: MouseClickParams = serde_json::from_value(req.params)
                     .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
 
-                let button = params
-                    .button
-                    .as_deref()
-                    .unwrap_or("left")
-                    .parse::<MouseButton>()
+                let button = parse_mouse_button(params.button.as_deref())?;
+                let modifiers = parse_modifiers(params.modifiers.as_deref())?;
+                for action in [MouseAction::Press(button), MouseAction::Release(None)] {
+                    let event = MouseEvent::new(action, params.row, params.col);
+                    terminal.send_mouse(event.with_modifiers(modifiers)).await?;
+                }
+                Ok(Response::ok_empty(id))
+            }
+            "mouse_down" => {
+                let params: MouseDownParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+
+                let button = parse_mouse_button(params.button.as_deref())?;
+                let modifiers = parse_modifiers(params.modifiers.as_deref())?;
+                let event = MouseEvent::new(MouseAction::Press(button), params.row, params.col);
+                terminal.send_mouse(event.with_modifiers(modifiers)).await?;
+                Ok(Response::ok_empty(id))
+            }
+            "mouse_up" => {
+                let params: MouseUpParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+
+                let modifiers = parse_modifiers(params.modifiers.as_deref())?;
+                let event = MouseEvent::new(MouseAction::Release(None), params.row, params.col);
+                terminal.send_mouse(event.with_modifiers(modifiers)).await?;
+                Ok(Response::ok_empty(id))
+            }
+            "mouse_scroll" => {
+                let params: MouseScrollParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+
+                let direction = params
+                    .direction
+                    .parse::<ScrollDirection>()
+                    .map_err(TermwrightError::Protocol)?;
+                let modifiers = parse_modifiers(params.modifiers.as_deref())?;
+                for _ in 0..params.amount.unwrap_or(1) {
+                    let action = MouseAction::Wheel(direction);
+                    let event = MouseEvent::new(action, params.row, params.col);
+                    terminal.send_mouse(event.with_modifiers(modifiers)).await?;
+                }
+                Ok(Response::ok_empty(id))
+            }
+            "mouse_drag" => {
+                let params: MouseDragParams = serde_json::from_value(req.params)
                     .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
 
-                terminal.mouse_click(params.row, params.col, button).await?;
+                let button = parse_mouse_button(params.button.as_deref())?;
+                let modifiers = parse_modifiers(params.modifiers.as_deref())?;
+                let steps = params.steps.unwrap_or(DEFAULT_DRAG_STEPS);
+                for event in drag_events(params.from, params.to, button, steps) {
+                    terminal.send_mouse(event.with_modifiers(modifiers)).await?;
+                }
                 Ok(Response::ok_empty(id))
             }
             "wait_for_text" => {
@@ -255,6 +414,9 @@ async fn handle_request(terminal: &Terminal, req: Request) -> Response {
                     .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
 
                 let mut waiter = terminal.expect(&params.text);
+                if params.scrollback {
+                    waiter = waiter.include_scrollback();
+                }
                 if let Some(timeout_ms) = params.timeout_ms {
                     waiter = waiter.timeout(Duration::from_millis(timeout_ms));
                 }
@@ -266,12 +428,92 @@ async fn handle_request(terminal: &Terminal, req: Request) -> Response {
                     .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
 
                 let mut waiter = terminal.expect_pattern(&params.pattern);
+                if params.scrollback {
+                    waiter = waiter.include_scrollback();
+                }
+                if let Some(timeout_ms) = params.timeout_ms {
+                    waiter = waiter.timeout(Duration::from_millis(timeout_ms));
+                }
+                waiter.await?;
+                Ok(Response::ok_empty(id))
+            }
+            "wait_for_title" => {
+                let params: WaitForTitleParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+
+                let condition = match (params.title, params.pattern) {
+                    (Some(title), None) => WaitCondition::TitleEquals(title),
+                    (None, Some(pattern)) => {
+                        regex::Regex::new(&pattern).map_err(|e| {
+                            TermwrightError::Protocol(format!("invalid regex: {}", e))
+                        })?;
+                        WaitCondition::TitleMatches(pattern)
+                    }
+                    _ => {
+                        return Err(TermwrightError::Protocol(
+                            "wait_for_title needs exactly one of title or pattern".to_string(),
+                        ));
+                    }
+                };
+                let mut waiter = terminal.wait_for(condition);
+                if let Some(timeout_ms) = params.timeout_ms {
+                    waiter = waiter.timeout(Duration::from_millis(timeout_ms));
+                }
+                waiter.await?;
+                Ok(Response::ok_empty(id))
+            }
+            "wait_for_bell" => {
+                let params: WaitForBellParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+
+                let since = match params.since {
+                    Some(since) => since,
+                    None => terminal.bell_count().await,
+                };
+                let mut waiter = terminal.wait_for(WaitCondition::BellRung(since));
+                if let Some(timeout_ms) = params.timeout_ms {
+                    waiter = waiter.timeout(Duration::from_millis(timeout_ms));
+                }
+                waiter.await?;
+                let count = terminal.bell_count().await;
+                Ok(Response::ok(id, BellCountResult { count })?)
+            }
+            "wait_for_link" => {
+                let params: WaitForLinkParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+
+                let condition = WaitCondition::TextLinked(params.text, params.uri);
+                let mut waiter = terminal.wait_for(condition);
+                if let Some(timeout_ms) = params.timeout_ms {
+                    waiter = waiter.timeout(Duration::from_millis(timeout_ms));
+                }
+                waiter.await?;
+                Ok(Response::ok_empty(id))
+            }
+            "wait_for_clipboard" => {
+                let params: WaitForClipboardParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+
+                let selection = parse_selection(params.selection.as_deref())?;
+                let condition = WaitCondition::ClipboardContains(selection, params.text);
+                let mut waiter = terminal.wait_for(condition);
                 if let Some(timeout_ms) = params.timeout_ms {
                     waiter = waiter.timeout(Duration::from_millis(timeout_ms));
                 }
                 waiter.await?;
                 Ok(Response::ok_empty(id))
             }
+            "shell_run" => {
+                let params: ShellRunParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+
+                let mut shell = terminal.shell();
+                if let Some(timeout_ms) = params.timeout_ms {
+                    shell = shell.timeout(Duration::from_millis(timeout_ms));
+                }
+                let output = shell.run_command(&params.command).await?;
+                Ok(Response::ok(id, output)?)
+            }
             "wait_for_idle" => {
                 let params: WaitForIdleParams = serde_json::from_value(req.params)
                     .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
@@ -337,18 +579,24 @@ async fn handle_request(terminal: &Terminal, req: Request) -> Response {
                 let params: WaitForExitParams = serde_json::from_value(req.params)
                     .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
 
-                let exit_code = if let Some(timeout_ms) = params.timeout_ms {
-                    tokio::time::timeout(Duration::from_millis(timeout_ms), terminal.wait_exit())
-                        .await
-                        .map_err(|_| TermwrightError::Timeout {
-                            condition: "process to exit".to_string(),
-                            timeout: Duration::from_millis(timeout_ms),
-                        })??
+                let status = if let Some(timeout_ms) = params.timeout_ms {
+                    let timeout = Duration::from_millis(timeout_ms);
+                    match tokio::time::timeout(timeout, terminal.wait_exit()).await {
+                        Ok(status) => status?,
+                        Err(_) => {
+                            let screen = terminal.screen().await;
+                            return Err(TermwrightError::Timeout {
+                                condition: "process to exit".to_string(),
+                                timeout,
+                                context: Some(Box::new(TimeoutContext::new(screen, &[]))),
+                            });
+                        }
+                    }
                 } else {
                     terminal.wait_exit().await?
                 };
 
-                Ok(Response::ok(id, WaitForExitResult { exit_code })?)
+                Ok(Response::ok(id, WaitForExitResult::from(status))?)
             }
             "resize" => {
                 let params: ResizeParams = serde_json::from_value(req.params)
@@ -356,6 +604,16 @@ async fn handle_request(terminal: &Terminal, req: Request) -> Response {
                 terminal.resize(params.cols, params.rows).await?;
                 Ok(Response::ok_empty(id))
             }
+            "record_start" => {
+                let params: RecordStartParams = serde_json::from_value(req.params)
+                    .map_err(|e| TermwrightError::Protocol(e.to_string()))?;
+                terminal.record_start(&params.path, params.input).await?;
+                Ok(Response::ok_empty(id))
+            }
+            "record_stop" => {
+                let path = terminal.record_stop()?;
+                Ok(Response::ok(id, RecordStopResult { path })?)
+            }
             "close" => {
                 let _ = terminal.kill().await;
                 Ok(Response::err(id, "closing", "closing"))
@@ -366,51 +624,61 @@ async fn handle_request(terminal: &Terminal, req: Request) -> Response {
                 format!("unknown method: {other}"),
             )),
         }
-    })()
+    }
     .await;
 
     match result {
         Ok(r) => r,
-        Err(e) => Response::err(id, "error", e.to_string()),
+        Err(e) => error_response(id, e),
     }
 }
 
-fn parse_key(input: &str) -> Result<Key> {
-    let normalized = input.trim().to_lowercase();
-
-    let key = match normalized.as_str() {
-        "enter" => Key::Enter,
-        "tab" => Key::Tab,
-        "escape" | "esc" => Key::Escape,
-        "backspace" => Key::Backspace,
-        "delete" | "del" => Key::Delete,
-        "up" => Key::Up,
-        "down" => Key::Down,
-        "left" => Key::Left,
-        "right" => Key::Right,
-        "home" => Key::Home,
-        "end" => Key::End,
-        "pageup" | "page_up" => Key::PageUp,
-        "pagedown" | "page_down" => Key::PageDown,
-        _ if normalized.starts_with('f') => {
-            let n: u8 = normalized[1..]
-                .parse()
-                .map_err(|_| TermwrightError::Protocol(format!("invalid key: {input}")))?;
-            Key::F(n)
-        }
-        _ => {
-            let mut chars = input.chars();
-            let ch = chars
-                .next()
-                .ok_or_else(|| TermwrightError::Protocol("empty key".to_string()))?;
-            if chars.next().is_some() {
-                return Err(TermwrightError::Protocol(format!("invalid key: {input}")));
-            }
-            Key::Char(ch)
+fn error_response(id: u64, err: TermwrightError) -> Response {
+    match err {
+        TermwrightError::Timeout {
+            condition,
+            timeout,
+            context,
+        } => {
+            let message = format!("Timeout after {timeout:?} waiting for: {condition}");
+            let data = TimeoutErrorData {
+                condition,
+                timeout_ms: timeout.as_millis() as u64,
+                context: context.map(|context| *context),
+            };
+            Response::err_with_data(id, "timeout", message, serde_json::to_value(data).ok())
         }
-    };
+        other => Response::err(id, "error", other.to_string()),
+    }
+}
+
+fn parse_key(input: &str) -> Result<Key> {
+    input.parse().map_err(TermwrightError::Protocol)
+}
+
+fn parse_mouse_button(button: Option<&str>) -> Result<MouseButton> {
+    button
+        .unwrap_or("left")
+        .parse::<MouseButton>()
+        .map_err(TermwrightError::Protocol)
+}
 
-    Ok(key)
+fn parse_selection(selection: Option<&str>) -> Result<ClipboardSelection> {
+    selection
+        .unwrap_or("clipboard")
+        .parse::<ClipboardSelection>()
+        .map_err(TermwrightError::Protocol)
+}
+
+fn parse_modifiers(modifiers: Option<&[String]>) -> Result<Modifiers> {
+    modifiers
+        .unwrap_or_default()
+        .iter()
+        .try_fold(Modifiers::NONE, |acc, name| {
+            Modifiers::from_name(name)
+                .map(|modifier| acc | modifier)
+                .ok_or_else(|| TermwrightError::Protocol(format!("unknown modifier: {name}")))
+        })
 }
 
 fn parse_mouse_buttons(buttons: Option<&[String]>) -> Result<Vec<MouseButton>> {
@@ -1,7 +1,10 @@
 //! Error types for termwright.
 
+use std::path::PathBuf;
 use std::time::Duration;
 
+use crate::wait::TimeoutContext;
+
 /// Result type alias using TermwrightError.
 pub type Result<T> = std::result::Result<T, TermwrightError>;
 
@@ -13,12 +16,14 @@ pub enum TermwrightError {
     Pty(#[from] std::io::Error),
 
     /// Timeout waiting for a condition.
-    #[error("Timeout after {timeout:?} waiting for: {condition}")]
+    #[error("Timeout after {timeout:?} waiting for: {condition}{}", render_context(.context))]
     Timeout {
         /// The condition that was being waited for.
         condition: String,
         /// How long we waited.
         timeout: Duration,
+        /// Screen state when the wait gave up, if it was captured.
+        context: Option<Box<TimeoutContext>>,
     },
 
     /// The spawned process exited unexpectedly.
@@ -70,4 +75,29 @@ pub enum TermwrightError {
     /// Protocol/serialization error (daemon).
     #[error("Protocol error: {0}")]
     Protocol(String),
+
+    /// A screen did not match its golden snapshot.
+    #[error(
+        "Snapshot {name} does not match; the new screen was saved to {} for review\n{diff}",
+        pending.display()
+    )]
+    SnapshotMismatch {
+        /// Name of the snapshot.
+        name: String,
+        /// The pending `.snap.new` file holding the new screen.
+        pending: PathBuf,
+        /// Side-by-side diff of the saved snapshot and the new screen.
+        diff: String,
+    },
+
+    /// Invalid snapshot name or snapshot file.
+    #[error("Snapshot error: {0}")]
+    Snapshot(String),
+}
+
+fn render_context(context: &Option<Box<TimeoutContext>>) -> String {
+    context
+        .as_ref()
+        .map(|context| format!("\n{context}"))
+        .unwrap_or_default()
 }
@@ -20,7 +20,7 @@ impl CapabilitiesInfo {
     pub fn new() -> Self {
         Self {
             termwright_version: env!("CARGO_PKG_VERSION"),
-            protocol_version: 1,
+            protocol_version: 2,
             features: vec![
                 FeatureInfo {
                     name: "screenshots",
@@ -62,6 +62,16 @@ impl CapabilitiesInfo {
                     description: "waitForTextGone/waitForPatternGone steps",
                     available: true,
                 },
+                FeatureInfo {
+                    name: "asciicast_recording",
+                    description: "Session recording as asciicast v2",
+                    available: true,
+                },
+                FeatureInfo {
+                    name: "golden_snapshots",
+                    description: "Screen assertions against reviewed snapshot files",
+                    available: true,
+                },
             ],
         }
     }
@@ -2,6 +2,8 @@
 
 use serde::Serialize;
 
+use crate::input::Modifiers;
+
 #[derive(Debug, Serialize)]
 pub struct KeyInfo {
     pub name: &'static str,
@@ -52,6 +54,8 @@ impl KeysOverview {
         out.push_str("  press: {key: \"Enter\"}\n");
         out.push_str("  press: {key: \"F1\"}\n");
         out.push_str("  press: {key: \"a\"}\n");
+        out.push_str("  press: {key: \"ctrl+shift+up\"}\n");
+        out.push_str("  press: {key: \"alt+enter\"}\n");
         out.push_str("  hotkey: {ctrl: true, ch: \"c\"}\n");
         out
     }
@@ -115,9 +119,14 @@ fn all_keys() -> Vec<KeyCategory> {
             keys: vec![
                 KeyInfo {
                     name: "Enter",
-                    aliases: vec![],
+                    aliases: vec!["return"],
                     description: "Enter/Return key",
                 },
+                KeyInfo {
+                    name: "Space",
+                    aliases: vec![],
+                    description: "Space bar (useful in chords like ctrl+space)",
+                },
                 KeyInfo {
                     name: "Tab",
                     aliases: vec![],
@@ -138,6 +147,11 @@ fn all_keys() -> Vec<KeyCategory> {
                     aliases: vec!["del"],
                     description: "Delete key",
                 },
+                KeyInfo {
+                    name: "Insert",
+                    aliases: vec!["ins"],
+                    description: "Insert key",
+                },
             ],
         },
         KeyCategory {
@@ -148,6 +162,26 @@ fn all_keys() -> Vec<KeyCategory> {
                 description: "Function keys (e.g., F1, F2, ... F12)",
             }],
         },
+        KeyCategory {
+            name: "Keypad",
+            keys: vec![
+                KeyInfo {
+                    name: "KP0-KP9",
+                    aliases: vec![],
+                    description: "Keypad digits (e.g., KP0, KP5)",
+                },
+                KeyInfo {
+                    name: "KP.",
+                    aliases: vec![],
+                    description: "Keypad operators: KP. KP+ KP- KP* KP/ KP=",
+                },
+                KeyInfo {
+                    name: "KPEnter",
+                    aliases: vec!["kp_enter"],
+                    description: "Keypad Enter",
+                },
+            ],
+        },
         KeyCategory {
             name: "Characters",
             keys: vec![KeyInfo {
@@ -156,5 +190,50 @@ fn all_keys() -> Vec<KeyCategory> {
                 description: "Any single character (a, A, 1, @, etc.)",
             }],
         },
+        KeyCategory {
+            name: "Modifiers",
+            keys: modifier_keys(),
+        },
     ]
 }
+
+/// Chord modifiers, taken from the names the key parser accepts.
+fn modifier_keys() -> Vec<KeyInfo> {
+    let mut keys: Vec<KeyInfo> = Vec::new();
+    let mut previous = None;
+    for (name, modifier) in Modifiers::NAMES {
+        match keys.last_mut() {
+            Some(key) if previous == Some(*modifier) => key.aliases.push(name),
+            _ => keys.push(KeyInfo {
+                name,
+                aliases: vec![],
+                description: "Combine with any key using '+' (e.g., ctrl+shift+up)",
+            }),
+        }
+        previous = Some(*modifier);
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Key;
+
+    #[test]
+    fn test_documented_keys_parse() {
+        for category in all_keys() {
+            for key in &category.keys {
+                let names = std::iter::once(key.name).chain(key.aliases.iter().copied());
+                for name in names.filter(|n| !n.contains('<') && !n.contains('-')) {
+                    let name = if category.name == "Modifiers" {
+                        format!("{name}+a")
+                    } else {
+                        name.to_string()
+                    };
+                    assert!(name.parse::<Key>().is_ok(), "{name} should parse");
+                }
+            }
+        }
+    }
+}
@@ -22,7 +22,7 @@ pub struct ProtocolsOverview {
 impl ProtocolsOverview {
     pub fn new() -> Self {
         Self {
-            protocol_version: 1,
+            protocol_version: 2,
             methods: all_methods(),
         }
     }
@@ -101,16 +101,16 @@ fn all_methods() -> Vec<MethodInfo> {
             params: "null",
             response: "{protocol_version, termwright_version, pid}",
             example_request: r#"{"id":1,"method":"handshake","params":null}"#,
-            example_response: r#"{"id":1,"result":{"protocol_version":1,"termwright_version":"0.1.0","pid":12345}}"#,
+            example_response: r#"{"id":1,"result":{"protocol_version":2,"termwright_version":"0.1.0","pid":12345}}"#,
         },
         MethodInfo {
             name: "status",
             category: "session",
             brief: "Check if process has exited",
             params: "null",
-            response: "{exited: bool, exit_code: number|null}",
+            response: "{exited: bool, exit_code: number|null, signal: string|null, core_dumped: bool, frames: number}",
             example_request: r#"{"id":1,"method":"status","params":null}"#,
-            example_response: r#"{"id":1,"result":{"exited":false,"exit_code":null}}"#,
+            example_response: r#"{"id":1,"result":{"exited":false,"exit_code":null,"signal":null,"core_dumped":false,"frames":0}}"#,
         },
         MethodInfo {
             name: "close",
@@ -126,11 +126,93 @@ fn all_methods() -> Vec<MethodInfo> {
             name: "screen",
             category: "screen",
             brief: "Get screen content",
-            params: r#"{format: "text"|"json"|"json_compact"}"#,
-            response: "string (text) or object (json)",
+            params: r#"{format: "text"|"json"|"json_compact"|"svg"|"html"}"#,
+            response: "string (text, svg, html) or object (json)",
             example_request: r#"{"id":1,"method":"screen","params":{"format":"text"}}"#,
             example_response: r#"{"id":1,"result":"Screen content here..."}"#,
         },
+        MethodInfo {
+            name: "scrollback",
+            category: "screen",
+            brief: "Get scrollback history plus the visible screen",
+            params: r#"{format: "text"|"json"|"json_compact"|"svg"|"html"}"#,
+            response: "string (text, svg, html) or object (json)",
+            example_request: r#"{"id":1,"method":"scrollback","params":{"format":"text"}}"#,
+            example_response: r#"{"id":1,"result":"Older lines...\nScreen content here..."}"#,
+        },
+        MethodInfo {
+            name: "find_text",
+            category: "screen",
+            brief: "Find all occurrences of text",
+            params: r#"{text: string, scrollback?: bool}"#,
+            response: "[{position: {row, col}, text, length}]",
+            example_request: r#"{"id":1,"method":"find_text","params":{"text":"error","scrollback":true}}"#,
+            example_response: r#"{"id":1,"result":[{"position":{"row":3,"col":0},"text":"error","length":5}]}"#,
+        },
+        MethodInfo {
+            name: "hyperlinks",
+            category: "screen",
+            brief: "List text printed inside OSC 8 hyperlinks",
+            params: r#"{scrollback?: bool}"#,
+            response: "[{uri, id?, text, region: {start: {row, col}, end: {row, col}}}]",
+            example_request: r#"{"id":1,"method":"hyperlinks","params":{}}"#,
+            example_response: r#"{"id":1,"result":[{"uri":"https://example.com","text":"docs","region":{"start":{"row":0,"col":4},"end":{"row":1,"col":8}}}]}"#,
+        },
+        MethodInfo {
+            name: "title",
+            category: "screen",
+            brief: "Get the window title and icon name",
+            params: "null",
+            response: "{title: string, icon_name: string}",
+            example_request: r#"{"id":1,"method":"title","params":null}"#,
+            example_response: r#"{"id":1,"result":{"title":"vim - main.rs","icon_name":"vim"}}"#,
+        },
+        MethodInfo {
+            name: "bell_count",
+            category: "screen",
+            brief: "Get the number of bells received so far",
+            params: "null",
+            response: "{count: number}",
+            example_request: r#"{"id":1,"method":"bell_count","params":null}"#,
+            example_response: r#"{"id":1,"result":{"count":0}}"#,
+        },
+        MethodInfo {
+            name: "output_mark",
+            category: "screen",
+            brief: "Get a mark at the end of the raw output so far",
+            params: "null",
+            response: "{mark: number}",
+            example_request: r#"{"id":1,"method":"output_mark","params":null}"#,
+            example_response: r#"{"id":1,"result":{"mark":1024}}"#,
+        },
+        MethodInfo {
+            name: "output_read",
+            category: "screen",
+            brief: "Read raw output bytes written after a mark",
+            params: r#"{since?: number}"#,
+            response: "{chunks: [{offset, elapsed_ms, bytes_base64}], truncated: bool, mark: number, annotated: string}",
+            example_request: r#"{"id":1,"method":"output_read","params":{"since":1024}}"#,
+            example_response: r#"{"id":1,"result":{"chunks":[{"offset":1024,"elapsed_ms":350,"bytes_base64":"G1s/MTA0OWw="}],"truncated":false,"mark":1032,"annotated":"\e[?1049l  reset mode 1049 (alternate screen, saving cursor)
+"}}"#,
+        },
+        MethodInfo {
+            name: "clipboard_get",
+            category: "screen",
+            brief: "Get a clipboard selection written with OSC 52",
+            params: r#"{selection?: "clipboard"|"primary"|"secondary"}"#,
+            response: "{text: string|null}",
+            example_request: r#"{"id":1,"method":"clipboard_get","params":{}}"#,
+            example_response: r#"{"id":1,"result":{"text":"hello"}}"#,
+        },
+        MethodInfo {
+            name: "clipboard_set",
+            category: "input",
+            brief: "Seed a clipboard selection for OSC 52 reads",
+            params: r#"{text: string, selection?: "clipboard"|"primary"|"secondary"}"#,
+            response: "null",
+            example_request: r#"{"id":1,"method":"clipboard_set","params":{"text":"hello"}}"#,
+            example_response: r#"{"id":1,"result":null}"#,
+        },
         MethodInfo {
             name: "screenshot",
             category: "screen",
@@ -150,11 +232,20 @@ fn all_methods() -> Vec<MethodInfo> {
             example_request: r#"{"id":1,"method":"type","params":{"text":"Hello"}}"#,
             example_response: r#"{"id":1,"result":null}"#,
         },
+        MethodInfo {
+            name: "paste",
+            category: "input",
+            brief: "Paste text, bracketed if the app enabled bracketed paste",
+            params: r#"{text: string}"#,
+            response: "null",
+            example_request: r#"{"id":1,"method":"paste","params":{"text":"line 1\nline 2"}}"#,
+            example_response: r#"{"id":1,"result":null}"#,
+        },
         MethodInfo {
             name: "press",
             category: "input",
             brief: "Press a single key",
-            params: r#"{key: string}"#,
+            params: r#"{key: string}  (name or chord, e.g. "ctrl+shift+up")"#,
             response: "null",
             example_request: r#"{"id":1,"method":"press","params":{"key":"Enter"}}"#,
             example_response: r#"{"id":1,"result":null}"#,
@@ -181,7 +272,7 @@ fn all_methods() -> Vec<MethodInfo> {
             name: "mouse_click",
             category: "input",
             brief: "Click mouse at position",
-            params: r#"{row: number, col: number, button?: "left"|"right"|"middle"}"#,
+            params: r#"{row: number, col: number, button?: "left"|"right"|"middle", modifiers?: string[]}"#,
             response: "null",
             example_request: r#"{"id":1,"method":"mouse_click","params":{"row":5,"col":10}}"#,
             example_response: r#"{"id":1,"result":null}"#,
@@ -190,17 +281,53 @@ fn all_methods() -> Vec<MethodInfo> {
             name: "mouse_move",
             category: "input",
             brief: "Move mouse cursor",
-            params: r#"{row: number, col: number, buttons?: string[]}"#,
+            params: r#"{row: number, col: number, buttons?: string[], modifiers?: string[]}"#,
             response: "null",
             example_request: r#"{"id":1,"method":"mouse_move","params":{"row":5,"col":10}}"#,
             example_response: r#"{"id":1,"result":null}"#,
         },
+        MethodInfo {
+            name: "mouse_down",
+            category: "input",
+            brief: "Press a mouse button at position",
+            params: r#"{row: number, col: number, button?: "left"|"right"|"middle", modifiers?: string[]}"#,
+            response: "null",
+            example_request: r#"{"id":1,"method":"mouse_down","params":{"row":5,"col":10}}"#,
+            example_response: r#"{"id":1,"result":null}"#,
+        },
+        MethodInfo {
+            name: "mouse_up",
+            category: "input",
+            brief: "Release the pressed mouse button at position",
+            params: r#"{row: number, col: number, modifiers?: string[]}"#,
+            response: "null",
+            example_request: r#"{"id":1,"method":"mouse_up","params":{"row":5,"col":10}}"#,
+            example_response: r#"{"id":1,"result":null}"#,
+        },
+        MethodInfo {
+            name: "mouse_scroll",
+            category: "input",
+            brief: "Scroll the mouse wheel at position",
+            params: r#"{row: number, col: number, direction: "up"|"down"|"left"|"right", amount?: number, modifiers?: string[]}"#,
+            response: "null",
+            example_request: r#"{"id":1,"method":"mouse_scroll","params":{"row":5,"col":10,"direction":"down","amount":3}}"#,
+            example_response: r#"{"id":1,"result":null}"#,
+        },
+        MethodInfo {
+            name: "mouse_drag",
+            category: "input",
+            brief: "Drag with a button held from one cell to another",
+            params: r#"{from: {row, col}, to: {row, col}, button?: string, steps?: number, modifiers?: string[]}"#,
+            response: "null",
+            example_request: r#"{"id":1,"method":"mouse_drag","params":{"from":{"row":2,"col":0},"to":{"row":2,"col":20}}}"#,
+            example_response: r#"{"id":1,"result":null}"#,
+        },
         // Wait
         MethodInfo {
             name: "wait_for_text",
             category: "wait",
             brief: "Wait for text to appear",
-            params: r#"{text: string, timeout_ms?: number}"#,
+            params: r#"{text: string, timeout_ms?: number, scrollback?: bool}"#,
             response: "null",
             example_request: r#"{"id":1,"method":"wait_for_text","params":{"text":"Ready","timeout_ms":5000}}"#,
             example_response: r#"{"id":1,"result":null}"#,
@@ -209,11 +336,47 @@ fn all_methods() -> Vec<MethodInfo> {
             name: "wait_for_pattern",
             category: "wait",
             brief: "Wait for regex pattern to match",
-            params: r#"{pattern: string, timeout_ms?: number}"#,
+            params: r#"{pattern: string, timeout_ms?: number, scrollback?: bool}"#,
             response: "null",
             example_request: r#"{"id":1,"method":"wait_for_pattern","params":{"pattern":"v\\d+","timeout_ms":5000}}"#,
             example_response: r#"{"id":1,"result":null}"#,
         },
+        MethodInfo {
+            name: "wait_for_title",
+            category: "wait",
+            brief: "Wait for the window title to equal text or match a pattern",
+            params: r#"{title?: string, pattern?: string, timeout_ms?: number}"#,
+            response: "null",
+            example_request: r#"{"id":1,"method":"wait_for_title","params":{"pattern":"Done$","timeout_ms":5000}}"#,
+            example_response: r#"{"id":1,"result":null}"#,
+        },
+        MethodInfo {
+            name: "wait_for_link",
+            category: "wait",
+            brief: "Wait for text to appear inside a hyperlink to a URI",
+            params: r#"{text: string, uri: string, timeout_ms?: number}"#,
+            response: "null",
+            example_request: r#"{"id":1,"method":"wait_for_link","params":{"text":"docs","uri":"https://example.com","timeout_ms":5000}}"#,
+            example_response: r#"{"id":1,"result":null}"#,
+        },
+        MethodInfo {
+            name: "wait_for_clipboard",
+            category: "wait",
+            brief: "Wait for a clipboard selection to contain text",
+            params: r#"{text: string, selection?: string, timeout_ms?: number}"#,
+            response: "null",
+            example_request: r#"{"id":1,"method":"wait_for_clipboard","params":{"text":"hello","timeout_ms":5000}}"#,
+            example_response: r#"{"id":1,"result":null}"#,
+        },
+        MethodInfo {
+            name: "wait_for_bell",
+            category: "wait",
+            brief: "Wait for the bell count to exceed a count",
+            params: r#"{since?: number, timeout_ms?: number}"#,
+            response: "{count: number}",
+            example_request: r#"{"id":1,"method":"wait_for_bell","params":{"since":0,"timeout_ms":5000}}"#,
+            example_response: r#"{"id":1,"result":{"count":1}}"#,
+        },
         MethodInfo {
             name: "wait_for_idle",
             category: "wait",
@@ -228,9 +391,9 @@ fn all_methods() -> Vec<MethodInfo> {
             category: "wait",
             brief: "Wait for process to exit",
             params: r#"{timeout_ms?: number}"#,
-            response: "{exit_code: number}",
+            response: "{exit_code: number|null, signal: string|null, core_dumped: bool}",
             example_request: r#"{"id":1,"method":"wait_for_exit","params":{"timeout_ms":5000}}"#,
-            example_response: r#"{"id":1,"result":{"exit_code":0}}"#,
+            example_response: r#"{"id":1,"result":{"exit_code":0,"signal":null,"core_dumped":false}}"#,
         },
         MethodInfo {
             name: "wait_for_text_gone",
@@ -270,6 +433,15 @@ fn all_methods() -> Vec<MethodInfo> {
             example_response: r#"{"id":1,"result":null}"#,
         },
         // Control
+        MethodInfo {
+            name: "shell_run",
+            category: "control",
+            brief: "Run a command in a shell with OSC 133 prompt marks",
+            params: r#"{command: string, timeout_ms?: number}"#,
+            response: "{output_text: string, exit_code: number|null, cwd: string|null}",
+            example_request: r#"{"id":1,"method":"shell_run","params":{"command":"ls","timeout_ms":5000}}"#,
+            example_response: r#"{"id":1,"result":{"output_text":"Cargo.toml\nsrc","exit_code":0,"cwd":"/repo"}}"#,
+        },
         MethodInfo {
             name: "resize",
             category: "control",
@@ -279,5 +451,23 @@ fn all_methods() -> Vec<MethodInfo> {
             example_request: r#"{"id":1,"method":"resize","params":{"cols":120,"rows":40}}"#,
             example_response: r#"{"id":1,"result":null}"#,
         },
+        MethodInfo {
+            name: "record_start",
+            category: "control",
+            brief: "Start recording the session as asciicast v2",
+            params: r#"{path: string, input?: bool}"#,
+            response: "null",
+            example_request: r#"{"id":1,"method":"record_start","params":{"path":"session.cast"}}"#,
+            example_response: r#"{"id":1,"result":null}"#,
+        },
+        MethodInfo {
+            name: "record_stop",
+            category: "control",
+            brief: "Stop recording and flush the file",
+            params: "null",
+            response: "{path: string|null}",
+            example_request: r#"{"id":1,"method":"record_stop","params":null}"#,
+            example_response: r#"{"id":1,"result":{"path":"session.cast"}}"#,
+        },
     ]
 }
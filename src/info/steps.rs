@@ -126,6 +126,13 @@ fn all_steps() -> Vec<StepInfo> {
                     default: Some("30000"),
                     description: "Timeout in milliseconds",
                 },
+                ParamInfo {
+                    name: "scrollback",
+                    required: false,
+                    r#type: "bool",
+                    default: Some("false"),
+                    description: "Also search lines scrolled off the top of the screen",
+                },
             ],
             example: r#"waitForText: {text: "Ready", timeoutMs: 5000}"#,
             tips: vec!["Use exact text that appears on screen"],
@@ -149,6 +156,13 @@ fn all_steps() -> Vec<StepInfo> {
                     default: Some("30000"),
                     description: "Timeout in milliseconds",
                 },
+                ParamInfo {
+                    name: "scrollback",
+                    required: false,
+                    r#type: "bool",
+                    default: Some("false"),
+                    description: "Also search lines scrolled off the top of the screen",
+                },
             ],
             example: r#"waitForPattern: {pattern: "v\\d+\\.\\d+", timeoutMs: 5000}"#,
             tips: vec!["Escape backslashes in YAML strings"],
@@ -235,7 +249,7 @@ fn all_steps() -> Vec<StepInfo> {
                 required: true,
                 r#type: "string",
                 default: None,
-                description: "Key name (Enter, Tab, Up, Down, F1, etc.)",
+                description: "Key name or chord (Enter, F1, a, ctrl+shift+up, etc.)",
             }],
             example: r#"press: {key: "Enter"}"#,
             tips: vec![
@@ -257,6 +271,46 @@ fn all_steps() -> Vec<StepInfo> {
             example: r#"type: {text: "Hello, World!"}"#,
             tips: vec!["Does not press Enter at the end; use a separate press step"],
         },
+        StepInfo {
+            name: "paste",
+            category: "input",
+            brief: "Paste text as a terminal emulator would",
+            params: vec![ParamInfo {
+                name: "text",
+                required: true,
+                r#type: "string",
+                default: None,
+                description: "Text to paste",
+            }],
+            example: r#"paste: {text: "line 1\nline 2"}"#,
+            tips: vec![
+                "Wrapped in ESC[200~ ... ESC[201~ when the app enabled bracketed paste",
+                "Falls back to typing the text when bracketed paste is off",
+            ],
+        },
+        StepInfo {
+            name: "setClipboard",
+            category: "input",
+            brief: "Seed the clipboard the app reads with OSC 52",
+            params: vec![
+                ParamInfo {
+                    name: "text",
+                    required: true,
+                    r#type: "string",
+                    default: None,
+                    description: "Clipboard contents",
+                },
+                ParamInfo {
+                    name: "selection",
+                    required: false,
+                    r#type: "string",
+                    default: Some("clipboard"),
+                    description: "clipboard, primary or secondary",
+                },
+            ],
+            example: r#"setClipboard: {text: "copied elsewhere"}"#,
+            tips: vec!["Requires OSC emulation (on unless noOscEmulation is set)"],
+        },
         StepInfo {
             name: "hotkey",
             category: "input",
@@ -287,6 +341,46 @@ fn all_steps() -> Vec<StepInfo> {
             example: r#"hotkey: {ctrl: true, ch: "c"}"#,
             tips: vec!["Use for Ctrl+C, Ctrl+S, Alt+F, etc."],
         },
+        StepInfo {
+            name: "shellRun",
+            category: "input",
+            brief: "Run a shell command and wait for it to finish (OSC 133)",
+            params: vec![
+                ParamInfo {
+                    name: "command",
+                    required: true,
+                    r#type: "string",
+                    default: None,
+                    description: "Command line to type at the prompt",
+                },
+                ParamInfo {
+                    name: "exitCode",
+                    required: false,
+                    r#type: "number",
+                    default: None,
+                    description: "Exit code the command must report",
+                },
+                ParamInfo {
+                    name: "output",
+                    required: false,
+                    r#type: "string",
+                    default: None,
+                    description: "Text the command's output must contain",
+                },
+                ParamInfo {
+                    name: "timeoutMs",
+                    required: false,
+                    r#type: "number",
+                    default: Some("30000"),
+                    description: "Timeout for the prompt and for the command",
+                },
+            ],
+            example: r#"shellRun: {command: "make test", exitCode: 0}"#,
+            tips: vec![
+                "The shell must emit OSC 133 A/C/D marks from its prompt hooks",
+                "Waits on the D mark, so no idle waits are needed",
+            ],
+        },
         // Assert steps
         StepInfo {
             name: "expectText",
@@ -307,10 +401,133 @@ fn all_steps() -> Vec<StepInfo> {
                     default: Some("30000"),
                     description: "Timeout to wait for text",
                 },
+                ParamInfo {
+                    name: "scrollback",
+                    required: false,
+                    r#type: "bool",
+                    default: Some("false"),
+                    description: "Also search lines scrolled off the top of the screen",
+                },
             ],
             example: r#"expectText: {text: "Success"}"#,
             tips: vec!["Use waitForIdle before for more reliable assertions"],
         },
+        StepInfo {
+            name: "expectTitle",
+            category: "assert",
+            brief: "Assert the window title (with optional wait)",
+            params: vec![
+                ParamInfo {
+                    name: "text",
+                    required: false,
+                    r#type: "string",
+                    default: None,
+                    description: "Exact title expected",
+                },
+                ParamInfo {
+                    name: "pattern",
+                    required: false,
+                    r#type: "string",
+                    default: None,
+                    description: "Regex the title must match",
+                },
+                ParamInfo {
+                    name: "timeoutMs",
+                    required: false,
+                    r#type: "number",
+                    default: Some("30000"),
+                    description: "Timeout to wait for the title",
+                },
+            ],
+            example: r#"expectTitle: {pattern: "Building \d+%"}"#,
+            tips: vec![
+                "Give exactly one of text or pattern",
+                "The title is set by OSC 0 or OSC 2 sequences",
+            ],
+        },
+        StepInfo {
+            name: "expectClipboard",
+            category: "assert",
+            brief: "Assert the app copied text to the clipboard with OSC 52",
+            params: vec![
+                ParamInfo {
+                    name: "text",
+                    required: true,
+                    r#type: "string",
+                    default: None,
+                    description: "Text the clipboard must contain",
+                },
+                ParamInfo {
+                    name: "selection",
+                    required: false,
+                    r#type: "string",
+                    default: Some("clipboard"),
+                    description: "clipboard, primary or secondary",
+                },
+                ParamInfo {
+                    name: "timeoutMs",
+                    required: false,
+                    r#type: "number",
+                    default: Some("30000"),
+                    description: "Timeout to wait for the clipboard",
+                },
+            ],
+            example: r#"expectClipboard: {text: "hello world"}"#,
+            tips: vec!["Requires OSC emulation (on unless noOscEmulation is set)"],
+        },
+        StepInfo {
+            name: "expectLink",
+            category: "assert",
+            brief: "Assert text is printed inside an OSC 8 hyperlink",
+            params: vec![
+                ParamInfo {
+                    name: "text",
+                    required: true,
+                    r#type: "string",
+                    default: None,
+                    description: "Text that must appear inside the link",
+                },
+                ParamInfo {
+                    name: "uri",
+                    required: true,
+                    r#type: "string",
+                    default: None,
+                    description: "URI the text must link to",
+                },
+                ParamInfo {
+                    name: "timeoutMs",
+                    required: false,
+                    r#type: "number",
+                    default: Some("30000"),
+                    description: "Timeout to wait for the link",
+                },
+            ],
+            example: r#"expectLink: {text: "src/main.rs", uri: "file:///repo/src/main.rs"}"#,
+            tips: vec!["A link wrapped across rows matches per row"],
+        },
+        StepInfo {
+            name: "expectBell",
+            category: "assert",
+            brief: "Assert the bell has rung (with optional wait)",
+            params: vec![
+                ParamInfo {
+                    name: "count",
+                    required: false,
+                    r#type: "number",
+                    default: Some("1"),
+                    description: "Total bells expected since the session started",
+                },
+                ParamInfo {
+                    name: "timeoutMs",
+                    required: false,
+                    r#type: "number",
+                    default: Some("30000"),
+                    description: "Timeout to wait for the bell",
+                },
+            ],
+            example: r#"expectBell: {count: 2}"#,
+            tips: vec!["Counts audible (BEL) and visual (ESC g) bells"],
+        },
         StepInfo {
             name: "expectPattern",
             category: "assert",
@@ -330,6 +547,13 @@ fn all_steps() -> Vec<StepInfo> {
                     default: Some("30000"),
                     description: "Timeout to wait for pattern",
                 },
+                ParamInfo {
+                    name: "scrollback",
+                    required: false,
+                    r#type: "bool",
+                    default: Some("false"),
+                    description: "Also search lines scrolled off the top of the screen",
+                },
             ],
             example: r#"expectPattern: {pattern: "Items:\\s*\\d+"}"#,
             tips: vec!["Escape backslashes in YAML"],
@@ -368,6 +592,39 @@ fn all_steps() -> Vec<StepInfo> {
                 "Useful for checking no errors after an action",
             ],
         },
+        StepInfo {
+            name: "expectExit",
+            category: "assert",
+            brief: "Wait for the process to exit and check its status",
+            params: vec![
+                ParamInfo {
+                    name: "code",
+                    required: false,
+                    r#type: "number",
+                    default: None,
+                    description: "Expected exit code",
+                },
+                ParamInfo {
+                    name: "signal",
+                    required: false,
+                    r#type: "string",
+                    default: None,
+                    description: "Expected terminating signal (e.g. SIGSEGV)",
+                },
+                ParamInfo {
+                    name: "timeoutMs",
+                    required: false,
+                    r#type: "number",
+                    default: Some("30000"),
+                    description: "Timeout to wait for exit",
+                },
+            ],
+            example: r#"expectExit: {code: 2}"#,
+            tips: vec![
+                "Omit code and signal to only wait for the process to exit",
+                "Signal names may omit the SIG prefix (SEGV, TERM)",
+            ],
+        },
         // Capture steps
         StepInfo {
             name: "screenshot",
@@ -386,5 +643,32 @@ fn all_steps() -> Vec<StepInfo> {
                 "Saved to the artifacts directory",
             ],
         },
+        StepInfo {
+            name: "snapshot",
+            category: "capture",
+            brief: "Compare the screen with a golden snapshot",
+            params: vec![
+                ParamInfo {
+                    name: "name",
+                    required: true,
+                    r#type: "string",
+                    default: None,
+                    description: "Snapshot name; stored as snapshots/<name>.snap next to the steps file",
+                },
+                ParamInfo {
+                    name: "styles",
+                    required: false,
+                    r#type: "bool",
+                    default: Some("false"),
+                    description: "Also compare colors, attributes and the cursor position",
+                },
+            ],
+            example: r#"snapshot: {name: "main-menu", styles: true}"#,
+            tips: vec![
+                "A mismatch writes <name>.snap.new and fails the step",
+                "Review pending snapshots with `termwright snapshot review`",
+                "Set TERMWRIGHT_UPDATE=1 to accept differences as they are found",
+            ],
+        },
     ]
 }
@@ -1,5 +1,10 @@
 //! Key definitions and escape sequence generation.
 
+use std::str::FromStr;
+
+use super::Modifiers;
+use crate::screen::TerminalModes;
+
 /// Represents a keyboard key or key combination.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Key {
@@ -15,6 +20,8 @@ pub enum Key {
     Backspace,
     /// Delete key.
     Delete,
+    /// Insert key.
+    Insert,
     /// Up arrow.
     Up,
     /// Down arrow.
@@ -37,11 +44,84 @@ pub enum Key {
     Ctrl(char),
     /// Alt + character.
     Alt(char),
+    /// Numeric keypad key: a digit or one of `. + - * / =`.
+    Keypad(char),
+    /// Numeric keypad Enter key.
+    KeypadEnter,
+    /// A key pressed with a set of modifiers; see [`Key::with_modifiers`].
+    Modified(Box<Key>, Modifiers),
 }
 
+/// Named keys accepted by [`Key::from_str`], with their aliases.
+const KEY_NAMES: &[(&[&str], Key)] = &[
+    (&["enter", "return"], Key::Enter),
+    (&["tab"], Key::Tab),
+    (&["escape", "esc"], Key::Escape),
+    (&["backspace"], Key::Backspace),
+    (&["delete", "del"], Key::Delete),
+    (&["insert", "ins"], Key::Insert),
+    (&["space"], Key::Char(' ')),
+    (&["up"], Key::Up),
+    (&["down"], Key::Down),
+    (&["left"], Key::Left),
+    (&["right"], Key::Right),
+    (&["home"], Key::Home),
+    (&["end"], Key::End),
+    (&["pageup", "page_up"], Key::PageUp),
+    (&["pagedown", "page_down"], Key::PageDown),
+    (&["kpenter", "kp_enter"], Key::KeypadEnter),
+];
+
+/// Kitty keyboard flag: disambiguate escape codes.
+const KITTY_DISAMBIGUATE: u8 = 1;
+/// Kitty keyboard flag: report all keys as escape codes.
+const KITTY_REPORT_ALL_KEYS: u8 = 8;
+
 impl Key {
+    /// Press this key with the given modifiers held.
+    ///
+    /// Modifiers are encoded the way xterm does: `CSI 1;<mod>X` for cursor
+    /// keys and F1-F4, `CSI n;<mod>~` for editing and other function keys,
+    /// control characters for Ctrl with a character, and an ESC prefix for
+    /// Alt/Meta where no other encoding exists.
+    pub fn with_modifiers(self, modifiers: Modifiers) -> Key {
+        match self {
+            Key::Modified(key, existing) => Key::Modified(key, existing | modifiers),
+            key if modifiers.is_empty() => key,
+            key => Key::Modified(Box::new(key), modifiers),
+        }
+    }
+
     /// Convert the key to its escape sequence bytes.
+    ///
+    /// Uses the encoding of a terminal in its default modes. Use
+    /// [`Key::to_escape_sequence_with_modes`] to honour the modes an
+    /// application has enabled.
     pub fn to_escape_sequence(&self) -> Vec<u8> {
+        self.to_escape_sequence_with_modes(&TerminalModes::default())
+    }
+
+    /// Convert the key to escape sequence bytes the way xterm would in the
+    /// given modes.
+    ///
+    /// With application cursor mode (DECCKM) the arrow, Home and End keys
+    /// send SS3 sequences (`ESC O A`), and with application keypad mode
+    /// (DECKPAM) the keypad keys send SS3 sequences instead of characters.
+    ///
+    /// When the application enabled the kitty keyboard protocol or xterm's
+    /// modifyOtherKeys, keys that are ambiguous in the legacy encoding are
+    /// sent as `CSI code;mods u` or `CSI 27;mods;code ~`, so Ctrl+I can be
+    /// told apart from Tab.
+    pub fn to_escape_sequence_with_modes(&self, modes: &TerminalModes) -> Vec<u8> {
+        if let Some(bytes) = self.encode_enhanced(modes) {
+            return bytes;
+        }
+        if modes.application_cursor {
+            if let Some(code) = self.cursor_key_code() {
+                return vec![0x1b, b'O', code];
+            }
+        }
+
         match self {
             Key::Char(c) => {
                 let mut buf = [0u8; 4];
@@ -51,39 +131,19 @@ impl Key {
             Key::Tab => vec![b'\t'],
             Key::Escape => vec![0x1b],
             Key::Backspace => vec![0x7f],
-            Key::Delete => vec![0x1b, b'[', b'3', b'~'],
-            Key::Up => vec![0x1b, b'[', b'A'],
-            Key::Down => vec![0x1b, b'[', b'B'],
-            Key::Right => vec![0x1b, b'[', b'C'],
-            Key::Left => vec![0x1b, b'[', b'D'],
-            Key::Home => vec![0x1b, b'[', b'H'],
-            Key::End => vec![0x1b, b'[', b'F'],
-            Key::PageUp => vec![0x1b, b'[', b'5', b'~'],
-            Key::PageDown => vec![0x1b, b'[', b'6', b'~'],
-            Key::F(n) => match n {
-                1 => vec![0x1b, b'O', b'P'],
-                2 => vec![0x1b, b'O', b'Q'],
-                3 => vec![0x1b, b'O', b'R'],
-                4 => vec![0x1b, b'O', b'S'],
-                5 => vec![0x1b, b'[', b'1', b'5', b'~'],
-                6 => vec![0x1b, b'[', b'1', b'7', b'~'],
-                7 => vec![0x1b, b'[', b'1', b'8', b'~'],
-                8 => vec![0x1b, b'[', b'1', b'9', b'~'],
-                9 => vec![0x1b, b'[', b'2', b'0', b'~'],
-                10 => vec![0x1b, b'[', b'2', b'1', b'~'],
-                11 => vec![0x1b, b'[', b'2', b'3', b'~'],
-                12 => vec![0x1b, b'[', b'2', b'4', b'~'],
+            Key::Delete | Key::Insert | Key::PageUp | Key::PageDown => {
+                format!("\x1b[{}~", self.tilde_code().unwrap_or_default()).into_bytes()
+            }
+            Key::Up | Key::Down | Key::Right | Key::Left | Key::Home | Key::End => {
+                let code = self.cursor_key_code().unwrap_or_default();
+                vec![0x1b, b'[', code]
+            }
+            Key::F(_) => match (self.ss3_function_code(), self.tilde_code()) {
+                (Some(code), _) => vec![0x1b, b'O', code],
+                (None, Some(code)) => format!("\x1b[{code}~").into_bytes(),
                 _ => vec![],
             },
-            Key::Ctrl(c) => {
-                // Ctrl+A = 0x01, Ctrl+B = 0x02, etc.
-                let c = c.to_ascii_lowercase();
-                if c.is_ascii_lowercase() {
-                    vec![(c as u8) - b'a' + 1]
-                } else {
-                    vec![]
-                }
-            }
+            Key::Ctrl(c) => ctrl_byte(*c).map(|b| vec![b]).unwrap_or_default(),
             Key::Alt(c) => {
                 // Alt is ESC followed by the character
                 let mut seq = vec![0x1b];
@@ -91,7 +151,251 @@ impl Key {
                 seq.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
                 seq
             }
+            Key::Keypad(c) => match keypad_code(*c) {
+                Some(code) if modes.application_keypad => vec![0x1b, b'O', code],
+                Some(_) => vec![*c as u8],
+                None => vec![],
+            },
+            Key::KeypadEnter if modes.application_keypad => vec![0x1b, b'O', b'M'],
+            Key::KeypadEnter => vec![b'\r'],
+            Key::Modified(key, modifiers) => key.encode_modified(*modifiers, modes),
+        }
+    }
+
+    /// Encode this key with modifiers held.
+    fn encode_modified(&self, modifiers: Modifiers, modes: &TerminalModes) -> Vec<u8> {
+        if modifiers.is_empty() {
+            return self.to_escape_sequence_with_modes(modes);
+        }
+        let param = modifiers.xterm_param();
+        let esc_prefix = |mut bytes: Vec<u8>| {
+            if modifiers.escape_prefixed() {
+                bytes.insert(0, 0x1b);
+            }
+            bytes
+        };
+
+        if let Some(code) = self.cursor_key_code().or(self.ss3_function_code()) {
+            return format!("\x1b[1;{param}{}", code as char).into_bytes();
+        }
+        if let Some(code) = self.tilde_code() {
+            return format!("\x1b[{code};{param}~").into_bytes();
+        }
+
+        match self {
+            Key::Modified(key, inner) => key.encode_modified(modifiers | *inner, modes),
+            Key::Ctrl(c) => Key::Char(*c).encode_modified(modifiers | Modifiers::CTRL, modes),
+            Key::Alt(c) => Key::Char(*c).encode_modified(modifiers | Modifiers::ALT, modes),
+            Key::Char(c) => {
+                let c = if modifiers.contains(Modifiers::SHIFT) {
+                    c.to_ascii_uppercase()
+                } else {
+                    *c
+                };
+                let bytes = match ctrl_byte(c) {
+                    Some(b) if modifiers.contains(Modifiers::CTRL) => vec![b],
+                    _ => Key::Char(c).to_escape_sequence(),
+                };
+                esc_prefix(bytes)
+            }
+            Key::Tab if modifiers.contains(Modifiers::SHIFT) => esc_prefix(b"\x1b[Z".to_vec()),
+            Key::Backspace if modifiers.contains(Modifiers::CTRL) => esc_prefix(vec![0x08]),
+            key => esc_prefix(key.to_escape_sequence_with_modes(modes)),
+        }
+    }
+
+    /// Encode text and text-like keys with the kitty keyboard protocol or
+    /// modifyOtherKeys, if enabled and the key needs it.
+    fn encode_enhanced(&self, modes: &TerminalModes) -> Option<Vec<u8>> {
+        let flags = modes.kitty_keyboard_flags;
+        if flags == 0 && modes.modify_other_keys == 0 {
+            return None;
+        }
+        let (key, modifiers) = self.split_modifiers();
+        let (code, modifiers) = key.csi_u_code(modifiers)?;
+        let plain_text =
+            matches!(key, Key::Char(_)) && (modifiers.is_empty() || modifiers == Modifiers::SHIFT);
+
+        if flags != 0 {
+            let enhanced = flags & KITTY_REPORT_ALL_KEYS != 0
+                || (flags & KITTY_DISAMBIGUATE != 0
+                    && (key == Key::Escape || (!modifiers.is_empty() && !plain_text)));
+            return enhanced.then(|| {
+                if modifiers.is_empty() {
+                    format!("\x1b[{code}u").into_bytes()
+                } else {
+                    format!("\x1b[{code};{}u", modifiers.xterm_param()).into_bytes()
+                }
+            });
+        }
+
+        let enhanced = match modes.modify_other_keys {
+            0 => false,
+            // Level 1 only changes keys whose legacy encoding loses the modifier.
+            1 => match &key {
+                Key::Char(c) => modifiers.contains(Modifiers::CTRL) && ctrl_byte(*c).is_none(),
+                Key::Tab => !modifiers.is_empty() && modifiers != Modifiers::SHIFT,
+                _ => !modifiers.is_empty(),
+            },
+            _ => !modifiers.is_empty() && !plain_text,
+        };
+        enhanced.then(|| format!("\x1b[27;{};{code}~", modifiers.xterm_param()).into_bytes())
+    }
+
+    /// Separate a key into its base key and the modifiers held with it.
+    fn split_modifiers(&self) -> (Key, Modifiers) {
+        match self {
+            Key::Modified(key, modifiers) => {
+                let (key, inner) = key.split_modifiers();
+                (key, inner | *modifiers)
+            }
+            Key::Ctrl(c) => (Key::Char(*c), Modifiers::CTRL),
+            Key::Alt(c) => (Key::Char(*c), Modifiers::ALT),
+            key => (key.clone(), Modifiers::NONE),
+        }
+    }
+
+    /// Unicode key code used by `CSI u` style encodings, with Shift added for
+    /// uppercase letters since the code is always the unshifted key. With Ctrl
+    /// held the letter's case is ignored, as in the legacy control bytes.
+    fn csi_u_code(&self, modifiers: Modifiers) -> Option<(u32, Modifiers)> {
+        let code = match self {
+            Key::Char(c) if c.is_ascii_uppercase() => {
+                let shift = if modifiers.contains(Modifiers::CTRL) {
+                    Modifiers::NONE
+                } else {
+                    Modifiers::SHIFT
+                };
+                return Some((c.to_ascii_lowercase() as u32, modifiers | shift));
+            }
+            Key::Char(c) => *c as u32,
+            Key::Enter => 13,
+            Key::Tab => 9,
+            Key::Backspace => 127,
+            Key::Escape => 27,
+            _ => return None,
+        };
+        Some((code, modifiers))
+    }
+
+    /// Parameter of the `CSI n ~` sequence for editing and F5-F12 keys.
+    fn tilde_code(&self) -> Option<u8> {
+        match self {
+            Key::Insert => Some(2),
+            Key::Delete => Some(3),
+            Key::PageUp => Some(5),
+            Key::PageDown => Some(6),
+            Key::F(n @ 5) => Some(n + 10),
+            Key::F(n @ 6..=10) => Some(n + 11),
+            Key::F(n @ 11..=12) => Some(n + 12),
+            _ => None,
+        }
+    }
+
+    /// Final byte of the SS3 sequence for F1-F4 (`P` in `ESC O P`).
+    fn ss3_function_code(&self) -> Option<u8> {
+        match self {
+            Key::F(n @ 1..=4) => Some(b'P' + n - 1),
+            _ => None,
+        }
+    }
+
+    /// Final byte of the cursor key sequence (`A` in `ESC [ A`).
+    fn cursor_key_code(&self) -> Option<u8> {
+        match self {
+            Key::Up => Some(b'A'),
+            Key::Down => Some(b'B'),
+            Key::Right => Some(b'C'),
+            Key::Left => Some(b'D'),
+            Key::Home => Some(b'H'),
+            Key::End => Some(b'F'),
+            _ => None,
+        }
+    }
+}
+
+/// Control character sent for Ctrl with the given character, as xterm does.
+fn ctrl_byte(c: char) -> Option<u8> {
+    match c.to_ascii_lowercase() {
+        c @ 'a'..='z' => Some(c as u8 - b'a' + 1),
+        '@' | ' ' | '2' => Some(0x00),
+        '[' | '3' => Some(0x1b),
+        '\\' | '4' => Some(0x1c),
+        ']' | '5' => Some(0x1d),
+        '^' | '6' => Some(0x1e),
+        '_' | '/' | '7' => Some(0x1f),
+        '?' | '8' => Some(0x7f),
+        _ => None,
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    /// Parse a key name or chord such as `Enter`, `F5`, `a`, `KP5`,
+    /// `ctrl+shift+up` or `alt+enter`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let input = s.trim();
+        // A trailing "++" means the key itself is '+', e.g. "ctrl++".
+        let (prefix, name) = if let Some(prefix) = input.strip_suffix("++") {
+            (Some(prefix), "+")
+        } else {
+            match input.rsplit_once('+') {
+                Some((prefix, name)) if !name.is_empty() => (Some(prefix), name),
+                _ => (None, input),
+            }
+        };
+
+        let mut modifiers = Modifiers::NONE;
+        for part in prefix.into_iter().flat_map(|p| p.split('+')) {
+            modifiers |= Modifiers::from_name(part.trim())
+                .ok_or_else(|| format!("unknown modifier '{part}' in key: {s}"))?;
         }
+
+        let key = parse_key_name(name.trim()).ok_or_else(|| format!("invalid key: {s}"))?;
+        Ok(key.with_modifiers(modifiers))
+    }
+}
+
+/// Parse a single key name without modifiers.
+fn parse_key_name(name: &str) -> Option<Key> {
+    let normalized = name.to_lowercase();
+    if let Some((_, key)) = KEY_NAMES
+        .iter()
+        .find(|(names, _)| names.contains(&normalized.as_str()))
+    {
+        return Some(key.clone());
+    }
+
+    let mut chars = name.chars();
+    let first = chars.next()?;
+    if chars.next().is_none() {
+        return Some(Key::Char(first));
+    }
+
+    if let Some(rest) = normalized.strip_prefix("kp") {
+        let mut chars = rest.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) if keypad_code(c).is_some() => Some(Key::Keypad(c)),
+            _ => None,
+        };
+    }
+
+    let n: u8 = normalized.strip_prefix('f')?.parse().ok()?;
+    (1..=12).contains(&n).then_some(Key::F(n))
+}
+
+/// Final byte of the application keypad sequence for a keypad character.
+fn keypad_code(c: char) -> Option<u8> {
+    match c {
+        '0'..='9' => Some(b'p' + (c as u8 - b'0')),
+        '*' => Some(b'j'),
+        '+' => Some(b'k'),
+        '-' => Some(b'm'),
+        '.' => Some(b'n'),
+        '/' => Some(b'o'),
+        '=' => Some(b'X'),
+        _ => None,
     }
 }
 
@@ -120,6 +424,214 @@ mod tests {
         assert_eq!(Key::Left.to_escape_sequence(), vec![0x1b, b'[', b'D']);
     }
 
+    #[test]
+    fn test_application_cursor_mode() {
+        let modes = TerminalModes {
+            application_cursor: true,
+            ..Default::default()
+        };
+        assert_eq!(Key::Up.to_escape_sequence_with_modes(&modes), b"\x1bOA");
+        assert_eq!(Key::Left.to_escape_sequence_with_modes(&modes), b"\x1bOD");
+        assert_eq!(Key::Home.to_escape_sequence_with_modes(&modes), b"\x1bOH");
+        assert_eq!(Key::End.to_escape_sequence_with_modes(&modes), b"\x1bOF");
+        assert_eq!(
+            Key::PageUp.to_escape_sequence_with_modes(&modes),
+            b"\x1b[5~"
+        );
+        assert_eq!(Key::Home.to_escape_sequence(), b"\x1b[H");
+    }
+
+    #[test]
+    fn test_keypad_keys() {
+        let modes = TerminalModes {
+            application_keypad: true,
+            ..Default::default()
+        };
+        assert_eq!(Key::Keypad('5').to_escape_sequence(), b"5");
+        assert_eq!(
+            Key::Keypad('5').to_escape_sequence_with_modes(&modes),
+            b"\x1bOu"
+        );
+        assert_eq!(
+            Key::Keypad('+').to_escape_sequence_with_modes(&modes),
+            b"\x1bOk"
+        );
+        assert_eq!(Key::KeypadEnter.to_escape_sequence(), b"\r");
+        assert_eq!(
+            Key::KeypadEnter.to_escape_sequence_with_modes(&modes),
+            b"\x1bOM"
+        );
+        assert!(Key::Keypad('x').to_escape_sequence().is_empty());
+    }
+
+    #[test]
+    fn test_modified_cursor_and_function_keys() {
+        let ctrl_shift = Modifiers::CTRL | Modifiers::SHIFT;
+        assert_eq!(
+            Key::Up.with_modifiers(Modifiers::CTRL).to_escape_sequence(),
+            b"\x1b[1;5A"
+        );
+        assert_eq!(
+            Key::F(5).with_modifiers(ctrl_shift).to_escape_sequence(),
+            b"\x1b[15;6~"
+        );
+        assert_eq!(
+            Key::F(1)
+                .with_modifiers(Modifiers::SHIFT)
+                .to_escape_sequence(),
+            b"\x1b[1;2P"
+        );
+        assert_eq!(
+            Key::Delete
+                .with_modifiers(Modifiers::ALT)
+                .to_escape_sequence(),
+            b"\x1b[3;3~"
+        );
+        assert_eq!(Key::F(12).to_escape_sequence(), b"\x1b[24~");
+        assert_eq!(Key::Insert.to_escape_sequence(), b"\x1b[2~");
+    }
+
+    #[test]
+    fn test_modified_legacy_keys() {
+        assert_eq!(
+            Key::Tab
+                .with_modifiers(Modifiers::SHIFT)
+                .to_escape_sequence(),
+            b"\x1b[Z"
+        );
+        assert_eq!(
+            Key::Enter
+                .with_modifiers(Modifiers::ALT)
+                .to_escape_sequence(),
+            b"\x1b\r"
+        );
+        assert_eq!(
+            Key::Char(' ')
+                .with_modifiers(Modifiers::CTRL)
+                .to_escape_sequence(),
+            vec![0x00]
+        );
+        assert_eq!(
+            Key::Char('[')
+                .with_modifiers(Modifiers::CTRL)
+                .to_escape_sequence(),
+            vec![0x1b]
+        );
+        assert_eq!(
+            Key::Char('a')
+                .with_modifiers(Modifiers::SHIFT | Modifiers::ALT)
+                .to_escape_sequence(),
+            b"\x1bA"
+        );
+        assert_eq!(Key::Up.with_modifiers(Modifiers::NONE), Key::Up);
+    }
+
+    #[test]
+    fn test_kitty_keyboard_protocol() {
+        let modes = TerminalModes {
+            kitty_keyboard_flags: KITTY_DISAMBIGUATE,
+            ..Default::default()
+        };
+        let encode = |key: Key| key.to_escape_sequence_with_modes(&modes);
+        assert_eq!(encode(Key::Ctrl('i')), b"\x1b[105;5u");
+        assert_eq!(encode(Key::Tab), b"\t");
+        assert_eq!(encode(Key::Escape), b"\x1b[27u");
+        assert_eq!(
+            encode(Key::Tab.with_modifiers(Modifiers::SHIFT)),
+            b"\x1b[9;2u"
+        );
+        assert_eq!(encode(Key::Alt('a')), b"\x1b[97;3u");
+        assert_eq!(encode(Key::Char('A')), b"A");
+        // Ctrl ignores the letter's case, as the legacy control bytes do.
+        assert_eq!(encode(Key::Ctrl('A')), b"\x1b[97;5u");
+        assert_eq!(encode("ctrl+A".parse().unwrap()), b"\x1b[97;5u");
+        assert_eq!(encode("ctrl+shift+a".parse().unwrap()), b"\x1b[97;6u");
+        // Legacy Alt keeps the case, so an uppercase letter is Alt+Shift.
+        assert_eq!(encode(Key::Alt('A')), b"\x1b[97;4u");
+        // Meta is sent as kitty's Super.
+        assert_eq!(
+            encode(Key::Char('a').with_modifiers(Modifiers::META)),
+            b"\x1b[97;9u"
+        );
+        assert_eq!(
+            encode(Key::Up.with_modifiers(Modifiers::CTRL)),
+            b"\x1b[1;5A"
+        );
+
+        let modes = TerminalModes {
+            kitty_keyboard_flags: KITTY_DISAMBIGUATE | KITTY_REPORT_ALL_KEYS,
+            ..Default::default()
+        };
+        assert_eq!(
+            Key::Char('a').to_escape_sequence_with_modes(&modes),
+            b"\x1b[97u"
+        );
+        assert_eq!(
+            Key::Char('A').to_escape_sequence_with_modes(&modes),
+            b"\x1b[97;2u"
+        );
+        assert_eq!(
+            Key::Enter.to_escape_sequence_with_modes(&modes),
+            b"\x1b[13u"
+        );
+    }
+
+    #[test]
+    fn test_modify_other_keys() {
+        let level = |modify_other_keys| TerminalModes {
+            modify_other_keys,
+            ..Default::default()
+        };
+        assert_eq!(
+            Key::Ctrl('a').to_escape_sequence_with_modes(&level(2)),
+            b"\x1b[27;5;97~"
+        );
+        assert_eq!(
+            Key::Ctrl('i').to_escape_sequence_with_modes(&level(2)),
+            b"\x1b[27;5;105~"
+        );
+        assert_eq!(Key::Tab.to_escape_sequence_with_modes(&level(2)), b"\t");
+        assert_eq!(
+            Key::Char('A').to_escape_sequence_with_modes(&level(2)),
+            b"A"
+        );
+
+        assert_eq!(
+            Key::Ctrl('a').to_escape_sequence_with_modes(&level(1)),
+            vec![0x01]
+        );
+        assert_eq!(
+            Key::Enter
+                .with_modifiers(Modifiers::CTRL)
+                .to_escape_sequence_with_modes(&level(1)),
+            b"\x1b[27;5;13~"
+        );
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!("Enter".parse::<Key>(), Ok(Key::Enter));
+        assert_eq!("f5".parse::<Key>(), Ok(Key::F(5)));
+        assert_eq!("A".parse::<Key>(), Ok(Key::Char('A')));
+        assert_eq!("kp+".parse::<Key>(), Ok(Key::Keypad('+')));
+        assert_eq!(
+            "ctrl+shift+up".parse::<Key>(),
+            Ok(Key::Up.with_modifiers(Modifiers::CTRL | Modifiers::SHIFT))
+        );
+        assert_eq!(
+            "Alt+Enter".parse::<Key>(),
+            Ok(Key::Enter.with_modifiers(Modifiers::ALT))
+        );
+        assert_eq!(
+            "ctrl++".parse::<Key>(),
+            Ok(Key::Char('+').with_modifiers(Modifiers::CTRL))
+        );
+        assert_eq!("+".parse::<Key>(), Ok(Key::Char('+')));
+        assert!("hyper+a".parse::<Key>().is_err());
+        assert!("f13".parse::<Key>().is_err());
+        assert!("nope".parse::<Key>().is_err());
+    }
+
     #[test]
     fn test_ctrl_key() {
         assert_eq!(Key::Ctrl('c').to_escape_sequence(), vec![0x03]); // Ctrl+C
@@ -1,7 +1,10 @@
 //! Input handling for terminal applications.
 
 mod keys;
+mod modifiers;
 mod mouse;
 
 pub use keys::Key;
-pub use mouse::MouseButton;
+pub use modifiers::Modifiers;
+pub(crate) use mouse::{CELL_PIXEL_HEIGHT, CELL_PIXEL_WIDTH, drag_events};
+pub use mouse::{MouseAction, MouseButton, MouseEvent, ScrollDirection};
@@ -0,0 +1,79 @@
+//! Modifier keys held down with keys and mouse events.
+
+use std::ops::{BitOr, BitOrAssign};
+
+/// A set of modifier keys held down with another key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    /// No modifiers.
+    pub const NONE: Modifiers = Modifiers(0);
+    /// Shift key.
+    pub const SHIFT: Modifiers = Modifiers(1);
+    /// Alt (Option) key.
+    pub const ALT: Modifiers = Modifiers(2);
+    /// Ctrl key.
+    pub const CTRL: Modifiers = Modifiers(4);
+    /// Meta (Super/Command) key.
+    ///
+    /// Sent as xterm's Meta bit, which the kitty keyboard protocol reads as
+    /// Super, matching the `super` and `cmd` chord names. Kitty's separate
+    /// Meta modifier is never sent.
+    pub const META: Modifiers = Modifiers(8);
+
+    /// Names accepted in key chords such as `ctrl+shift+up`.
+    pub const NAMES: &'static [(&'static str, Modifiers)] = &[
+        ("ctrl", Modifiers::CTRL),
+        ("control", Modifiers::CTRL),
+        ("shift", Modifiers::SHIFT),
+        ("alt", Modifiers::ALT),
+        ("option", Modifiers::ALT),
+        ("meta", Modifiers::META),
+        ("super", Modifiers::META),
+        ("cmd", Modifiers::META),
+    ];
+
+    /// Returns true if no modifiers are set.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns true if all modifiers in `other` are set.
+    pub fn contains(self, other: Modifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns true if Alt or Meta is set, which legacy encodings send as an
+    /// ESC prefix.
+    pub(crate) fn escape_prefixed(self) -> bool {
+        self.0 & (Modifiers::ALT.0 | Modifiers::META.0) != 0
+    }
+
+    /// The xterm modifier parameter (`1 + bits`), as in `CSI 1;5A` for Ctrl+Up.
+    pub fn xterm_param(self) -> u8 {
+        1 + self.0
+    }
+
+    /// Look up a modifier by its chord name (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Modifiers> {
+        Modifiers::NAMES
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, m)| *m)
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Modifiers) -> Modifiers {
+        Modifiers(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Modifiers {
+    fn bitor_assign(&mut self, rhs: Modifiers) {
+        self.0 |= rhs.0;
+    }
+}
@@ -1,5 +1,21 @@
+//! Mouse events, gestures and their escape sequence encodings.
+
 use std::str::FromStr;
 
+use super::Modifiers;
+use crate::screen::{MouseEncoding, MouseMode, Position, TerminalModes};
+
+/// Width in pixels reported for one cell, used by SGR-pixels mouse reports
+/// and the PTY window size.
+pub(crate) const CELL_PIXEL_WIDTH: u16 = 8;
+/// Height in pixels reported for one cell.
+pub(crate) const CELL_PIXEL_HEIGHT: u16 = 16;
+
+/// Largest coordinate the single-byte mouse encoding can represent.
+const MAX_DEFAULT_COORD: u32 = 223;
+/// Largest coordinate the UTF-8 mouse encoding can represent.
+const MAX_UTF8_COORD: u32 = 2015;
+
 /// Mouse buttons understood by the daemon API.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MouseButton {
@@ -40,3 +56,320 @@ impl MouseButton {
         }
     }
 }
+
+/// Direction of a mouse wheel scroll.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl FromStr for ScrollDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "up" => Ok(ScrollDirection::Up),
+            "down" => Ok(ScrollDirection::Down),
+            "left" => Ok(ScrollDirection::Left),
+            "right" => Ok(ScrollDirection::Right),
+            other => Err(format!("unknown scroll direction: {other}")),
+        }
+    }
+}
+
+impl std::fmt::Display for ScrollDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScrollDirection::Up => write!(f, "up"),
+            ScrollDirection::Down => write!(f, "down"),
+            ScrollDirection::Left => write!(f, "left"),
+            ScrollDirection::Right => write!(f, "right"),
+        }
+    }
+}
+
+impl ScrollDirection {
+    fn wheel_code(self) -> u8 {
+        match self {
+            ScrollDirection::Up => 64,
+            ScrollDirection::Down => 65,
+            ScrollDirection::Left => 66,
+            ScrollDirection::Right => 67,
+        }
+    }
+}
+
+/// What happened in a mouse event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseAction {
+    /// A button was pressed.
+    Press(MouseButton),
+    /// A button was released; `None` if it is not known which one.
+    Release(Option<MouseButton>),
+    /// The mouse moved, optionally with a button held.
+    Motion(Option<MouseButton>),
+    /// The wheel was scrolled one notch.
+    Wheel(ScrollDirection),
+}
+
+/// A mouse event at a cell position, with modifier keys held.
+///
+/// Events are encoded for the mouse mode and encoding the application has
+/// enabled; see [`MouseEvent::encode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseEvent {
+    /// What happened.
+    pub action: MouseAction,
+    /// Cell the event happened at.
+    pub position: Position,
+    /// Modifier keys held during the event.
+    pub modifiers: Modifiers,
+}
+
+impl MouseEvent {
+    /// Create an event at the given cell with no modifiers.
+    pub fn new(action: MouseAction, row: u16, col: u16) -> Self {
+        Self {
+            action,
+            position: Position::new(row, col),
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    /// Hold the given modifier keys during the event.
+    pub fn with_modifiers(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers |= modifiers;
+        self
+    }
+
+    /// Encode the event the way xterm would report it in the given modes.
+    ///
+    /// Returns `None` when the application's mouse mode does not report
+    /// this kind of event, such as releases in X10 mode or motion without a
+    /// held button in button-event mode. When the application has not
+    /// enabled mouse reporting at all, every event is sent in SGR encoding so
+    /// apps that parse mouse input without requesting it still see it.
+    pub fn encode(&self, modes: &TerminalModes) -> Option<Vec<u8>> {
+        let (mode, encoding) = match modes.mouse_mode {
+            MouseMode::None => (MouseMode::AnyMotion, MouseEncoding::Sgr),
+            mode => (mode, modes.mouse_encoding),
+        };
+
+        let reported = match (mode, self.action) {
+            (MouseMode::Press, MouseAction::Press(_) | MouseAction::Wheel(_)) => true,
+            (MouseMode::Press, _) => false,
+            (MouseMode::PressRelease, MouseAction::Motion(_)) => false,
+            (MouseMode::ButtonMotion, MouseAction::Motion(held)) => held.is_some(),
+            _ => true,
+        };
+        if !reported {
+            return None;
+        }
+
+        let sgr = matches!(encoding, MouseEncoding::Sgr | MouseEncoding::SgrPixels);
+        let mut code = match self.action {
+            MouseAction::Press(button) => button.press_code(),
+            MouseAction::Release(Some(button)) if sgr => button.press_code(),
+            MouseAction::Release(_) => 3,
+            MouseAction::Motion(held) => 32 + held.map_or(3, MouseButton::press_code),
+            MouseAction::Wheel(direction) => direction.wheel_code(),
+        };
+        // X10 mode does not report modifiers.
+        if mode != MouseMode::Press {
+            code |= modifier_bits(self.modifiers);
+        }
+
+        let (x, y) = if encoding == MouseEncoding::SgrPixels {
+            (
+                u32::from(self.position.col) * u32::from(CELL_PIXEL_WIDTH)
+                    + u32::from(CELL_PIXEL_WIDTH / 2)
+                    + 1,
+                u32::from(self.position.row) * u32::from(CELL_PIXEL_HEIGHT)
+                    + u32::from(CELL_PIXEL_HEIGHT / 2)
+                    + 1,
+            )
+        } else {
+            (
+                u32::from(self.position.col) + 1,
+                u32::from(self.position.row) + 1,
+            )
+        };
+
+        let release = matches!(self.action, MouseAction::Release(_));
+        Some(match encoding {
+            MouseEncoding::Sgr | MouseEncoding::SgrPixels => {
+                let suffix = if release { 'm' } else { 'M' };
+                format!("\x1b[<{code};{x};{y}{suffix}").into_bytes()
+            }
+            MouseEncoding::Urxvt => format!("\x1b[{};{x};{y}M", u32::from(code) + 32).into_bytes(),
+            MouseEncoding::Utf8 => {
+                let mut bytes = b"\x1b[M".to_vec();
+                for value in [
+                    u32::from(code),
+                    x.min(MAX_UTF8_COORD),
+                    y.min(MAX_UTF8_COORD),
+                ] {
+                    let c = char::from_u32(value + 32).unwrap_or(' ');
+                    let mut buf = [0u8; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                }
+                bytes
+            }
+            MouseEncoding::Default => {
+                let mut bytes = b"\x1b[M".to_vec();
+                for value in [
+                    u32::from(code),
+                    x.min(MAX_DEFAULT_COORD),
+                    y.min(MAX_DEFAULT_COORD),
+                ] {
+                    bytes.push((value + 32) as u8);
+                }
+                bytes
+            }
+        })
+    }
+}
+
+/// Mouse report bits for held modifiers: Shift 4, Alt/Meta 8, Ctrl 16.
+fn modifier_bits(modifiers: Modifiers) -> u8 {
+    let mut bits = 0;
+    if modifiers.contains(Modifiers::SHIFT) {
+        bits |= 4;
+    }
+    if modifiers.contains(Modifiers::ALT) || modifiers.contains(Modifiers::META) {
+        bits |= 8;
+    }
+    if modifiers.contains(Modifiers::CTRL) {
+        bits |= 16;
+    }
+    bits
+}
+
+/// Events for dragging `button` from one cell to another, moving through
+/// `steps` intermediate positions.
+pub(crate) fn drag_events(
+    from: Position,
+    to: Position,
+    button: MouseButton,
+    steps: u16,
+) -> Vec<MouseEvent> {
+    let mut events = vec![MouseEvent::new(
+        MouseAction::Press(button),
+        from.row,
+        from.col,
+    )];
+    let steps = i32::from(steps.max(1));
+    let lerp = |a: u16, b: u16, i: i32| {
+        let (a, b) = (i32::from(a), i32::from(b));
+        (a + (b - a) * i / steps) as u16
+    };
+    for i in 1..=steps {
+        events.push(MouseEvent::new(
+            MouseAction::Motion(Some(button)),
+            lerp(from.row, to.row, i),
+            lerp(from.col, to.col, i),
+        ));
+    }
+    events.push(MouseEvent::new(
+        MouseAction::Release(Some(button)),
+        to.row,
+        to.col,
+    ));
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn modes(mouse_mode: MouseMode, mouse_encoding: MouseEncoding) -> TerminalModes {
+        TerminalModes {
+            mouse_mode,
+            mouse_encoding,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_encodings() {
+        let press = MouseEvent::new(MouseAction::Press(MouseButton::Left), 4, 9);
+        let encode = |encoding| press.encode(&modes(MouseMode::PressRelease, encoding));
+        assert_eq!(encode(MouseEncoding::Default).unwrap(), b"\x1b[M *%");
+        assert_eq!(encode(MouseEncoding::Sgr).unwrap(), b"\x1b[<0;10;5M");
+        assert_eq!(encode(MouseEncoding::Urxvt).unwrap(), b"\x1b[32;10;5M");
+        assert_eq!(encode(MouseEncoding::SgrPixels).unwrap(), b"\x1b[<0;77;73M");
+
+        let far = MouseEvent::new(MouseAction::Press(MouseButton::Left), 0, 299);
+        let utf8 = far
+            .encode(&modes(MouseMode::PressRelease, MouseEncoding::Utf8))
+            .unwrap();
+        assert_eq!(utf8, "\x1b[M \u{14c}!".as_bytes());
+    }
+
+    #[test]
+    fn test_release_wheel_and_modifiers() {
+        let sgr = modes(MouseMode::PressRelease, MouseEncoding::Sgr);
+        let release = MouseEvent::new(MouseAction::Release(Some(MouseButton::Right)), 0, 0);
+        assert_eq!(release.encode(&sgr).unwrap(), b"\x1b[<2;1;1m");
+        assert_eq!(
+            release
+                .encode(&modes(MouseMode::PressRelease, MouseEncoding::Default))
+                .unwrap(),
+            b"\x1b[M#!!"
+        );
+
+        let wheel = MouseEvent::new(MouseAction::Wheel(ScrollDirection::Down), 1, 1)
+            .with_modifiers(Modifiers::CTRL | Modifiers::SHIFT);
+        assert_eq!(wheel.encode(&sgr).unwrap(), b"\x1b[<85;2;2M");
+    }
+
+    #[test]
+    fn test_mode_filtering() {
+        let x10 = modes(MouseMode::Press, MouseEncoding::Default);
+        let release = MouseEvent::new(MouseAction::Release(None), 0, 0);
+        assert_eq!(release.encode(&x10), None);
+        let click = MouseEvent::new(MouseAction::Press(MouseButton::Left), 0, 0)
+            .with_modifiers(Modifiers::CTRL);
+        assert_eq!(click.encode(&x10).unwrap(), b"\x1b[M !!");
+
+        let hover = MouseEvent::new(MouseAction::Motion(None), 0, 0);
+        let button_motion = modes(MouseMode::ButtonMotion, MouseEncoding::Sgr);
+        assert_eq!(hover.encode(&button_motion), None);
+        let drag = MouseEvent::new(MouseAction::Motion(Some(MouseButton::Left)), 0, 0);
+        assert_eq!(drag.encode(&button_motion).unwrap(), b"\x1b[<32;1;1M");
+
+        // Without mouse reporting events are still sent, SGR encoded.
+        assert_eq!(
+            hover.encode(&TerminalModes::default()).unwrap(),
+            b"\x1b[<35;1;1M"
+        );
+    }
+
+    #[test]
+    fn test_drag_events() {
+        let events = drag_events(
+            Position::new(0, 0),
+            Position::new(2, 4),
+            MouseButton::Left,
+            2,
+        );
+        let positions: Vec<_> = events.iter().map(|e| e.position).collect();
+        assert_eq!(
+            positions,
+            vec![
+                Position::new(0, 0),
+                Position::new(1, 2),
+                Position::new(2, 4),
+                Position::new(2, 4),
+            ]
+        );
+        assert_eq!(events[0].action, MouseAction::Press(MouseButton::Left));
+        assert_eq!(
+            events[3].action,
+            MouseAction::Release(Some(MouseButton::Left))
+        );
+    }
+}
@@ -56,15 +56,25 @@
 //! - [`screen`]: Screen state representation and querying
 //! - [`input`]: Key definitions and escape sequences
 //! - [`wait`]: Wait conditions and duration helpers
+//! - [`snapshot`]: Golden snapshot assertions
+//! - [`shell`]: Running shell commands through prompt integration marks
+//! - [`capture`]: Raw output capture and escape sequence listings
+//! - [`asciicast`]: Asciicast v2 session recordings
+//! - [`replay`]: Offline playback of recordings and output logs
 //! - [`error`]: Error types
 //! - [`prelude`]: Convenient re-exports
 
+pub mod asciicast;
+pub mod capture;
 pub mod daemon;
 pub mod error;
 pub mod info;
 pub mod input;
 pub mod output;
+pub mod replay;
 pub mod screen;
+pub mod shell;
+pub mod snapshot;
 pub mod terminal;
 pub mod wait;
 
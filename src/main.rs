@@ -1,6 +1,6 @@
 //! Termwright CLI - Terminal automation from the command line.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command as ProcessCommand, Stdio};
 use std::time::Duration;
 
@@ -13,7 +13,9 @@ use termwright::info::{
     InfoOverview, capabilities::CapabilitiesInfo, keys::KeysOverview, protocols::ProtocolsOverview,
     steps::StepsOverview,
 };
+use termwright::output::{AnimationConfig, AnimationFormat};
 use termwright::prelude::*;
+use termwright::snapshot::PendingSnapshot;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
 
@@ -73,6 +75,9 @@ enum Commands {
         #[arg(long, default_value = "30")]
         timeout: u64,
 
+        /// Record the session as asciicast v2 to this file
+        #[arg(long)]
+        record: Option<PathBuf>,
         /// The command to run
         #[arg(required = true)]
         command: String,
@@ -116,6 +121,9 @@ enum Commands {
         #[arg(long, default_value = "30")]
         timeout: u64,
 
+        /// Record the session as asciicast v2 to this file
+        #[arg(long)]
+        record: Option<PathBuf>,
         /// The command to run
         #[arg(required = true)]
         command: String,
@@ -125,6 +133,41 @@ enum Commands {
         args: Vec<String>,
     },
 
+    /// Render an asciicast recording as an animated GIF or APNG
+    RenderCast {
+        /// Recording to render
+        #[arg(required = true)]
+        input: PathBuf,
+
+        /// Output file (.gif, .png or .apng)
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Output format: gif or apng (defaults to the output file extension)
+        #[arg(long)]
+        format: Option<AnimationFormat>,
+
+        /// Most frames per second (0 keeps every frame)
+        #[arg(long, default_value = "30")]
+        max_fps: u32,
+
+        /// Longest pause in milliseconds (0 keeps the recorded pauses)
+        #[arg(long, default_value = "2000")]
+        max_idle: u64,
+
+        /// Do not draw the cursor
+        #[arg(long)]
+        no_cursor: bool,
+
+        /// Font name for rendering
+        #[arg(long)]
+        font: Option<String>,
+
+        /// Font size in pixels
+        #[arg(long, default_value = "14")]
+        font_size: f32,
+    },
+
     /// Run a steps file for end-to-end testing
     RunSteps {
         /// Path to YAML or JSON steps file
@@ -138,6 +181,10 @@ enum Commands {
         /// Record trace output to artifacts directory
         #[arg(long)]
         trace: bool,
+
+        /// Record the session as asciicast v2 to this file
+        #[arg(long)]
+        record: Option<PathBuf>,
     },
 
     /// Execute a single daemon request and print the response
@@ -177,6 +224,9 @@ enum Commands {
         #[arg(long, hide = true)]
         background_child: bool,
 
+        /// Record the session as asciicast v2 to this file
+        #[arg(long)]
+        record: Option<PathBuf>,
         /// The command to run
         #[arg(required = true)]
         command: String,
@@ -192,6 +242,12 @@ enum Commands {
         command: HubCommands,
     },
 
+    /// Work with golden snapshots
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+
     /// Show information about steps, protocols, and capabilities
     Info {
         #[command(subcommand)]
@@ -244,6 +300,24 @@ enum HubCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum SnapshotCommands {
+    /// Accept or reject pending snapshots left by failed assertions
+    Review {
+        /// Directory to search, including subdirectories
+        #[arg(long, default_value = ".")]
+        dir: PathBuf,
+
+        /// Accept every pending snapshot without asking
+        #[arg(long, conflicts_with = "reject")]
+        accept: bool,
+
+        /// Reject every pending snapshot without asking
+        #[arg(long)]
+        reject: bool,
+    },
+}
+
 #[derive(Subcommand)]
 enum InfoCommands {
     /// List all step types for YAML/JSON step files
@@ -271,6 +345,9 @@ enum OutputFormat {
     Text,
     Json,
     JsonCompact,
+    Svg,
+    Html,
+    Ansi,
 }
 
 impl std::str::FromStr for OutputFormat {
@@ -281,8 +358,11 @@ impl std::str::FromStr for OutputFormat {
             "text" => Ok(OutputFormat::Text),
             "json" => Ok(OutputFormat::Json),
             "json-compact" | "jsoncompact" => Ok(OutputFormat::JsonCompact),
+            "svg" => Ok(OutputFormat::Svg),
+            "html" => Ok(OutputFormat::Html),
+            "ansi" => Ok(OutputFormat::Ansi),
             _ => Err(format!(
-                "Unknown format: {}. Use text, json, or json-compact",
+                "Unknown format: {}. Use text, json, json-compact, svg, html, or ansi",
                 s
             )),
         }
@@ -306,6 +386,7 @@ async fn main() -> Result<()> {
             delay,
             format,
             timeout,
+            record,
             command,
             args,
         } => {
@@ -316,6 +397,7 @@ async fn main() -> Result<()> {
                 delay,
                 format,
                 timeout,
+                record,
                 no_default_env,
                 no_osc_emulation,
                 &command,
@@ -332,6 +414,7 @@ async fn main() -> Result<()> {
             font,
             font_size,
             timeout,
+            record,
             command,
             args,
         } => {
@@ -344,6 +427,7 @@ async fn main() -> Result<()> {
                 font,
                 font_size,
                 timeout,
+                record,
                 no_default_env,
                 no_osc_emulation,
                 &command,
@@ -355,18 +439,42 @@ async fn main() -> Result<()> {
             file,
             connect,
             trace,
+            record,
         } => {
             runner::run_steps(
                 &file,
                 RunStepsOptions {
                     connect,
                     trace,
+                    record,
                     no_default_env,
                     no_osc_emulation,
                 },
             )
             .await?;
         }
+        Commands::RenderCast {
+            input,
+            output,
+            format,
+            max_fps,
+            max_idle,
+            no_cursor,
+            font,
+            font_size,
+        } => {
+            let config = AnimationConfig {
+                screenshot: ScreenshotConfig {
+                    font_name: font,
+                    font_size,
+                    ..ScreenshotConfig::default()
+                },
+                max_fps,
+                max_idle: (max_idle > 0).then(|| Duration::from_millis(max_idle)),
+                cursor: !no_cursor,
+            };
+            render_cast(&input, &output, format, config)?;
+        }
         Commands::Exec {
             socket,
             method,
@@ -380,6 +488,7 @@ async fn main() -> Result<()> {
             socket,
             background,
             background_child,
+            record,
             command,
             args,
         } => {
@@ -389,6 +498,7 @@ async fn main() -> Result<()> {
                 socket,
                 background,
                 background_child,
+                record,
                 no_default_env,
                 no_osc_emulation,
                 &command,
@@ -421,6 +531,15 @@ async fn main() -> Result<()> {
                 hub_stop(&socket, input.as_ref()).await?;
             }
         },
+        Commands::Snapshot { command } => match command {
+            SnapshotCommands::Review {
+                dir,
+                accept,
+                reject,
+            } => {
+                snapshot_review(&dir, accept, reject)?;
+            }
+        },
         Commands::Info { command, json } => {
             run_info_command(command, json)?;
         }
@@ -429,6 +548,7 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_command(
     cols: u16,
     rows: u16,
@@ -436,6 +556,7 @@ async fn run_command(
     delay: u64,
     format: OutputFormat,
     timeout: u64,
+    record: Option<PathBuf>,
     no_default_env: bool,
     no_osc_emulation: bool,
     command: &str,
@@ -450,6 +571,9 @@ async fn run_command(
     if no_osc_emulation {
         builder = builder.no_osc_emulation();
     }
+    if let Some(path) = record {
+        builder = builder.record_to(path);
+    }
 
     let term = builder.spawn(command, &args_str).await?;
 
@@ -476,6 +600,15 @@ async fn run_command(
         OutputFormat::JsonCompact => {
             println!("{}", screen.to_json_compact()?);
         }
+        OutputFormat::Svg => {
+            print!("{}", screen.to_svg());
+        }
+        OutputFormat::Html => {
+            print!("{}", screen.to_html());
+        }
+        OutputFormat::Ansi => {
+            print!("{}", screen.to_ansi());
+        }
     }
 
     // Kill the process
@@ -533,6 +666,7 @@ struct HubEntry {
     pid: u32,
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn hub_start(
     count: u16,
     cols: u16,
@@ -650,6 +784,7 @@ async fn spawn_background_daemon(
             return Err(TermwrightError::Timeout {
                 condition: "daemon socket to become ready".to_string(),
                 timeout: Duration::from_secs(2),
+                context: None,
             });
         }
 
@@ -696,6 +831,7 @@ async fn send_close_request(socket: &PathBuf) -> Result<()> {
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn take_screenshot(
     cols: u16,
     rows: u16,
@@ -705,6 +841,7 @@ async fn take_screenshot(
     font: Option<String>,
     font_size: f32,
     timeout: u64,
+    record: Option<PathBuf>,
     no_default_env: bool,
     no_osc_emulation: bool,
     command: &str,
@@ -719,6 +856,9 @@ async fn take_screenshot(
     if no_osc_emulation {
         builder = builder.no_osc_emulation();
     }
+    if let Some(path) = record {
+        builder = builder.record_to(path);
+    }
 
     let term = builder.spawn(command, &args_str).await?;
 
@@ -761,12 +901,79 @@ async fn take_screenshot(
     Ok(())
 }
 
+fn render_cast(
+    input: &PathBuf,
+    output: &PathBuf,
+    format: Option<AnimationFormat>,
+    config: AnimationConfig,
+) -> Result<()> {
+    let format = format
+        .or_else(|| AnimationFormat::from_path(output))
+        .unwrap_or_default();
+
+    let mut animation = Animation::with_config(config);
+    animation.push_replay(&mut Replay::open(input)?);
+
+    std::fs::write(output, animation.encode(format)?).map_err(TermwrightError::Pty)?;
+    eprintln!("Animation saved to: {}", output.display());
+    Ok(())
+}
+
+fn snapshot_review(dir: &Path, accept_all: bool, reject_all: bool) -> Result<()> {
+    let pending = PendingSnapshot::find(dir)?;
+    if pending.is_empty() {
+        println!("No pending snapshots in {}", dir.display());
+        return Ok(());
+    }
+
+    let stdin = std::io::stdin();
+    let (mut accepted, mut rejected, mut skipped) = (0, 0, 0);
+    for snapshot in &pending {
+        let choice = if accept_all {
+            'a'
+        } else if reject_all {
+            'r'
+        } else {
+            println!("{}", snapshot.pending.display());
+            println!();
+            println!("{}", snapshot.diff()?);
+            print!("[a]ccept, [r]eject or [s]kip? ");
+            std::io::Write::flush(&mut std::io::stdout()).map_err(TermwrightError::Pty)?;
+            let mut answer = String::new();
+            if stdin.read_line(&mut answer).map_err(TermwrightError::Pty)? == 0 {
+                // No more answers; leave the rest pending.
+                println!();
+                skipped += pending.len() - accepted - rejected - skipped;
+                break;
+            }
+            answer.trim().chars().next().unwrap_or('s')
+        };
+
+        match choice.to_ascii_lowercase() {
+            'a' => {
+                snapshot.accept()?;
+                accepted += 1;
+            }
+            'r' => {
+                snapshot.reject()?;
+                rejected += 1;
+            }
+            _ => skipped += 1,
+        }
+    }
+
+    println!("{accepted} accepted, {rejected} rejected, {skipped} skipped");
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn run_daemon_command(
     cols: u16,
     rows: u16,
     socket: Option<PathBuf>,
     background: bool,
     background_child: bool,
+    record: Option<PathBuf>,
     no_default_env: bool,
     no_osc_emulation: bool,
     command: &str,
@@ -799,6 +1006,9 @@ async fn run_daemon_command(
         if no_osc_emulation {
             child.arg("--no-osc-emulation");
         }
+        if let Some(path) = &record {
+            child.arg("--record").arg(path);
+        }
 
         child.arg("--").arg(command);
 
@@ -822,6 +1032,7 @@ async fn run_daemon_command(
                 return Err(TermwrightError::Timeout {
                     condition: "daemon socket to become ready".to_string(),
                     timeout: Duration::from_secs(2),
+                    context: None,
                 });
             }
 
@@ -846,6 +1057,9 @@ async fn run_daemon_command(
     if no_osc_emulation {
         builder = builder.no_osc_emulation();
     }
+    if let Some(path) = record {
+        builder = builder.record_to(path);
+    }
 
     let terminal = builder.spawn(command, &args_str).await?;
 
@@ -0,0 +1,436 @@
+//! Animated renderings of terminal sessions.
+
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, Rgba, RgbaImage};
+
+use crate::error::{Result, TermwrightError};
+use crate::replay::Replay;
+use crate::screen::Screen;
+
+use super::image::{Renderer, ScreenshotConfig};
+
+/// How long the last screen is held when idle time is not capped.
+const LAST_FRAME_HOLD: Duration = Duration::from_secs(1);
+
+/// GIF quantization speed, from 1 (best) to 30 (fastest). Terminal frames
+/// have few colors, so quality barely suffers.
+const GIF_SPEED: i32 = 10;
+
+/// File format of an animation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnimationFormat {
+    /// Animated GIF.
+    #[default]
+    Gif,
+    /// Animated PNG.
+    Apng,
+}
+
+impl AnimationFormat {
+    /// Pick the format from a file extension: `.gif`, or `.png` or `.apng`.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?;
+        extension.parse().ok()
+    }
+}
+
+impl FromStr for AnimationFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gif" => Ok(AnimationFormat::Gif),
+            "apng" | "png" => Ok(AnimationFormat::Apng),
+            _ => Err(format!("Unknown animation format: {s}. Use gif or apng")),
+        }
+    }
+}
+
+/// Configuration for animation rendering.
+#[derive(Debug, Clone)]
+pub struct AnimationConfig {
+    /// How each frame is drawn.
+    pub screenshot: ScreenshotConfig,
+    /// Frames per second at most. Screens within one frame interval are
+    /// merged, keeping the last; zero keeps every screen.
+    pub max_fps: u32,
+    /// Longest a screen stays up; longer pauses are cut short. Also how long
+    /// the last screen is held. `None` keeps the recorded pauses.
+    pub max_idle: Option<Duration>,
+    /// Draw the cursor.
+    pub cursor: bool,
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        Self {
+            screenshot: ScreenshotConfig::default(),
+            max_fps: 30,
+            max_idle: Some(Duration::from_secs(2)),
+            cursor: true,
+        }
+    }
+}
+
+/// An animation of timestamped screens.
+///
+/// Consecutive screens that look the same are shown as one frame.
+#[derive(Default)]
+pub struct Animation {
+    screens: Vec<(Duration, Screen)>,
+    config: AnimationConfig,
+}
+
+impl Animation {
+    /// Create an empty animation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty animation with custom configuration.
+    pub fn with_config(config: AnimationConfig) -> Self {
+        Self {
+            screens: Vec::new(),
+            config,
+        }
+    }
+
+    /// Add the frames of a replay, from the start.
+    ///
+    /// Only screens that will be shown are kept: the last of each frame
+    /// interval, when it looks different from the one before. This follows
+    /// the frame rate and cursor setting at the time of the call.
+    pub fn push_replay(&mut self, replay: &mut Replay) -> &mut Self {
+        let times = replay.frame_times();
+        for (index, time) in times.iter().enumerate() {
+            let merged = times
+                .get(index + 1)
+                .is_some_and(|next| self.snap(*next) == self.snap(*time));
+            if merged {
+                continue;
+            }
+            let Some(screen) = replay.screen_at_frame(index) else {
+                break;
+            };
+            let unchanged = self
+                .screens
+                .last()
+                .is_some_and(|(_, last)| self.looks_same(last, &screen));
+            if !unchanged {
+                self.push(*time, screen);
+            }
+        }
+        self
+    }
+
+    /// Add the screen shown from `time` on.
+    pub fn push(&mut self, time: Duration, screen: Screen) -> &mut Self {
+        self.screens.push((time, screen));
+        self
+    }
+
+    /// Set the font family and size.
+    pub fn font(mut self, name: &str, size: f32) -> Self {
+        self.config.screenshot.font_name = Some(name.to_string());
+        self.config.screenshot.font_size = size;
+        self
+    }
+
+    /// Set the most frames per second; zero keeps every screen.
+    pub fn max_fps(mut self, fps: u32) -> Self {
+        self.config.max_fps = fps;
+        self
+    }
+
+    /// Set the longest a screen stays up, or `None` to keep the recorded
+    /// pauses.
+    pub fn max_idle(mut self, idle: Option<Duration>) -> Self {
+        self.config.max_idle = idle;
+        self
+    }
+
+    /// Set whether the cursor is drawn.
+    pub fn cursor(mut self, cursor: bool) -> Self {
+        self.config.cursor = cursor;
+        self
+    }
+
+    /// The frames that will be rendered and how long each is shown.
+    pub fn frames(&self) -> Vec<(&Screen, Duration)> {
+        let mut screens: Vec<_> = self.screens.iter().collect();
+        screens.sort_by_key(|(time, _)| *time);
+
+        // Snap to frame intervals, keeping the last screen of each.
+        let mut kept: Vec<(Duration, &Screen)> = Vec::new();
+        for (time, screen) in screens {
+            let time = self.snap(*time);
+            match kept.last_mut() {
+                Some(last) if last.0 == time => last.1 = screen,
+                _ => kept.push((time, screen)),
+            }
+        }
+        kept.dedup_by(|next, previous| self.looks_same(previous.1, next.1));
+
+        let hold = self.config.max_idle.unwrap_or(LAST_FRAME_HOLD);
+        (0..kept.len())
+            .map(|i| {
+                let shown = kept.get(i + 1).map_or(hold, |(next, _)| *next - kept[i].0);
+                let shown = self.config.max_idle.map_or(shown, |idle| shown.min(idle));
+                (kept[i].1, shown)
+            })
+            .collect()
+    }
+
+    /// The start of the frame interval `time` falls in.
+    fn snap(&self, time: Duration) -> Duration {
+        match self.config.max_fps {
+            0 => time,
+            fps => {
+                let slot = time.as_nanos() * fps as u128 / 1_000_000_000;
+                Duration::from_nanos((slot * 1_000_000_000 / fps as u128) as u64)
+            }
+        }
+    }
+
+    /// Whether two screens render the same.
+    fn looks_same(&self, a: &Screen, b: &Screen) -> bool {
+        let cursor = |screen: &Screen| {
+            (self.config.cursor && screen.cursor_visible())
+                .then(|| (screen.cursor, screen.cursor_style()))
+        };
+        a.size == b.size && a.raw_cells() == b.raw_cells() && cursor(a) == cursor(b)
+    }
+
+    /// The frames to render, and a renderer that draws them on a canvas
+    /// fitting the largest screen.
+    fn prepare(&self) -> Result<(Vec<(&Screen, Duration)>, FrameRenderer)> {
+        let frames = self.frames();
+        if frames.is_empty() {
+            return Err(TermwrightError::Image(
+                "animation has no frames".to_string(),
+            ));
+        }
+
+        let renderer = Renderer::new(self.config.screenshot.clone())?;
+        let (mut width, mut height) = (0, 0);
+        for (screen, _) in &frames {
+            let (w, h) = renderer.image_size(screen.size.cols, screen.size.rows)?;
+            width = width.max(w);
+            height = height.max(h);
+        }
+        let renderer = FrameRenderer {
+            renderer,
+            width,
+            height,
+            cursor: self.config.cursor,
+        };
+        Ok((frames, renderer))
+    }
+
+    /// Encode as an animated GIF that loops forever.
+    ///
+    /// Frames are rendered one at a time as they are encoded.
+    pub fn to_gif(&self) -> Result<Vec<u8>> {
+        let (frames, renderer) = self.prepare()?;
+        let gif_error = |e: image::ImageError| TermwrightError::Image(e.to_string());
+
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = GifEncoder::new_with_speed(&mut bytes, GIF_SPEED);
+            encoder.set_repeat(Repeat::Infinite).map_err(gif_error)?;
+            for (screen, shown) in frames {
+                let image = renderer.render(screen)?;
+                let delay = Delay::from_saturating_duration(shown);
+                encoder
+                    .encode_frame(Frame::from_parts(image, 0, 0, delay))
+                    .map_err(gif_error)?;
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Encode as an animated PNG that loops forever.
+    ///
+    /// Frames are rendered one at a time as they are encoded.
+    pub fn to_apng(&self) -> Result<Vec<u8>> {
+        let (frames, renderer) = self.prepare()?;
+        let png_error = |e: png::EncodingError| TermwrightError::Image(e.to_string());
+
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, renderer.width, renderer.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .set_animated(frames.len() as u32, 0)
+            .map_err(png_error)?;
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        for (screen, shown) in frames {
+            let image = renderer.render(screen)?;
+            let millis = shown.as_millis().min(u16::MAX as u128) as u16;
+            writer.set_frame_delay(millis, 1000).map_err(png_error)?;
+            writer.write_image_data(image.as_raw()).map_err(png_error)?;
+        }
+        writer.finish().map_err(png_error)?;
+        Ok(bytes)
+    }
+
+    /// Encode in the given format.
+    pub fn encode(&self, format: AnimationFormat) -> Result<Vec<u8>> {
+        match format {
+            AnimationFormat::Gif => self.to_gif(),
+            AnimationFormat::Apng => self.to_apng(),
+        }
+    }
+
+    /// Save to a file, in the format its extension names.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let format = AnimationFormat::from_path(path).ok_or_else(|| {
+            TermwrightError::Image(format!(
+                "cannot tell the animation format of {}; use .gif or .png",
+                path.display()
+            ))
+        })?;
+        std::fs::write(path, self.encode(format)?)?;
+        Ok(())
+    }
+}
+
+/// Draws frames on a canvas of one size.
+struct FrameRenderer {
+    renderer: Renderer,
+    width: u32,
+    height: u32,
+    cursor: bool,
+}
+
+impl FrameRenderer {
+    /// Render one frame, padded to the canvas when a resize made its screen
+    /// smaller.
+    fn render(&self, screen: &Screen) -> Result<RgbaImage> {
+        let image = if self.cursor {
+            self.renderer.render(screen)?
+        } else {
+            let mut screen = screen.clone();
+            screen.modes.cursor_hidden = true;
+            self.renderer.render(&screen)?
+        };
+        if image.dimensions() == (self.width, self.height) {
+            return Ok(image);
+        }
+        let mut canvas = RgbaImage::from_pixel(self.width, self.height, Rgba([0, 0, 0, 255]));
+        image::imageops::overlay(&mut canvas, &image, 0, 0);
+        Ok(canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(animation: &Animation) -> Vec<(String, Duration)> {
+        animation
+            .frames()
+            .into_iter()
+            .map(|(screen, shown)| (screen.text().trim().to_string(), shown))
+            .collect()
+    }
+
+    #[test]
+    fn merges_repeated_screens_and_caps_rate_and_pauses() {
+        let ms = Duration::from_millis;
+        let mut animation = Animation::new().max_fps(10).max_idle(Some(ms(500)));
+        animation
+            .push(ms(0), Screen::from_ansi("a", 10, 2))
+            .push(ms(20), Screen::from_ansi("ab", 10, 2))
+            .push(ms(100), Screen::from_ansi("ab", 10, 2))
+            .push(ms(300), Screen::from_ansi("abc", 10, 2))
+            .push(ms(5000), Screen::from_ansi("abcd", 10, 2));
+
+        assert_eq!(
+            frames(&animation),
+            vec![
+                ("ab".to_string(), ms(300)),
+                ("abc".to_string(), ms(500)),
+                ("abcd".to_string(), ms(500)),
+            ]
+        );
+
+        let mut animation = Animation::new().max_fps(0).max_idle(None);
+        animation
+            .push(ms(0), Screen::from_ansi("a", 10, 2))
+            .push(ms(20), Screen::from_ansi("ab", 10, 2));
+        assert_eq!(
+            frames(&animation),
+            vec![
+                ("a".to_string(), ms(20)),
+                ("ab".to_string(), LAST_FRAME_HOLD)
+            ]
+        );
+    }
+
+    #[test]
+    fn cursor_moves_are_frames_only_when_drawn() {
+        let ms = Duration::from_millis;
+        let mut animation = Animation::new();
+        animation
+            .push(ms(0), Screen::from_ansi("a", 10, 2))
+            .push(ms(500), Screen::from_ansi("a\x1b[H", 10, 2));
+        assert_eq!(animation.frames().len(), 2);
+
+        let mut animation = Animation::new().cursor(false);
+        animation
+            .push(ms(0), Screen::from_ansi("a", 10, 2))
+            .push(ms(500), Screen::from_ansi("a\x1b[H", 10, 2));
+        assert_eq!(animation.frames().len(), 1);
+    }
+
+    #[test]
+    fn replays_keep_only_screens_that_are_shown() {
+        let cast = concat!(
+            r#"{"version":2,"width":10,"height":2}"#,
+            "\n",
+            r#"[0.0,"o","a"]"#,
+            "\n",
+            r#"[0.01,"o","b"]"#,
+            "\n",
+            r#"[0.5,"o","\u001b[m"]"#,
+            "\n",
+            r#"[1.0,"o","c"]"#,
+            "\n",
+        );
+        let mut replay = Replay::from_cast(cast.as_bytes()).unwrap();
+        let mut animation = Animation::new().max_fps(10);
+        animation.push_replay(&mut replay);
+
+        let pushed: Vec<_> = animation
+            .screens
+            .iter()
+            .map(|(time, screen)| (*time, screen.text().trim().to_string()))
+            .collect();
+        assert_eq!(
+            pushed,
+            vec![
+                (Duration::from_millis(10), "ab".to_string()),
+                (Duration::from_secs(1), "abc".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(
+            AnimationFormat::from_path("out.GIF"),
+            Some(AnimationFormat::Gif)
+        );
+        assert_eq!(
+            AnimationFormat::from_path("out.apng"),
+            Some(AnimationFormat::Apng)
+        );
+        assert_eq!(AnimationFormat::from_path("out.txt"), None);
+    }
+}
@@ -0,0 +1,186 @@
+//! ANSI escape renderings of screens.
+//!
+//! A screen is written as the text and escape sequences that draw it, one
+//! line per row, so it can be printed to a real terminal or read back into
+//! a [`Screen`]. Nothing above the first row is cleared and the cursor is
+//! moved relative to the last row, so the screen draws wherever the output
+//! starts.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::replay::Replay;
+use crate::screen::{Cell, CellAttributes, Color, Hyperlink, Screen};
+
+/// Colors and attributes set by SGR.
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Pen {
+    fg: Color,
+    bg: Color,
+    attrs: CellAttributes,
+}
+
+impl Pen {
+    fn of(cell: &Cell) -> Self {
+        Self {
+            fg: cell.fg,
+            bg: cell.bg,
+            attrs: cell.attrs,
+        }
+    }
+
+    /// The SGR sequence that changes this pen into `next`, setting only
+    /// what differs.
+    fn transition(&self, next: &Pen) -> String {
+        if *next == Pen::default() {
+            return "\x1b[m".to_string();
+        }
+
+        let mut params = Vec::new();
+        let (from, to) = (self.attrs, next.attrs);
+        for (was, is, on, off) in [
+            (from.bold, to.bold, "1", "22"),
+            (from.italic, to.italic, "3", "23"),
+            (from.underline, to.underline, "4", "24"),
+            (from.inverse, to.inverse, "7", "27"),
+        ] {
+            if was != is {
+                params.push(if is { on } else { off }.to_string());
+            }
+        }
+        if self.fg != next.fg {
+            params.push(color_params(&next.fg, true));
+        }
+        if self.bg != next.bg {
+            params.push(color_params(&next.bg, false));
+        }
+        format!("\x1b[{}m", params.join(";"))
+    }
+}
+
+/// SGR parameters selecting a color, in the shortest form.
+fn color_params(color: &Color, foreground: bool) -> String {
+    let base: u16 = if foreground { 30 } else { 40 };
+    match *color {
+        Color::Default => (base + 9).to_string(),
+        Color::Indexed(idx) if idx < 8 => (base + idx as u16).to_string(),
+        Color::Indexed(idx) if idx < 16 => (base + 60 + idx as u16 - 8).to_string(),
+        Color::Indexed(idx) => format!("{};5;{idx}", base + 8),
+        Color::Rgb(r, g, b) => format!("{};2;{r};{g};{b}", base + 8),
+    }
+}
+
+/// The OSC 8 sequence opening `link`, or closing the open link.
+fn link_sequence(link: Option<&Hyperlink>) -> String {
+    match link {
+        Some(Hyperlink { uri, id: Some(id) }) => format!("\x1b]8;id={id};{uri}\x1b\\"),
+        Some(Hyperlink { uri, id: None }) => format!("\x1b]8;;{uri}\x1b\\"),
+        None => "\x1b]8;;\x1b\\".to_string(),
+    }
+}
+
+/// Render a screen as ANSI text.
+pub(crate) fn to_ansi(screen: &Screen) -> String {
+    let blank = Cell::default();
+    let mut ansi = String::new();
+    let mut pen = Pen::default();
+    let mut link = None;
+    let mut end = 0;
+
+    for (row_idx, row) in screen.raw_cells().iter().enumerate() {
+        if row_idx > 0 {
+            // Terminals fill lines scrolled in with the current background.
+            if pen.bg != Color::Default || pen.attrs.inverse {
+                ansi.push_str("\x1b[m");
+                pen = Pen::default();
+            }
+            ansi.push_str("\r\n");
+        }
+
+        let used = row
+            .iter()
+            .rposition(|cell| *cell != blank)
+            .map_or(0, |i| i + 1);
+        let mut col = 0;
+        while col < used {
+            let cell = &row[col];
+            let next = Pen::of(cell);
+            if next != pen {
+                ansi.push_str(&pen.transition(&next));
+                pen = next;
+            }
+            if cell.hyperlink.as_ref() != link {
+                link = cell.hyperlink.as_ref();
+                ansi.push_str(&link_sequence(link));
+            }
+            ansi.push(cell.char);
+            // A wide character covers the cell after it too.
+            col += cell.char.width().unwrap_or(1).max(1);
+        }
+        end = col;
+    }
+
+    if link.is_some() {
+        ansi.push_str(&link_sequence(None));
+    }
+    if pen != Pen::default() {
+        ansi.push_str("\x1b[m");
+    }
+
+    let last_row = screen.size.rows.saturating_sub(1);
+    let up = last_row.saturating_sub(screen.cursor.row);
+    if up > 0 {
+        let _ = write!(ansi, "\x1b[{up}A");
+    }
+    let col = screen.cursor.col as usize;
+    if up > 0 || col != end || end >= screen.size.cols as usize {
+        let _ = write!(ansi, "\x1b[{}G", col + 1);
+    }
+    ansi
+}
+
+/// Build a screen of the given size by drawing ANSI text on a blank
+/// terminal.
+pub(crate) fn from_ansi(ansi: &str, cols: u16, rows: u16) -> Screen {
+    Replay::from_raw(ansi.as_bytes(), cols, rows).screen_at(Duration::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen::Position;
+
+    #[test]
+    fn sets_only_what_changes() {
+        let screen = from_ansi("a\x1b[1;31mb\x1b[32mc\x1b[mx\r\n\x1b[44m \x1b[m", 10, 3);
+
+        assert_eq!(
+            to_ansi(&screen),
+            "a\x1b[1;31mb\x1b[32mc\x1b[mx\r\n\x1b[44m \x1b[m\r\n\x1b[1A\x1b[2G"
+        );
+    }
+
+    #[test]
+    fn round_trips_cells_links_and_cursor() {
+        let original = from_ansi(
+            concat!(
+                "\x1b[3;4mit\x1b[24m \x1b[7;38;5;200mx\x1b[m \x1b[38;2;1;2;3;103mrgb\x1b[m\r\n",
+                "宽 字|\x1b]8;id=a;https://example.com\x1b\\link\x1b]8;;\x1b\\\r\n",
+                "0123456789\r\n",
+                "\x1b[2;4H",
+            ),
+            10,
+            4,
+        );
+        assert_eq!(original.cursor, Position::new(1, 3));
+        assert_eq!(original.hyperlinks().len(), 1);
+
+        let ansi = to_ansi(&original);
+        let copy = from_ansi(&ansi, 10, 4);
+        assert_eq!(copy.raw_cells(), original.raw_cells());
+        assert_eq!(copy.cursor, original.cursor);
+        assert_eq!(copy.text(), original.text());
+    }
+}
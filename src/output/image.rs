@@ -9,7 +9,7 @@ use imageproc::drawing::{draw_filled_rect_mut, draw_text_mut};
 use imageproc::rect::Rect;
 
 use crate::error::{Result, TermwrightError};
-use crate::screen::Screen;
+use crate::screen::{CursorShape, Screen};
 
 use super::colors::color_to_rgba;
 
@@ -100,7 +100,46 @@ impl Screenshot {
 
 /// Render a screen to an image.
 fn render_screen(screen: &Screen, config: &ScreenshotConfig) -> Result<RgbaImage> {
-    // Load font
+    Renderer::new(config.clone())?.render(screen)
+}
+
+/// Renders screens with a font loaded once, for rendering many frames.
+pub(crate) struct Renderer {
+    config: ScreenshotConfig,
+    font_data: Vec<u8>,
+}
+
+impl Renderer {
+    pub fn new(config: ScreenshotConfig) -> Result<Self> {
+        let font_data = load_font(&config)?;
+        Ok(Self { config, font_data })
+    }
+
+    pub fn render(&self, screen: &Screen) -> Result<RgbaImage> {
+        draw_screen(screen, &self.config, &self.font_data)
+    }
+
+    /// Width and height of the image of a screen of `cols` by `rows`.
+    pub fn image_size(&self, cols: u16, rows: u16) -> Result<(u32, u32)> {
+        let font = FontRef::try_from_slice(&self.font_data)
+            .map_err(|e| TermwrightError::Font(e.to_string()))?;
+        let (char_width, line_height) = cell_size(&font, &self.config);
+        Ok((
+            (cols as f32 * char_width).ceil() as u32,
+            (rows as f32 * line_height).ceil() as u32,
+        ))
+    }
+}
+
+/// Width and height of a cell.
+fn cell_size(font: &FontRef, config: &ScreenshotConfig) -> (f32, f32) {
+    let scaled_font = font.as_scaled(PxScale::from(config.font_size));
+    let char_width = scaled_font.h_advance(font.glyph_id('M'));
+    (char_width, config.font_size * config.line_height)
+}
+
+/// Read the configured font, or the system monospace font.
+fn load_font(config: &ScreenshotConfig) -> Result<Vec<u8>> {
     let source = SystemSource::new();
     let handle = match &config.font_name {
         Some(name) => source
@@ -111,23 +150,22 @@ fn render_screen(screen: &Screen, config: &ScreenshotConfig) -> Result<RgbaImage
             .map_err(|e| TermwrightError::Font(e.to_string()))?,
     };
 
-    let font_data = match handle {
+    match handle {
         Handle::Path { path, .. } => {
-            std::fs::read(path).map_err(|e| TermwrightError::Font(e.to_string()))?
+            std::fs::read(path).map_err(|e| TermwrightError::Font(e.to_string()))
         }
-        Handle::Memory { bytes, .. } => bytes.to_vec(),
-    };
+        Handle::Memory { bytes, .. } => Ok(bytes.to_vec()),
+    }
+}
 
+fn draw_screen(screen: &Screen, config: &ScreenshotConfig, font_data: &[u8]) -> Result<RgbaImage> {
     let font =
-        FontRef::try_from_slice(&font_data).map_err(|e| TermwrightError::Font(e.to_string()))?;
+        FontRef::try_from_slice(font_data).map_err(|e| TermwrightError::Font(e.to_string()))?;
 
     let scale = PxScale::from(config.font_size);
-    let scaled_font = font.as_scaled(scale);
 
     // Calculate dimensions
-    let line_height = config.font_size * config.line_height;
-    let glyph = font.glyph_id('M');
-    let char_width = scaled_font.h_advance(glyph);
+    let (char_width, line_height) = cell_size(&font, config);
 
     let width = screen.size.cols as f32 * char_width;
     let height = screen.size.rows as f32 * line_height;
@@ -138,6 +176,15 @@ fn render_screen(screen: &Screen, config: &ScreenshotConfig) -> Result<RgbaImage
     let full_rect = Rect::at(0, 0).of_size(width.ceil() as u32, height.ceil() as u32);
     draw_filled_rect_mut(&mut image, full_rect, default_bg);
 
+    // The cursor sits past the last column while a wrap is pending
+    let cursor = screen.cursor_visible().then(|| {
+        (
+            screen.cursor.row as usize,
+            (screen.cursor.col as usize).min((screen.size.cols as usize).saturating_sub(1)),
+        )
+    });
+    let cursor_shape = screen.cursor_style().shape;
+
     // Render each cell
     for (row_idx, row) in screen.raw_cells().iter().enumerate() {
         let y = row_idx as f32 * line_height;
@@ -145,8 +192,12 @@ fn render_screen(screen: &Screen, config: &ScreenshotConfig) -> Result<RgbaImage
         for (col_idx, cell) in row.iter().enumerate() {
             let x = col_idx as f32 * char_width;
 
-            // Determine colors (handle inverse)
-            let (fg_color, bg_color) = if cell.attrs.inverse {
+            let at_cursor = cursor == Some((row_idx, col_idx));
+
+            // Determine colors (handle inverse, and a block cursor inverting
+            // the cell under it)
+            let inverse = cell.attrs.inverse != (at_cursor && cursor_shape == CursorShape::Block);
+            let (fg_color, bg_color) = if inverse {
                 (
                     color_to_rgba(&cell.bg, false),
                     color_to_rgba(&cell.fg, true),
@@ -175,6 +226,28 @@ fn render_screen(screen: &Screen, config: &ScreenshotConfig) -> Result<RgbaImage
                     &cell.char.to_string(),
                 );
             }
+
+            // Draw underline and bar cursors over the cell
+            if at_cursor {
+                let thickness = (config.font_size / 8.0).ceil().max(1.0) as u32;
+                let cursor_rect = match cursor_shape {
+                    CursorShape::Block => None,
+                    CursorShape::Underline => Some(
+                        Rect::at(
+                            x.round() as i32,
+                            (y + line_height).round() as i32 - thickness as i32,
+                        )
+                        .of_size(char_width.ceil() as u32, thickness),
+                    ),
+                    CursorShape::Bar => Some(
+                        Rect::at(x.round() as i32, y.round() as i32)
+                            .of_size(thickness, line_height.ceil() as u32),
+                    ),
+                };
+                if let Some(cursor_rect) = cursor_rect {
+                    draw_filled_rect_mut(&mut image, cursor_rect, fg_color);
+                }
+            }
         }
     }
 
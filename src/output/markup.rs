@@ -0,0 +1,378 @@
+//! SVG and HTML renderings of screens.
+//!
+//! Unlike screenshots, these keep the screen as text, so it stays
+//! selectable and searchable. Colors go through the same palette as
+//! screenshots.
+
+use std::fmt::Write;
+
+use image::Rgba;
+use unicode_width::UnicodeWidthChar;
+
+use super::colors::color_to_rgba;
+use crate::screen::{Cell, Color, Hyperlink, Screen};
+
+/// Font size of SVG text, in pixels.
+const FONT_SIZE: f32 = 14.0;
+/// Width of a cell relative to the font size.
+const CELL_WIDTH: f32 = 0.6;
+/// Height of a line relative to the font size.
+const LINE_HEIGHT: f32 = 1.2;
+/// Monospace fonts to try, in order.
+const FONT_FAMILY: &str = "ui-monospace, 'DejaVu Sans Mono', Menlo, Consolas, monospace";
+/// URI schemes exported as links. Others, such as `javascript:`, come from
+/// the application's output and are written as plain text.
+const LINK_SCHEMES: &[&str] = &["http", "https", "file", "mailto"];
+
+/// How a run of cells is drawn, with inverse already applied.
+#[derive(Clone, PartialEq)]
+struct Style<'a> {
+    fg: Rgba<u8>,
+    bg: Rgba<u8>,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    link: Option<&'a Hyperlink>,
+}
+
+impl<'a> Style<'a> {
+    fn of(cell: &'a Cell) -> Self {
+        let (fg, bg) = if cell.attrs.inverse {
+            (
+                color_to_rgba(&cell.bg, false),
+                color_to_rgba(&cell.fg, true),
+            )
+        } else {
+            (
+                color_to_rgba(&cell.fg, true),
+                color_to_rgba(&cell.bg, false),
+            )
+        };
+        Self {
+            fg,
+            bg,
+            bold: cell.attrs.bold,
+            italic: cell.attrs.italic,
+            underline: cell.attrs.underline,
+            link: cell.hyperlink.as_ref().filter(|link| safe_link(&link.uri)),
+        }
+    }
+}
+
+/// Whether a hyperlink's scheme is safe to follow from an exported page.
+fn safe_link(uri: &str) -> bool {
+    uri.split_once(':').is_some_and(|(scheme, _)| {
+        LINK_SCHEMES
+            .iter()
+            .any(|safe| safe.eq_ignore_ascii_case(scheme))
+    })
+}
+
+/// Cells next to each other on a row that are drawn alike.
+struct Run<'a> {
+    col: usize,
+    /// Number of columns the run covers.
+    cols: usize,
+    text: String,
+    style: Style<'a>,
+}
+
+/// Split a row into runs, leaving out blank cells at the end.
+fn runs(row: &[Cell]) -> Vec<Run<'_>> {
+    let blank = Cell::default();
+    let blank = Style::of(&blank);
+    let end = row
+        .iter()
+        .rposition(|cell| cell.char != ' ' || Style::of(cell) != blank)
+        .map_or(0, |i| i + 1);
+
+    let mut runs: Vec<Run> = Vec::new();
+    let mut col = 0;
+    while col < end {
+        let cell = &row[col];
+        let style = Style::of(cell);
+        // A wide character covers the cell after it too.
+        let width = cell.char.width().unwrap_or(1).max(1);
+        match runs.last_mut() {
+            Some(run) if run.style == style => {
+                run.text.push(cell.char);
+                run.cols += width;
+            }
+            _ => runs.push(Run {
+                col,
+                cols: width,
+                text: cell.char.to_string(),
+                style,
+            }),
+        }
+        col += width;
+    }
+    runs
+}
+
+fn hex(color: Rgba<u8>) -> String {
+    let [r, g, b, _] = color.0;
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Escape text for use in XML and HTML content and attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Render a screen as an SVG image.
+///
+/// Each row is one `<text>` element, so text selects and searches line by
+/// line. Every run is stretched to its cells, keeping columns aligned
+/// whatever monospace font the viewer picks.
+pub(crate) fn to_svg(screen: &Screen) -> String {
+    let default_fg = color_to_rgba(&Color::Default, true);
+    let default_bg = color_to_rgba(&Color::Default, false);
+    let cell_width = FONT_SIZE * CELL_WIDTH;
+    let line_height = FONT_SIZE * LINE_HEIGHT;
+    let width = screen.size.cols as f32 * cell_width;
+    let height = screen.size.rows as f32 * line_height;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="{FONT_FAMILY}" font-size="{FONT_SIZE}">"#,
+        w = number(width),
+        h = number(height),
+    );
+    if !screen.title.is_empty() {
+        let _ = writeln!(svg, "<title>{}</title>", escape(&screen.title));
+    }
+    let _ = writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        hex(default_bg)
+    );
+
+    let rows: Vec<_> = screen.raw_cells().iter().map(|row| runs(row)).collect();
+    for (row, runs) in rows.iter().enumerate() {
+        for run in runs.iter().filter(|run| run.style.bg != default_bg) {
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                number(run.col as f32 * cell_width),
+                number(row as f32 * line_height),
+                number(run.cols as f32 * cell_width),
+                number(line_height),
+                hex(run.style.bg)
+            );
+        }
+    }
+
+    for (row, runs) in rows.iter().enumerate() {
+        if runs.is_empty() {
+            continue;
+        }
+        let _ = write!(
+            svg,
+            r#"<text y="{}" fill="{}" xml:space="preserve" style="white-space:pre">"#,
+            number(row as f32 * line_height + FONT_SIZE),
+            hex(default_fg)
+        );
+        for run in runs {
+            let mut tspan = format!(
+                r#"<tspan x="{}" textLength="{}" lengthAdjust="spacingAndGlyphs""#,
+                number(run.col as f32 * cell_width),
+                number(run.cols as f32 * cell_width)
+            );
+            if run.style.fg != default_fg {
+                let _ = write!(tspan, r#" fill="{}""#, hex(run.style.fg));
+            }
+            if run.style.bold {
+                tspan.push_str(r#" font-weight="bold""#);
+            }
+            if run.style.italic {
+                tspan.push_str(r#" font-style="italic""#);
+            }
+            if run.style.underline {
+                tspan.push_str(r#" text-decoration="underline""#);
+            }
+            let _ = write!(tspan, ">{}</tspan>", escape(&run.text));
+            match run.style.link {
+                Some(link) => {
+                    let _ = write!(svg, r#"<a href="{}">{tspan}</a>"#, escape(&link.uri));
+                }
+                None => svg.push_str(&tspan),
+            }
+        }
+        svg.push_str("</text>\n");
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Render a screen as a standalone HTML page holding one `<pre>` block.
+pub(crate) fn to_html(screen: &Screen) -> String {
+    let default_fg = color_to_rgba(&Color::Default, true);
+    let default_bg = color_to_rgba(&Color::Default, false);
+    let title = match screen.title.as_str() {
+        "" => "termwright",
+        title => title,
+    };
+
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>");
+    let _ = writeln!(html, "<html>");
+    let _ = writeln!(html, "<head>");
+    let _ = writeln!(html, r#"<meta charset="utf-8">"#);
+    let _ = writeln!(html, "<title>{}</title>", escape(title));
+    let _ = writeln!(html, "</head>");
+    let _ = writeln!(
+        html,
+        r#"<body style="margin:0;background:{}">"#,
+        hex(default_bg)
+    );
+    let _ = write!(
+        html,
+        r#"<pre style="margin:0;padding:0;color:{};background:{};font-family:{};font-size:{}px;line-height:{}">"#,
+        hex(default_fg),
+        hex(default_bg),
+        escape(FONT_FAMILY),
+        FONT_SIZE,
+        LINE_HEIGHT
+    );
+
+    for (row, cells) in screen.raw_cells().iter().enumerate() {
+        if row > 0 {
+            html.push('\n');
+        }
+        for run in runs(cells) {
+            let mut style = Vec::new();
+            if run.style.fg != default_fg {
+                style.push(format!("color:{}", hex(run.style.fg)));
+            }
+            if run.style.bg != default_bg {
+                style.push(format!("background:{}", hex(run.style.bg)));
+            }
+            if run.style.bold {
+                style.push("font-weight:bold".to_string());
+            }
+            if run.style.italic {
+                style.push("font-style:italic".to_string());
+            }
+            if run.style.underline {
+                style.push("text-decoration:underline".to_string());
+            }
+
+            let text = escape(&run.text);
+            let text = if style.is_empty() {
+                text
+            } else {
+                format!(r#"<span style="{}">{text}</span>"#, style.join(";"))
+            };
+            match run.style.link {
+                Some(link) => {
+                    let _ = write!(
+                        html,
+                        r#"<a href="{}" style="color:inherit">{text}</a>"#,
+                        escape(&link.uri)
+                    );
+                }
+                None => html.push_str(&text),
+            }
+        }
+    }
+
+    let _ = writeln!(html, "</pre>");
+    let _ = writeln!(html, "</body>");
+    let _ = writeln!(html, "</html>");
+    html
+}
+
+/// Format a coordinate without trailing zeros.
+fn number(value: f32) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    format!("{rounded}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn svg_keeps_text_and_styles() {
+        let svg = to_svg(&Screen::from_ansi(
+            "a<b \x1b[1;31mred\x1b[0m \x1b[7minv\x1b[0m",
+            20,
+            2,
+        ));
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(r#"width="168" height="33.6""#));
+        assert!(svg.contains(">a&lt;b </tspan>"));
+        assert!(svg.contains(r##"fill="#800000" font-weight="bold">red</tspan>"##));
+        // Inverse swaps the default colors.
+        assert!(svg.contains(r##"fill="#000000">inv</tspan>"##));
+        assert!(
+            svg.contains(r##"<rect x="67.2" y="0" width="25.2" height="16.8" fill="#ffffff"/>"##)
+        );
+        // Only rows with text get a text element.
+        assert_eq!(svg.matches("<text ").count(), 1);
+    }
+
+    #[test]
+    fn html_keeps_text_styles_and_links() {
+        let html = to_html(&Screen::from_ansi(
+            "\x1b[3;4mit\x1b[0m \x1b]8;;https://example.com/?a&b\x07link\x1b]8;;\x07\r\n\x1b[48;5;196m  ",
+            20,
+            2,
+        ));
+
+        assert!(
+            html.contains(
+                r#"<span style="font-style:italic;text-decoration:underline">it</span> "#
+            )
+        );
+        assert!(
+            html.contains(
+                r#"<a href="https://example.com/?a&amp;b" style="color:inherit">link</a>"#
+            )
+        );
+        assert!(html.contains("\n<span style=\"background:#ff0000\">  </span></pre>"));
+
+        // Wide characters cover two cells but are written once.
+        let html = to_html(&Screen::from_ansi("宽字 x", 20, 2));
+        assert!(html.contains(">宽字 x\n</pre>"));
+        let svg = to_svg(&Screen::from_ansi("宽字 x", 20, 2));
+        assert!(
+            svg.contains(r#"textLength="50.4" lengthAdjust="spacingAndGlyphs">宽字 x</tspan>"#)
+        );
+    }
+
+    #[test]
+    fn exports_only_safe_links() {
+        let html = to_html(&Screen::from_ansi(
+            "\x1b]8;;javascript:alert(1)\x07js\x1b]8;;\x07 \x1b]8;;MAILTO:a@b.c\x07mail\x1b]8;;\x07",
+            20,
+            2,
+        ));
+        assert!(!html.contains("javascript"));
+        assert!(html.contains(">js "));
+        assert!(html.contains(r#"<a href="MAILTO:a@b.c" style="color:inherit">mail</a>"#));
+
+        let svg = to_svg(&Screen::from_ansi(
+            "\x1b]8;;data:text/html,x\x07data\x1b]8;;\x07",
+            20,
+            2,
+        ));
+        assert!(!svg.contains("<a "));
+        assert!(svg.contains(">data</tspan>"));
+    }
+}
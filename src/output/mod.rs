@@ -1,6 +1,10 @@
 //! Output format modules.
 
+pub mod animation;
+pub(crate) mod ansi;
 pub mod colors;
 pub mod image;
+pub(crate) mod markup;
 
+pub use self::animation::{Animation, AnimationConfig, AnimationFormat};
 pub use self::image::{Screenshot, ScreenshotConfig};
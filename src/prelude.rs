@@ -4,12 +4,20 @@
 //! use termwright::prelude::*;
 //! ```
 
+pub use crate::capture::{CapturedOutput, OutputMark};
 pub use crate::daemon::client::DaemonClient;
 pub use crate::error::{Result, TermwrightError};
-pub use crate::input::{Key, MouseButton};
-pub use crate::output::{Screenshot, ScreenshotConfig};
+pub use crate::input::{Key, Modifiers, MouseAction, MouseButton, MouseEvent, ScrollDirection};
+pub use crate::output::{Animation, Screenshot, ScreenshotConfig};
+pub use crate::replay::Replay;
 pub use crate::screen::{
-    BoxStyle, Cell, CellAttributes, Color, DetectedBox, Position, Region, Screen, Size, TextMatch,
+    BoxStyle, Cell, CellAttributes, Color, CursorShape, CursorStyle, DetectedBox, Hyperlink,
+    HyperlinkSpan, MouseEncoding, MouseMode, Position, Region, Screen, Size, TerminalModes,
+    TextMatch,
 };
-pub use crate::terminal::{Terminal, TerminalBuilder, TerminalConfig};
-pub use crate::wait::{DurationExt, WaitCondition};
+pub use crate::shell::{CommandOutput, ShellSession};
+pub use crate::snapshot::SnapshotConfig;
+pub use crate::terminal::{
+    ClipboardSelection, ExitStatus, Terminal, TerminalBuilder, TerminalConfig, TerminalIdentity,
+};
+pub use crate::wait::{DurationExt, ScreenPredicate, TimeoutContext, WaitCondition};
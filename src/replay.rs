@@ -0,0 +1,607 @@
+//! Replaying recorded output without the application.
+//!
+//! A [`Replay`] feeds an asciicast recording or a raw output log through the
+//! same emulation a live [`Terminal`](crate::Terminal) uses: vt100 plus
+//! synchronized updates, hyperlinks, prompt marks and the OSC and CSI
+//! emulators. The screen can be inspected at any point of the recording, and
+//! waits run against the recording's own clock, so a flaky CI failure can be
+//! stepped through deterministically and regression tests can run against
+//! captured output without the application installed.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use termwright::prelude::*;
+//!
+//! # fn main() -> Result<()> {
+//! let mut replay = Replay::open("session.cast")?;
+//! replay.wait_for(WaitCondition::TextAppears("Ready".into()))?;
+//! println!("ready after {:?}", replay.time());
+//!
+//! let screen = replay.screen_at(Duration::from_millis(1500));
+//! assert!(screen.contains("Done"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::asciicast::{Event, EventKind, Header};
+use crate::capture::{CapturedOutput, OutputBuffer, OutputMark};
+use crate::error::{Result, TermwrightError};
+use crate::screen::Screen;
+use crate::terminal::{
+    DEFAULT_SCROLLBACK, DEFAULT_SYNC_TIMEOUT, Emulator, EmulatorState, TerminalIdentity,
+};
+use crate::wait::{DEFAULT_TIMEOUT, WaitBuilder, WaitCondition, WaitContext};
+
+/// Something that happened at a point in the recording.
+#[derive(Debug, Clone)]
+enum Action {
+    Output(Vec<u8>),
+    Resize { cols: u16, rows: u16 },
+}
+
+/// A recorded session played back into an offline terminal.
+///
+/// The replay starts at time zero with nothing shown. Seeking forward feeds
+/// the recorded output up to that point; seeking backward starts over from
+/// the beginning. A frame is one recorded output chunk, which for a live
+/// recording is one read from the PTY.
+pub struct Replay {
+    cols: u16,
+    rows: u16,
+    scrollback: usize,
+    sync_timeout: Duration,
+    events: Vec<(Duration, Action)>,
+    parser: vt100::Parser,
+    state: EmulatorState,
+    emulator: Emulator,
+    output: Arc<Mutex<OutputBuffer>>,
+    /// Index of the next event to apply.
+    next: usize,
+    /// Current position on the recording's clock.
+    time: Duration,
+    /// Instant standing for time zero, so synchronized updates time out on
+    /// the recording's clock.
+    epoch: Instant,
+}
+
+impl Replay {
+    /// Open an asciicast v2 recording.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::from_cast(std::io::BufReader::new(file))
+    }
+
+    /// Read an asciicast v2 recording.
+    ///
+    /// Output and resize events are replayed; input and markers are skipped.
+    pub fn from_cast(reader: impl BufRead) -> Result<Self> {
+        let mut lines = reader.lines();
+        let header: Header = match lines.next() {
+            Some(line) => serde_json::from_str(&line?)?,
+            None => {
+                return Err(TermwrightError::Protocol(
+                    "empty asciicast recording".to_string(),
+                ));
+            }
+        };
+        if header.version != 2 {
+            return Err(TermwrightError::Protocol(format!(
+                "unsupported asciicast version {}",
+                header.version
+            )));
+        }
+
+        if header.width == 0 || header.height == 0 {
+            return Err(TermwrightError::Protocol(format!(
+                "invalid terminal size {}x{}",
+                header.width, header.height
+            )));
+        }
+
+        let mut events = Vec::new();
+        for line in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: Event = serde_json::from_str(&line)?;
+            let time = Duration::try_from_secs_f64(event.time.max(0.0)).map_err(|_| {
+                TermwrightError::Protocol(format!("invalid event time in {line:?}"))
+            })?;
+            match event.kind {
+                EventKind::Output => events.push((time, Action::Output(event.data.into_bytes()))),
+                EventKind::Resize => {
+                    let size = event
+                        .data
+                        .split_once('x')
+                        .and_then(|(cols, rows)| Some((cols.parse().ok()?, rows.parse().ok()?)));
+                    let Some((cols, rows)) = size.filter(|&(cols, rows)| cols > 0 && rows > 0)
+                    else {
+                        return Err(TermwrightError::Protocol(format!(
+                            "invalid resize event {:?}",
+                            event.data
+                        )));
+                    };
+                    events.push((time, Action::Resize { cols, rows }));
+                }
+                EventKind::Input | EventKind::Marker => {}
+            }
+        }
+
+        Ok(Self::new(header.width, header.height, events))
+    }
+
+    /// Replay a raw output log as a single frame at time zero.
+    pub fn from_raw(bytes: impl Into<Vec<u8>>, cols: u16, rows: u16) -> Self {
+        Self::new(
+            cols,
+            rows,
+            vec![(Duration::ZERO, Action::Output(bytes.into()))],
+        )
+    }
+
+    /// Replay raw output captured from a terminal, keeping its timing.
+    ///
+    /// Use [`Terminal::output_since`](crate::Terminal::output_since) with
+    /// the default mark to capture everything still buffered.
+    pub fn from_output(output: &CapturedOutput, cols: u16, rows: u16) -> Self {
+        let events = output
+            .chunks
+            .iter()
+            .map(|chunk| (chunk.elapsed, Action::Output(chunk.bytes.clone())))
+            .collect();
+        Self::new(cols, rows, events)
+    }
+
+    fn new(cols: u16, rows: u16, mut events: Vec<(Duration, Action)>) -> Self {
+        events.sort_by_key(|(time, _)| *time);
+        let state = EmulatorState::new(DEFAULT_SYNC_TIMEOUT);
+        Self {
+            cols,
+            rows,
+            scrollback: DEFAULT_SCROLLBACK,
+            sync_timeout: DEFAULT_SYNC_TIMEOUT,
+            events,
+            parser: vt100::Parser::new(rows, cols, DEFAULT_SCROLLBACK),
+            emulator: Emulator::new(state.clone(), TerminalIdentity::default(), true),
+            state,
+            output: Arc::default(),
+            next: 0,
+            time: Duration::ZERO,
+            epoch: Instant::now(),
+        }
+        .rewound()
+    }
+
+    /// Set the number of scrollback lines kept, as with
+    /// [`TerminalBuilder::scrollback`](crate::terminal::TerminalBuilder::scrollback).
+    ///
+    /// Changing it starts the replay over from the beginning.
+    pub fn scrollback(mut self, lines: usize) -> Self {
+        self.scrollback = lines;
+        self.rewound()
+    }
+
+    /// Set how long a synchronized update may hold back output, as with
+    /// [`TerminalBuilder::sync_timeout`](crate::terminal::TerminalBuilder::sync_timeout).
+    ///
+    /// Changing it starts the replay over from the beginning.
+    pub fn sync_timeout(mut self, timeout: Duration) -> Self {
+        self.sync_timeout = timeout;
+        self.rewound()
+    }
+
+    /// Start over from the beginning with nothing shown.
+    fn rewound(mut self) -> Self {
+        self.rewind();
+        self
+    }
+
+    fn rewind(&mut self) {
+        let capacity = self
+            .events
+            .iter()
+            .map(|(_, action)| match action {
+                Action::Output(bytes) => bytes.len(),
+                Action::Resize { .. } => 0,
+            })
+            .sum();
+        self.parser = vt100::Parser::new(self.rows, self.cols, self.scrollback);
+        self.state = EmulatorState::new(self.sync_timeout);
+        self.emulator = Emulator::new(self.state.clone(), TerminalIdentity::default(), true);
+        self.output = Arc::new(Mutex::new(OutputBuffer::new(capacity)));
+        self.next = 0;
+        self.time = Duration::ZERO;
+    }
+
+    /// Length of the recording: the time of its last event.
+    pub fn duration(&self) -> Duration {
+        self.events.last().map_or(Duration::ZERO, |(time, _)| *time)
+    }
+
+    /// Number of frames in the recording.
+    pub fn frame_count(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|(_, action)| matches!(action, Action::Output(_)))
+            .count()
+    }
+
+    /// When each frame was written.
+    pub(crate) fn frame_times(&self) -> Vec<Duration> {
+        self.events
+            .iter()
+            .filter(|(_, action)| matches!(action, Action::Output(_)))
+            .map(|(time, _)| *time)
+            .collect()
+    }
+
+    /// Current position on the recording's clock.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// Whether every event of the recording has been replayed.
+    pub fn is_finished(&self) -> bool {
+        self.next == self.events.len()
+    }
+
+    /// Move to `time`, replaying every event up to and including it.
+    pub fn seek(&mut self, time: Duration) -> &mut Self {
+        if time < self.time {
+            self.rewind();
+        }
+        while self
+            .events
+            .get(self.next)
+            .is_some_and(|(at, _)| *at <= time)
+        {
+            self.step();
+        }
+        self.advance_to(time);
+        self
+    }
+
+    /// Move to just after frame `index` (counting from zero) was written.
+    ///
+    /// Returns `None`, without moving, when the recording has fewer frames.
+    pub fn seek_frame(&mut self, index: usize) -> Option<&mut Self> {
+        let event = self
+            .events
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, action))| matches!(action, Action::Output(_)))
+            .nth(index)
+            .map(|(event, _)| event)?;
+        if event < self.next {
+            self.rewind();
+        }
+        while self.next <= event {
+            self.step();
+        }
+        Some(self)
+    }
+
+    /// Get the screen at `time`.
+    pub fn screen_at(&mut self, time: Duration) -> Screen {
+        self.seek(time).screen()
+    }
+
+    /// Get the screen just after frame `index` was written.
+    pub fn screen_at_frame(&mut self, index: usize) -> Option<Screen> {
+        Some(self.seek_frame(index)?.screen())
+    }
+
+    /// Get a snapshot of the screen at the current time.
+    pub fn screen(&self) -> Screen {
+        self.state.screen(&self.parser)
+    }
+
+    /// Get a snapshot of the screen including scrollback history.
+    pub fn scrollback_screen(&mut self) -> Screen {
+        self.state.scrollback(&mut self.parser)
+    }
+
+    /// Get the window title set with OSC 0 or OSC 2.
+    pub fn title(&self) -> String {
+        self.parser.screen().title().to_string()
+    }
+
+    /// Get a mark at the end of the output replayed so far.
+    pub fn output_mark(&self) -> OutputMark {
+        self.output
+            .lock()
+            .map(|output| output.mark())
+            .unwrap_or_default()
+    }
+
+    /// Get the raw output replayed after `mark`.
+    pub fn output_since(&self, mark: OutputMark) -> CapturedOutput {
+        self.output
+            .lock()
+            .map(|output| output.since(mark))
+            .unwrap_or_default()
+    }
+
+    /// Play the recording forward until the condition holds, with the
+    /// default timeout of recording time.
+    ///
+    /// Returns the index of the matching branch (see [`WaitCondition::Any`]);
+    /// [`Replay::time`] tells when it matched.
+    pub fn wait_for(&mut self, condition: WaitCondition) -> Result<usize> {
+        self.wait(&WaitBuilder::new(condition).timeout(DEFAULT_TIMEOUT))
+    }
+
+    /// Play the recording forward until the wait's condition holds.
+    ///
+    /// Time only passes on the recording's clock: the condition is checked
+    /// after every frame, and at the moments a `ScreenStable` duration or a
+    /// synchronized update timeout would elapse. `ProcessExit` holds once the
+    /// whole recording has been replayed. On timeout the replay stays where
+    /// the wait gave up.
+    pub fn wait(&mut self, wait: &WaitBuilder) -> Result<usize> {
+        let condition = wait.condition();
+        let deadline = self.time.saturating_add(wait.get_timeout());
+        let stability = condition.stability_durations();
+
+        // For screen stability, track when output last changed the screen,
+        // so style and cursor changes count as well as text.
+        let mut stable_since = self.time;
+
+        loop {
+            let screen = if wait.get_include_scrollback() {
+                self.scrollback_screen()
+            } else {
+                self.screen()
+            };
+            let context = WaitContext {
+                exited: self.is_finished(),
+                stable_for: self.time - stable_since,
+                clipboard: self.state.clipboard(),
+                shell: self.state.shell_status(),
+                output: self.output.clone(),
+                output_patterns: wait.output_patterns(),
+            };
+            if let Some(branch) = condition.matching_branch(&screen, &context) {
+                return Ok(branch);
+            }
+            if self.time >= deadline {
+                return Err(wait.timeout_error_with_screen(screen));
+            }
+
+            let next_event = self.events.get(self.next).map(|(time, _)| *time);
+            let wake_at = stability
+                .iter()
+                .map(|duration| stable_since.saturating_add(*duration))
+                .chain(self.sync_deadline())
+                .chain(next_event)
+                .filter(|at| *at > self.time)
+                .min()
+                .map_or(deadline, |at| at.min(deadline));
+
+            let (next, held) = (self.next, self.sync_deadline());
+            if next_event.is_some_and(|at| at <= wake_at) {
+                self.step();
+            } else {
+                self.advance_to(wake_at);
+            }
+            // New output, or a held back frame shown, changes the screen.
+            if self.next != next || held.is_some_and(|at| at <= self.time) {
+                stable_since = self.time;
+            }
+        }
+    }
+
+    /// Apply the next event.
+    fn step(&mut self) {
+        let Some((time, action)) = self.events.get(self.next).cloned() else {
+            return;
+        };
+        self.next += 1;
+        self.advance_to(time);
+
+        match action {
+            Action::Output(bytes) => {
+                if let Ok(mut output) = self.output.lock() {
+                    output.push_at(&bytes, time);
+                }
+                // Replies to queries have nowhere to go.
+                let now = self.instant(self.time);
+                let _ = self.emulator.process(&mut self.parser, &bytes, now);
+            }
+            Action::Resize { cols, rows } => self.parser.set_size(rows, cols),
+        }
+    }
+
+    /// Let the clock run to `time`, showing synchronized updates that time
+    /// out on the way.
+    fn advance_to(&mut self, time: Duration) {
+        self.time = self.time.max(time);
+        let now = self.instant(self.time);
+        self.state.release_expired(&mut self.parser, now);
+    }
+
+    /// The instant standing for `time` on the recording's clock.
+    ///
+    /// Times past the end of the recording, by more than the synchronized
+    /// update timeout, all behave the same, so they are clamped to keep the
+    /// instant representable however far a seek or wait goes.
+    fn instant(&self, time: Duration) -> Instant {
+        let end = self
+            .epoch
+            .checked_add(self.duration())
+            .unwrap_or(self.epoch);
+        let horizon = end.checked_add(self.sync_timeout).unwrap_or(end);
+        self.epoch
+            .checked_add(time)
+            .map_or(horizon, |at| at.min(horizon))
+    }
+
+    /// When held back output of a synchronized update will be shown.
+    fn sync_deadline(&self) -> Option<Duration> {
+        Some(self.state.sync_deadline()? - self.epoch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAST: &str = concat!(
+        r#"{"version":2,"width":20,"height":3}"#,
+        "\n",
+        r#"[0.5,"o","loading\r\n"]"#,
+        "\n",
+        r#"[0.6,"i","q"]"#,
+        "\n",
+        r#"[1.0,"o","\u001b[?2026h\u001b[2J\u001b[Hready"]"#,
+        "\n",
+        r#"[1.2,"o","!\u001b[?2026l"]"#,
+        "\n",
+        r#"[2.0,"r","10x2"]"#,
+        "\n",
+    );
+
+    fn replay() -> Replay {
+        Replay::from_cast(CAST.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn seeks_by_time_and_frame() {
+        let mut replay = replay();
+        assert_eq!(replay.frame_count(), 3);
+        assert_eq!(replay.duration(), Duration::from_secs(2));
+
+        assert_eq!(
+            replay.screen_at(Duration::from_millis(400)).text().trim(),
+            ""
+        );
+        assert!(
+            replay
+                .screen_at(Duration::from_millis(500))
+                .contains("loading")
+        );
+        // The synchronized update is shown only once it ends.
+        assert!(
+            replay
+                .screen_at(Duration::from_millis(1100))
+                .contains("loading")
+        );
+        assert!(
+            replay
+                .screen_at(Duration::from_millis(1200))
+                .contains("ready!")
+        );
+
+        let screen = replay.screen_at_frame(1).unwrap();
+        assert_eq!(replay.time(), Duration::from_secs(1));
+        assert!(screen.contains("loading"));
+        assert!(replay.screen_at_frame(3).is_none());
+
+        assert_eq!(replay.screen_at(Duration::from_secs(5)).size.cols, 10);
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn waits_on_the_recording_clock() {
+        let mut replay = replay();
+        replay
+            .wait_for(WaitCondition::TextAppears("ready".into()))
+            .unwrap();
+        assert_eq!(replay.time(), Duration::from_millis(1200));
+
+        replay
+            .wait_for(WaitCondition::ScreenStable(Duration::from_secs(3)))
+            .unwrap();
+        assert_eq!(replay.time(), Duration::from_millis(5000));
+
+        let err = replay
+            .wait(
+                &WaitBuilder::new(WaitCondition::TextAppears("never".into()))
+                    .timeout(Duration::from_secs(1)),
+            )
+            .unwrap_err();
+        assert!(matches!(err, TermwrightError::Timeout { .. }));
+        assert_eq!(replay.time(), Duration::from_secs(6));
+
+        replay.seek(Duration::ZERO);
+        let mark = replay.output_mark();
+        replay
+            .wait_for(WaitCondition::OutputContains(mark, b"\x1b[2J".to_vec()))
+            .unwrap();
+        assert_eq!(replay.time(), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn rejects_event_times_out_of_range() {
+        let cast = concat!(
+            r#"{"version":2,"width":20,"height":3}"#,
+            "\n",
+            r#"[1e20,"o","late"]"#,
+            "\n",
+        );
+        let err = Replay::from_cast(cast.as_bytes()).err().unwrap();
+        assert!(
+            matches!(&err, TermwrightError::Protocol(message) if message.contains("1e20")),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn replays_event_times_far_in_the_future() {
+        let cast = concat!(
+            r#"{"version":2,"width":20,"height":3}"#,
+            "\n",
+            r#"[1e19,"o","x"]"#,
+            "\n",
+        );
+        let mut replay = Replay::from_cast(cast.as_bytes()).unwrap();
+        assert!(
+            replay
+                .screen_at(Duration::from_secs(1))
+                .text()
+                .trim()
+                .is_empty()
+        );
+        assert!(replay.screen_at(Duration::MAX).contains("x"));
+    }
+
+    #[test]
+    fn rejects_empty_terminal_sizes() {
+        let header = concat!(r#"{"version":2,"width":0,"height":0}"#, "\n");
+        let resize = concat!(
+            r#"{"version":2,"width":20,"height":3}"#,
+            "\n",
+            r#"[1.0,"r","0x0"]"#,
+            "\n",
+        );
+        for cast in [header, resize] {
+            let err = Replay::from_cast(cast.as_bytes()).err().unwrap();
+            assert!(matches!(err, TermwrightError::Protocol(_)), "{err:?}");
+        }
+    }
+
+    #[test]
+    fn style_and_cursor_changes_unsettle_the_screen() {
+        let cast = concat!(
+            r#"{"version":2,"width":20,"height":3}"#,
+            "\n",
+            r#"[0.0,"o","a"]"#,
+            "\n",
+            r#"[0.5,"o","\r\u001b[1ma"]"#,
+            "\n",
+            r#"[1.0,"o","\u001b[2;1H"]"#,
+            "\n",
+        );
+        let mut replay = Replay::from_cast(cast.as_bytes()).unwrap();
+        replay
+            .wait_for(WaitCondition::ScreenStable(Duration::from_millis(800)))
+            .unwrap();
+        assert_eq!(replay.time(), Duration::from_millis(1800));
+    }
+}
@@ -9,27 +9,44 @@ use serde::Serialize;
 use tokio::time::sleep;
 
 use crate::steps::{
-    ArtifactMode, ArtifactsConfig, ExpectPatternStep, ExpectTextStep, NotExpectPatternStep,
-    NotExpectTextStep, ScreenshotStep, SessionConfig, Step, StepsFile, WaitForPatternGoneStep,
-    WaitForTextGoneStep,
+    ArtifactMode, ArtifactsConfig, ExpectExitStep, ExpectPatternStep, ExpectTextStep,
+    ExpectTitleStep, NotExpectPatternStep, NotExpectTextStep, ScreenshotStep, SessionConfig,
+    ShellRunStep, SnapshotStep, Step, StepsFile, WaitForPatternGoneStep, WaitForTextGoneStep,
+    clipboard_selection,
 };
 use termwright::daemon::client::DaemonClient;
 use termwright::daemon::server::{DaemonConfig, run_daemon};
 use termwright::error::{Result, TermwrightError};
+use termwright::output::Animation;
+use termwright::screen::Screen;
+use termwright::snapshot::{self, SnapshotConfig};
 use termwright::terminal::Terminal;
 
 pub struct RunStepsOptions {
     pub connect: Option<PathBuf>,
     pub trace: bool,
+    pub record: Option<PathBuf>,
     pub no_default_env: bool,
     pub no_osc_emulation: bool,
 }
 
 pub async fn run_steps(path: &Path, options: RunStepsOptions) -> Result<()> {
     let steps_file = StepsFile::load(path)?;
+    let artifacts_dir = prepare_artifacts_dir(&steps_file.artifacts, options.trace)?;
+    let recording = recording_path(&steps_file.artifacts, artifacts_dir.as_deref(), &options);
+    // Snapshots live next to the steps file, like test sources.
+    let snapshot_dir = path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(snapshot::DEFAULT_DIR);
 
     let (client, daemon_handle) = if let Some(socket) = options.connect.clone() {
         let client = connect_daemon(&socket).await?;
+        if let Some(path) = recording.as_ref() {
+            // The daemon may run in another directory.
+            let path = std::path::absolute(path).map_err(TermwrightError::Pty)?;
+            client.record_start(path, false).await?;
+        }
         (client, None)
     } else {
         let session = steps_file.session.as_ref().ok_or_else(|| {
@@ -40,29 +57,35 @@ pub async fn run_steps(path: &Path, options: RunStepsOptions) -> Result<()> {
 
         let disable_default_env = options.no_default_env || session.no_default_env;
         let disable_osc_emulation = options.no_osc_emulation || session.no_osc_emulation;
-        let (socket, handle) =
-            spawn_daemon(session, disable_default_env, disable_osc_emulation).await?;
+        let (socket, handle) = spawn_daemon(
+            session,
+            disable_default_env,
+            disable_osc_emulation,
+            recording.as_deref(),
+        )
+        .await?;
         let client = connect_daemon(&socket).await?;
         (client, Some(handle))
     };
 
-    let artifacts_dir = prepare_artifacts_dir(&steps_file.artifacts, options.trace)?;
     let mut step_index = 0usize;
     let mut trace_entries = Vec::new();
+    let mut trace_screens = Vec::new();
+    let run_started = Instant::now();
 
     for step in &steps_file.steps {
         step_index += 1;
         let trace_before = if options.trace {
-            Some(capture_trace_snapshot(&client).await?)
+            Some(capture_trace_snapshot(&client, run_started).await?)
         } else {
             None
         };
         let started = Instant::now();
 
-        let result = execute_step(&client, step).await;
+        let result = execute_step(&client, step, &snapshot_dir).await;
 
         let trace_after = if options.trace {
-            Some(capture_trace_snapshot(&client).await?)
+            Some(capture_trace_snapshot(&client, run_started).await?)
         } else {
             None
         };
@@ -76,6 +99,7 @@ pub async fn run_steps(path: &Path, options: RunStepsOptions) -> Result<()> {
                 trace_after.as_ref(),
                 result.as_ref().err(),
             ));
+            trace_screens.extend(trace_before.into_iter().chain(trace_after));
         }
 
         if let Err(err) = result {
@@ -87,8 +111,12 @@ pub async fn run_steps(path: &Path, options: RunStepsOptions) -> Result<()> {
             if options.trace {
                 if let Some(dir) = artifacts_dir.as_ref() {
                     let _ = write_trace(dir, &trace_entries);
+                    write_trace_animation(dir, &trace_screens);
                 }
             }
+            if recording.is_some() {
+                let _ = client.record_stop().await;
+            }
             if let Some(handle) = daemon_handle {
                 let _ = client.close().await;
                 let _ = handle.await;
@@ -116,6 +144,18 @@ pub async fn run_steps(path: &Path, options: RunStepsOptions) -> Result<()> {
     if options.trace {
         if let Some(dir) = artifacts_dir.as_ref() {
             write_trace(dir, &trace_entries)?;
+            write_trace_animation(dir, &trace_screens);
+        }
+    }
+
+    if recording.is_some() {
+        client.record_stop().await?;
+        // A recording made only for the artifacts is not needed when every
+        // step passed.
+        if options.record.is_none() && steps_file.artifacts.mode == ArtifactMode::OnFailure {
+            if let Some(path) = recording.as_ref() {
+                let _ = fs::remove_file(path);
+            }
         }
     }
 
@@ -127,20 +167,25 @@ pub async fn run_steps(path: &Path, options: RunStepsOptions) -> Result<()> {
     Ok(())
 }
 
-async fn execute_step(client: &DaemonClient, step: &Step) -> Result<()> {
+async fn execute_step(client: &DaemonClient, step: &Step, snapshot_dir: &Path) -> Result<()> {
     match step {
         Step::WaitForText { wait_for_text } => {
-            client
-                .wait_for_text(&wait_for_text.text, timeout(wait_for_text.timeout_ms))
-                .await
+            wait_text(
+                client,
+                &wait_for_text.text,
+                wait_for_text.timeout_ms,
+                wait_for_text.scrollback,
+            )
+            .await
         }
         Step::WaitForPattern { wait_for_pattern } => {
-            client
-                .wait_for_pattern(
-                    &wait_for_pattern.pattern,
-                    timeout(wait_for_pattern.timeout_ms),
-                )
-                .await
+            wait_pattern(
+                client,
+                &wait_for_pattern.pattern,
+                wait_for_pattern.timeout_ms,
+                wait_for_pattern.scrollback,
+            )
+            .await
         }
         Step::WaitForIdle { wait_for_idle } => {
             client
@@ -158,6 +203,12 @@ async fn execute_step(client: &DaemonClient, step: &Step) -> Result<()> {
         } => wait_for_pattern_gone_step(client, wait_for_pattern_gone).await,
         Step::Press { press } => client.press(&press.key).await,
         Step::Type { r#type } => client.r#type(&r#type.text).await,
+        Step::Paste { paste } => client.paste(&paste.text).await,
+        Step::SetClipboard { set_clipboard } => {
+            let selection = clipboard_selection(set_clipboard.selection.as_deref())
+                .map_err(TermwrightError::Protocol)?;
+            client.set_clipboard(selection, &set_clipboard.text).await
+        }
         Step::Hotkey { hotkey } => {
             client
                 .hotkey(
@@ -167,7 +218,36 @@ async fn execute_step(client: &DaemonClient, step: &Step) -> Result<()> {
                 )
                 .await
         }
+        Step::ShellRun { shell_run } => shell_run_step(client, shell_run).await,
         Step::ExpectText { expect_text } => expect_text_step(client, expect_text).await,
+        Step::ExpectTitle { expect_title } => expect_title_step(client, expect_title).await,
+        Step::ExpectClipboard { expect_clipboard } => {
+            let selection = clipboard_selection(expect_clipboard.selection.as_deref())
+                .map_err(TermwrightError::Protocol)?;
+            client
+                .wait_for_clipboard(
+                    selection,
+                    &expect_clipboard.text,
+                    timeout(expect_clipboard.timeout_ms),
+                )
+                .await
+        }
+        Step::ExpectLink { expect_link } => {
+            client
+                .wait_for_link(
+                    &expect_link.text,
+                    &expect_link.uri,
+                    timeout(expect_link.timeout_ms),
+                )
+                .await
+        }
+        Step::ExpectBell { expect_bell } => {
+            let since = expect_bell.count.unwrap_or(1).saturating_sub(1);
+            client
+                .wait_for_bell(Some(since), timeout(expect_bell.timeout_ms))
+                .await
+                .map(|_| ())
+        }
         Step::ExpectPattern { expect_pattern } => expect_pattern_step(client, expect_pattern).await,
         Step::NotExpectText { not_expect_text } => {
             not_expect_text_step(client, not_expect_text).await
@@ -175,20 +255,96 @@ async fn execute_step(client: &DaemonClient, step: &Step) -> Result<()> {
         Step::NotExpectPattern { not_expect_pattern } => {
             not_expect_pattern_step(client, not_expect_pattern).await
         }
+        Step::ExpectExit { expect_exit } => expect_exit_step(client, expect_exit).await,
         Step::Screenshot { .. } => Ok(()),
+        Step::Snapshot { snapshot } => snapshot_step(client, snapshot, snapshot_dir).await,
     }
 }
 
+async fn snapshot_step(client: &DaemonClient, step: &SnapshotStep, dir: &Path) -> Result<()> {
+    let screen = client.screen().await?;
+    SnapshotConfig::new()
+        .dir(dir)
+        .styles(step.styles)
+        .assert(&step.name, &screen)
+}
+
+async fn shell_run_step(client: &DaemonClient, step: &ShellRunStep) -> Result<()> {
+    let output = client
+        .shell_run(&step.command, timeout(step.timeout_ms))
+        .await?;
+
+    if let Some(code) = step.exit_code {
+        if output.exit_code != Some(code) {
+            return Err(TermwrightError::Protocol(format!(
+                "expected `{}` to exit with code {code}, got {:?}",
+                step.command, output.exit_code
+            )));
+        }
+    }
+
+    if let Some(text) = step.output.as_deref() {
+        if !output.output_text.contains(text) {
+            return Err(TermwrightError::Protocol(format!(
+                "expected output of `{}` to contain '{text}', got:\n{}",
+                step.command, output.output_text
+            )));
+        }
+    }
+
+    Ok(())
+}
+
 async fn expect_text_step(client: &DaemonClient, step: &ExpectTextStep) -> Result<()> {
-    client
-        .wait_for_text(&step.text, timeout(step.timeout_ms))
-        .await
+    wait_text(client, &step.text, step.timeout_ms, step.scrollback).await
+}
+
+async fn expect_title_step(client: &DaemonClient, step: &ExpectTitleStep) -> Result<()> {
+    match (&step.text, &step.pattern) {
+        (Some(text), _) => client.wait_for_title(text, timeout(step.timeout_ms)).await,
+        (None, Some(pattern)) => {
+            client
+                .wait_for_title_pattern(pattern, timeout(step.timeout_ms))
+                .await
+        }
+        (None, None) => Err(TermwrightError::Protocol(
+            "expectTitle needs text or pattern".to_string(),
+        )),
+    }
 }
 
 async fn expect_pattern_step(client: &DaemonClient, step: &ExpectPatternStep) -> Result<()> {
-    client
-        .wait_for_pattern(&step.pattern, timeout(step.timeout_ms))
-        .await
+    wait_pattern(client, &step.pattern, step.timeout_ms, step.scrollback).await
+}
+
+async fn wait_text(
+    client: &DaemonClient,
+    text: &str,
+    timeout_ms: Option<u64>,
+    scrollback: bool,
+) -> Result<()> {
+    if scrollback {
+        client
+            .wait_for_text_in_scrollback(text, timeout(timeout_ms))
+            .await
+    } else {
+        client.wait_for_text(text, timeout(timeout_ms)).await
+    }
+}
+
+async fn wait_pattern(
+    client: &DaemonClient,
+    pattern: &str,
+    timeout_ms: Option<u64>,
+    scrollback: bool,
+) -> Result<()> {
+    if scrollback {
+        client
+            .wait_for_pattern_in_scrollback(pattern, timeout(timeout_ms))
+            .await
+    } else {
+        client.wait_for_pattern(pattern, timeout(timeout_ms)).await
+    }
 }
 
 async fn wait_for_text_gone_step(client: &DaemonClient, step: &WaitForTextGoneStep) -> Result<()> {
@@ -214,6 +370,38 @@ async fn not_expect_pattern_step(client: &DaemonClient, step: &NotExpectPatternS
     client.not_expect_pattern(&step.pattern).await
 }
 
+async fn expect_exit_step(client: &DaemonClient, step: &ExpectExitStep) -> Result<()> {
+    let status = client.wait_for_exit(timeout(step.timeout_ms)).await?;
+
+    if let Some(code) = step.code {
+        if status.code != Some(code) {
+            return Err(TermwrightError::Protocol(format!(
+                "expected process to exit with code {code}, but it {status}"
+            )));
+        }
+    }
+
+    if let Some(signal) = step.signal.as_deref() {
+        let expected = normalize_signal(signal);
+        if status.signal.as_deref() != Some(expected.as_str()) {
+            return Err(TermwrightError::Protocol(format!(
+                "expected process to be killed by {expected}, but it {status}"
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn normalize_signal(signal: &str) -> String {
+    let upper = signal.trim().to_uppercase();
+    if upper.starts_with("SIG") {
+        upper
+    } else {
+        format!("SIG{upper}")
+    }
+}
+
 fn timeout(timeout_ms: Option<u64>) -> Option<Duration> {
     timeout_ms.map(Duration::from_millis)
 }
@@ -222,6 +410,7 @@ async fn spawn_daemon(
     session: &SessionConfig,
     no_default_env: bool,
     no_osc_emulation: bool,
+    record: Option<&Path>,
 ) -> Result<(PathBuf, tokio::task::JoinHandle<Result<()>>)> {
     let (command, args) = session.command_and_args()?;
     let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
@@ -239,6 +428,12 @@ async fn spawn_daemon(
     if let Some(cwd) = session.cwd.as_ref() {
         builder = builder.working_dir(cwd);
     }
+    if let Some(lines) = session.scrollback {
+        builder = builder.scrollback(lines);
+    }
+    if let Some(path) = record {
+        builder = builder.record_to(path);
+    }
 
     let terminal = builder.spawn(&command, &args_ref).await?;
 
@@ -280,6 +475,22 @@ fn prepare_artifacts_dir(config: &ArtifactsConfig, trace: bool) -> Result<Option
     Ok(Some(run_dir))
 }
 
+/// Where to record the session: the `--record` path, or `session.cast` in
+/// the artifacts directory so a failed run can be replayed.
+fn recording_path(
+    config: &ArtifactsConfig,
+    artifacts_dir: Option<&Path>,
+    options: &RunStepsOptions,
+) -> Option<PathBuf> {
+    if options.record.is_some() {
+        return options.record.clone();
+    }
+    if config.mode == ArtifactMode::Off {
+        return None;
+    }
+    artifacts_dir.map(|dir| dir.join("session.cast"))
+}
+
 async fn capture_artifacts(
     client: &DaemonClient,
     dir: &Path,
@@ -351,12 +562,17 @@ impl TraceEntry {
 
 struct TraceSnapshot {
     hash: u64,
+    /// When the snapshot was taken, since the run started.
+    at: Duration,
+    screen: Screen,
 }
 
-async fn capture_trace_snapshot(client: &DaemonClient) -> Result<TraceSnapshot> {
-    let text = client.screen_text().await?;
+async fn capture_trace_snapshot(client: &DaemonClient, started: Instant) -> Result<TraceSnapshot> {
+    let screen = client.screen().await?;
     Ok(TraceSnapshot {
-        hash: hash_text(&text),
+        hash: hash_text(&screen.text()),
+        at: started.elapsed(),
+        screen,
     })
 }
 
@@ -375,12 +591,33 @@ fn step_label(step: &Step) -> String {
         Step::WaitForPatternGone { .. } => "waitForPatternGone".to_string(),
         Step::Press { .. } => "press".to_string(),
         Step::Type { .. } => "type".to_string(),
+        Step::Paste { .. } => "paste".to_string(),
+        Step::SetClipboard { .. } => "setClipboard".to_string(),
         Step::Hotkey { .. } => "hotkey".to_string(),
+        Step::ShellRun { .. } => "shellRun".to_string(),
         Step::ExpectText { .. } => "expectText".to_string(),
+        Step::ExpectTitle { .. } => "expectTitle".to_string(),
+        Step::ExpectBell { .. } => "expectBell".to_string(),
+        Step::ExpectClipboard { .. } => "expectClipboard".to_string(),
+        Step::ExpectLink { .. } => "expectLink".to_string(),
         Step::ExpectPattern { .. } => "expectPattern".to_string(),
         Step::NotExpectText { .. } => "notExpectText".to_string(),
         Step::NotExpectPattern { .. } => "notExpectPattern".to_string(),
+        Step::ExpectExit { .. } => "expectExit".to_string(),
         Step::Screenshot { .. } => "screenshot".to_string(),
+        Step::Snapshot { .. } => "snapshot".to_string(),
+    }
+}
+
+/// Render the trace snapshots as `trace.gif`. Rendering needs a system
+/// font, so a failure is reported without failing the run.
+fn write_trace_animation(dir: &Path, snapshots: &[TraceSnapshot]) {
+    let mut animation = Animation::new();
+    for snapshot in snapshots {
+        animation.push(snapshot.at, snapshot.screen.clone());
+    }
+    if let Err(err) = animation.save(dir.join("trace.gif")) {
+        eprintln!("Could not render trace.gif: {err}");
     }
 }
 
@@ -35,10 +35,11 @@ impl Size {
 }
 
 /// Color representation.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type", content = "value")]
 pub enum Color {
     /// Default terminal color.
+    #[default]
     Default,
     /// Indexed color (0-255).
     Indexed(u8),
@@ -46,12 +47,6 @@ pub enum Color {
     Rgb(u8, u8, u8),
 }
 
-impl Default for Color {
-    fn default() -> Self {
-        Color::Default
-    }
-}
-
 impl From<vt100::Color> for Color {
     fn from(color: vt100::Color) -> Self {
         match color {
@@ -62,6 +57,172 @@ impl From<vt100::Color> for Color {
     }
 }
 
+/// Mouse events the application asked the terminal to report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MouseMode {
+    /// Mouse reporting is disabled.
+    #[default]
+    None,
+    /// Button presses only (X10, mode 9).
+    Press,
+    /// Button presses and releases (mode 1000).
+    PressRelease,
+    /// Presses, releases and motion while a button is held (mode 1002).
+    ButtonMotion,
+    /// Presses, releases and all motion (mode 1003).
+    AnyMotion,
+}
+
+impl From<vt100::MouseProtocolMode> for MouseMode {
+    fn from(mode: vt100::MouseProtocolMode) -> Self {
+        match mode {
+            vt100::MouseProtocolMode::None => MouseMode::None,
+            vt100::MouseProtocolMode::Press => MouseMode::Press,
+            vt100::MouseProtocolMode::PressRelease => MouseMode::PressRelease,
+            vt100::MouseProtocolMode::ButtonMotion => MouseMode::ButtonMotion,
+            vt100::MouseProtocolMode::AnyMotion => MouseMode::AnyMotion,
+        }
+    }
+}
+
+/// Encoding the application asked mouse reports to use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MouseEncoding {
+    /// Single-byte coordinates.
+    #[default]
+    Default,
+    /// UTF-8 coordinates (mode 1005).
+    Utf8,
+    /// SGR coordinates (mode 1006).
+    Sgr,
+    /// urxvt decimal coordinates (mode 1015).
+    Urxvt,
+    /// SGR with pixel coordinates (mode 1016).
+    SgrPixels,
+}
+
+impl From<vt100::MouseProtocolEncoding> for MouseEncoding {
+    fn from(encoding: vt100::MouseProtocolEncoding) -> Self {
+        match encoding {
+            vt100::MouseProtocolEncoding::Default => MouseEncoding::Default,
+            vt100::MouseProtocolEncoding::Utf8 => MouseEncoding::Utf8,
+            vt100::MouseProtocolEncoding::Sgr => MouseEncoding::Sgr,
+        }
+    }
+}
+
+/// Cursor shape set with DECSCUSR.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CursorShape {
+    /// A block covering the whole cell.
+    #[default]
+    Block,
+    /// A line under the cell.
+    Underline,
+    /// A vertical bar at the left of the cell.
+    Bar,
+}
+
+/// Cursor shape and blinking set with DECSCUSR (`CSI Ps SP q`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CursorStyle {
+    /// The cursor shape.
+    pub shape: CursorShape,
+    /// Whether the cursor blinks.
+    pub blinking: bool,
+}
+
+impl Default for CursorStyle {
+    /// A blinking block, which `CSI 0 SP q` also selects.
+    fn default() -> Self {
+        Self {
+            shape: CursorShape::Block,
+            blinking: true,
+        }
+    }
+}
+
+impl CursorStyle {
+    /// Decode a DECSCUSR parameter (0 to 6).
+    pub fn from_decscusr(ps: u16) -> Option<Self> {
+        let shape = match ps {
+            0..=2 => CursorShape::Block,
+            3 | 4 => CursorShape::Underline,
+            5 | 6 => CursorShape::Bar,
+            _ => return None,
+        };
+        Some(Self {
+            shape,
+            blinking: ps == 0 || ps % 2 == 1,
+        })
+    }
+
+    /// The DECSCUSR parameter selecting this style.
+    pub fn decscusr(self) -> u16 {
+        let steady = match self.shape {
+            CursorShape::Block => 2,
+            CursorShape::Underline => 4,
+            CursorShape::Bar => 6,
+        };
+        if self.blinking { steady - 1 } else { steady }
+    }
+}
+
+/// Terminal modes set by the application.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TerminalModes {
+    /// The alternate screen is active (mode 1049/47).
+    pub alternate_screen: bool,
+    /// The cursor is hidden (DECTCEM reset).
+    pub cursor_hidden: bool,
+    /// Cursor keys send application sequences (DECCKM).
+    pub application_cursor: bool,
+    /// The keypad sends application sequences (DECKPAM).
+    pub application_keypad: bool,
+    /// Pasted text is wrapped in bracketed paste markers (mode 2004).
+    pub bracketed_paste: bool,
+    /// Mouse events being reported.
+    pub mouse_mode: MouseMode,
+    /// Encoding used for mouse reports.
+    pub mouse_encoding: MouseEncoding,
+    /// Active kitty keyboard protocol flags (`CSI > flags u`).
+    #[serde(default)]
+    pub kitty_keyboard_flags: u8,
+    /// xterm modifyOtherKeys level (`CSI > 4 ; level m`).
+    #[serde(default)]
+    pub modify_other_keys: u8,
+    /// Cursor shape and blinking (DECSCUSR).
+    #[serde(default)]
+    pub cursor_style: CursorStyle,
+}
+
+impl TerminalModes {
+    /// Read the current modes from a vt100 screen.
+    ///
+    /// vt100 does not track keyboard enhancements, the urxvt and SGR-pixels
+    /// mouse encodings or the cursor style, so those are left at their
+    /// defaults.
+    pub fn from_vt100(screen: &vt100::Screen) -> Self {
+        Self {
+            alternate_screen: screen.alternate_screen(),
+            cursor_hidden: screen.hide_cursor(),
+            application_cursor: screen.application_cursor(),
+            application_keypad: screen.application_keypad(),
+            bracketed_paste: screen.bracketed_paste(),
+            mouse_mode: screen.mouse_protocol_mode().into(),
+            mouse_encoding: screen.mouse_protocol_encoding().into(),
+            kitty_keyboard_flags: 0,
+            modify_other_keys: 0,
+            cursor_style: CursorStyle::default(),
+        }
+    }
+
+    /// Returns true if the application has enabled any mouse reporting.
+    pub fn mouse_reporting(&self) -> bool {
+        self.mouse_mode != MouseMode::None
+    }
+}
+
 /// Cell attributes (bold, italic, etc.).
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CellAttributes {
@@ -75,6 +236,16 @@ pub struct CellAttributes {
     pub inverse: bool,
 }
 
+/// An OSC 8 hyperlink target.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Hyperlink {
+    /// The linked URI.
+    pub uri: String,
+    /// The `id` parameter grouping cells that belong to the same link.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
 /// A single cell on the terminal screen.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Cell {
@@ -86,6 +257,9 @@ pub struct Cell {
     pub bg: Color,
     /// Text attributes.
     pub attrs: CellAttributes,
+    /// The hyperlink this cell was printed inside, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hyperlink: Option<Hyperlink>,
 }
 
 impl Default for Cell {
@@ -95,6 +269,7 @@ impl Default for Cell {
             fg: Color::Default,
             bg: Color::Default,
             attrs: CellAttributes::default(),
+            hyperlink: None,
         }
     }
 }
@@ -106,6 +281,18 @@ pub struct Screen {
     pub size: Size,
     /// Cursor position.
     pub cursor: Position,
+    /// Terminal modes at the time of the snapshot.
+    #[serde(default)]
+    pub modes: TerminalModes,
+    /// Window title set with OSC 0 or OSC 2.
+    #[serde(default)]
+    pub title: String,
+    /// Icon name set with OSC 0 or OSC 1.
+    #[serde(default)]
+    pub icon_name: String,
+    /// Number of audible (BEL) and visual (`ESC g`) bells received so far.
+    #[serde(default)]
+    pub bell_count: usize,
     /// Screen content as rows of cells.
     cells: Vec<Vec<Cell>>,
 }
@@ -132,6 +319,7 @@ impl Screen {
                             underline: cell.underline(),
                             inverse: cell.inverse(),
                         },
+                        hyperlink: None,
                     });
                 } else {
                     row_cells.push(Cell::default());
@@ -143,10 +331,47 @@ impl Screen {
         Self {
             size: Size::new(cols, rows),
             cursor: Position::new(cursor_pos.0, cursor_pos.1),
+            modes: TerminalModes::from_vt100(screen),
+            title: screen.title().to_string(),
+            icon_name: screen.icon_name().to_string(),
+            bell_count: screen.audible_bell_count() + screen.visual_bell_count(),
             cells,
         }
     }
 
+    /// Create a snapshot that includes the scrollback history.
+    ///
+    /// The returned screen contains every history line above the visible
+    /// rows, so row 0 is the oldest retained line. The cursor position is
+    /// shifted down by the number of history lines. While the alternate
+    /// screen is active only the visible rows are returned, since the
+    /// alternate screen has no scrollback.
+    pub fn with_history(parser: &mut vt100::Parser) -> Self {
+        // Offsets are clamped to the available history.
+        parser.set_scrollback(usize::MAX);
+        let history = parser.screen().scrollback();
+        parser.set_scrollback(0);
+
+        if history == 0 {
+            return Self::from_vt100(parser.screen());
+        }
+
+        // vt100 cannot scroll further back than the screen height, so grow
+        // the grid temporarily to bring the whole history into view at once.
+        let (rows, cols) = parser.screen().size();
+        let total = (usize::from(rows) + history).min(usize::from(u16::MAX)) as u16;
+        let history = total - rows;
+
+        parser.set_size(total, cols);
+        parser.set_scrollback(usize::from(history));
+        let mut screen = Self::from_vt100(parser.screen());
+        parser.set_scrollback(0);
+        parser.set_size(rows, cols);
+
+        screen.cursor.row = screen.cursor.row.saturating_add(history);
+        screen
+    }
+
     /// Get the full screen content as plain text.
     pub fn text(&self) -> String {
         self.cells
@@ -214,6 +439,80 @@ impl Screen {
         self.size
     }
 
+    /// Get the terminal modes.
+    pub fn modes(&self) -> TerminalModes {
+        self.modes
+    }
+
+    /// Get the cursor shape and blinking.
+    pub fn cursor_style(&self) -> CursorStyle {
+        self.modes.cursor_style
+    }
+
+    /// Returns true unless the application hid the cursor.
+    pub fn cursor_visible(&self) -> bool {
+        !self.modes.cursor_hidden
+    }
+
+    /// Get the window title.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Get the icon name.
+    pub fn icon_name(&self) -> &str {
+        &self.icon_name
+    }
+
+    /// Get the number of bells received so far.
+    pub fn bell_count(&self) -> usize {
+        self.bell_count
+    }
+
+    /// Attach a hyperlink to the cell at `position`.
+    pub(crate) fn set_hyperlink(&mut self, position: Position, hyperlink: Hyperlink) {
+        if let Some(cell) = self
+            .cells
+            .get_mut(position.row as usize)
+            .and_then(|row| row.get_mut(position.col as usize))
+        {
+            cell.hyperlink = Some(hyperlink);
+        }
+    }
+
+    /// Find the text printed inside OSC 8 hyperlinks.
+    ///
+    /// Each span covers consecutive cells on one row with the same link, so
+    /// a link that wraps onto the next row yields one span per row.
+    pub fn hyperlinks(&self) -> Vec<HyperlinkSpan> {
+        let mut spans = Vec::new();
+        for (row_idx, row) in self.cells.iter().enumerate() {
+            let mut col = 0;
+            while col < row.len() {
+                let Some(link) = &row[col].hyperlink else {
+                    col += 1;
+                    continue;
+                };
+                let start = col;
+                while col < row.len() && row[col].hyperlink.as_ref() == Some(link) {
+                    col += 1;
+                }
+                spans.push(HyperlinkSpan {
+                    uri: link.uri.clone(),
+                    id: link.id.clone(),
+                    text: row[start..col].iter().map(|c| c.char).collect(),
+                    region: Region::from_ranges(
+                        row_idx as u16,
+                        row_idx as u16 + 1,
+                        start as u16,
+                        col as u16,
+                    ),
+                });
+            }
+        }
+        spans
+    }
+
     /// Serialize to JSON.
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string_pretty(self)
@@ -222,15 +521,21 @@ impl Screen {
     /// Serialize to compact JSON (text only, no cell details).
     pub fn to_json_compact(&self) -> Result<String, serde_json::Error> {
         #[derive(Serialize)]
-        struct CompactScreen {
+        struct CompactScreen<'a> {
             size: Size,
             cursor: Position,
+            modes: TerminalModes,
+            title: &'a str,
             lines: Vec<String>,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            hyperlinks: Vec<HyperlinkSpan>,
         }
 
         let compact = CompactScreen {
             size: self.size,
             cursor: self.cursor,
+            modes: self.modes,
+            title: &self.title,
             lines: self
                 .cells
                 .iter()
@@ -242,11 +547,42 @@ impl Screen {
                         .to_string()
                 })
                 .collect(),
+            hyperlinks: self.hyperlinks(),
         };
 
         serde_json::to_string_pretty(&compact)
     }
 
+    /// Render as the text and escape sequences that draw this screen.
+    ///
+    /// Colors and attributes are set only where they change, links are
+    /// written as OSC 8, and the output ends by moving the cursor back to
+    /// its place. Printed to a real terminal, it shows the screen as the
+    /// application drew it; [`from_ansi`](Self::from_ansi) reads it back.
+    pub fn to_ansi(&self) -> String {
+        crate::output::ansi::to_ansi(self)
+    }
+
+    /// Build a screen by drawing ANSI text on a blank terminal of the given
+    /// size.
+    pub fn from_ansi(ansi: &str, cols: u16, rows: u16) -> Self {
+        crate::output::ansi::from_ansi(ansi, cols, rows)
+    }
+
+    /// Render as an SVG image.
+    ///
+    /// The text stays selectable and searchable, with colors taken from
+    /// the same palette as screenshots.
+    pub fn to_svg(&self) -> String {
+        crate::output::markup::to_svg(self)
+    }
+
+    /// Render as a standalone HTML page, with the same colors as
+    /// [`to_svg`](Self::to_svg).
+    pub fn to_html(&self) -> String {
+        crate::output::markup::to_html(self)
+    }
+
     /// Extract a region of the screen by coordinates.
     ///
     /// Returns a Region that can be used to extract text or cells.
@@ -480,7 +816,7 @@ fn is_vertical_line(c: char) -> bool {
 }
 
 /// A text match found on the screen.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TextMatch {
     /// Position of the match.
     pub position: Position,
@@ -490,8 +826,22 @@ pub struct TextMatch {
     pub length: usize,
 }
 
+/// Text printed inside an OSC 8 hyperlink.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HyperlinkSpan {
+    /// The linked URI.
+    pub uri: String,
+    /// The link's `id` parameter, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// The linked text.
+    pub text: String,
+    /// The cells the text occupies.
+    pub region: Region,
+}
+
 /// A rectangular region of the screen.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Region {
     /// Top-left corner.
     pub start: Position,
@@ -548,4 +898,98 @@ mod tests {
     fn test_color_default() {
         assert_eq!(Color::default(), Color::Default);
     }
+
+    #[test]
+    fn test_with_history_includes_scrolled_lines() {
+        let mut parser = vt100::Parser::new(3, 20, 100);
+        for i in 0..10 {
+            parser.process(format!("line {i}\r\n").as_bytes());
+        }
+
+        let visible = Screen::from_vt100(parser.screen());
+        assert!(!visible.contains("line 0"));
+
+        let screen = Screen::with_history(&mut parser);
+        assert_eq!(screen.size.rows, 11);
+        assert_eq!(screen.line(0).as_deref(), Some("line 0"));
+        assert_eq!(screen.line(9).as_deref(), Some("line 9"));
+        assert_eq!(screen.cursor().row, 10);
+
+        // The live parser is left untouched.
+        assert_eq!(parser.screen().size(), (3, 20));
+        assert_eq!(parser.screen().scrollback(), 0);
+        assert_eq!(Screen::from_vt100(parser.screen()).text(), visible.text());
+    }
+
+    #[test]
+    fn test_title_and_bells() {
+        let mut parser = vt100::Parser::new(3, 20, 0);
+        parser.process(b"\x1b]0;both\x07\x1b]2;Building 50%\x1b\\\x07\x1bg");
+        let screen = Screen::from_vt100(parser.screen());
+        assert_eq!(screen.title(), "Building 50%");
+        assert_eq!(screen.icon_name(), "both");
+        assert_eq!(screen.bell_count(), 2);
+    }
+
+    #[test]
+    fn test_cursor_style_decscusr_round_trip() {
+        for ps in 1..=6 {
+            assert_eq!(CursorStyle::from_decscusr(ps).unwrap().decscusr(), ps);
+        }
+        assert_eq!(CursorStyle::from_decscusr(0), Some(CursorStyle::default()));
+        assert_eq!(CursorStyle::from_decscusr(7), None);
+    }
+
+    #[test]
+    fn test_hyperlinks_group_linked_cells_per_row() {
+        let mut parser = vt100::Parser::new(2, 4, 0);
+        parser.process(b"ab\r\ncd");
+        let mut screen = Screen::from_vt100(parser.screen());
+        let link = Hyperlink {
+            uri: "https://example.com".to_string(),
+            id: None,
+        };
+        for (row, col) in [(0, 1), (1, 0), (1, 1)] {
+            screen.set_hyperlink(Position::new(row, col), link.clone());
+        }
+
+        let spans = screen.hyperlinks();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].text, "b");
+        assert_eq!(spans[0].region, Region::from_ranges(0, 1, 1, 2));
+        assert_eq!(spans[1].text, "cd");
+        assert_eq!(spans[1].region, Region::from_ranges(1, 2, 0, 2));
+        assert!(
+            screen
+                .to_json_compact()
+                .unwrap()
+                .contains(r#""uri": "https://example.com""#)
+        );
+    }
+
+    #[test]
+    fn test_terminal_modes() {
+        let mut parser = vt100::Parser::new(3, 20, 0);
+        assert_eq!(
+            Screen::from_vt100(parser.screen()).modes(),
+            TerminalModes::default()
+        );
+
+        parser.process(b"\x1b[?1049h\x1b[?25l\x1b[?1h\x1b=\x1b[?2004h\x1b[?1002h\x1b[?1006h");
+        let modes = Screen::from_vt100(parser.screen()).modes();
+        assert!(modes.alternate_screen);
+        assert!(modes.cursor_hidden);
+        assert!(modes.application_cursor);
+        assert!(modes.application_keypad);
+        assert!(modes.bracketed_paste);
+        assert_eq!(modes.mouse_mode, MouseMode::ButtonMotion);
+        assert_eq!(modes.mouse_encoding, MouseEncoding::Sgr);
+        assert!(modes.mouse_reporting());
+
+        parser.process(b"\x1b[?1049l\x1b[?25h\x1b[?1002l");
+        let modes = Screen::from_vt100(parser.screen()).modes();
+        assert!(!modes.alternate_screen);
+        assert!(!modes.cursor_hidden);
+        assert!(!modes.mouse_reporting());
+    }
 }
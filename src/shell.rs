@@ -0,0 +1,104 @@
+//! Driving shells and REPLs through shell integration marks.
+//!
+//! Shells that support FinalTerm-style integration (OSC 133) mark where the
+//! prompt, the command line and each command's output begin and end, and
+//! report their working directory with OSC 7. [`ShellSession`] uses those
+//! marks to run a command and collect exactly its output, instead of guessing
+//! when it has finished from idle time.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::terminal::Terminal;
+use crate::wait::WaitCondition;
+
+/// The result of a command run with [`ShellSession::run_command`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandOutput {
+    /// Output printed between the `C` and `D` marks, as it appeared on
+    /// screen without colors, with trailing blank lines removed.
+    pub output_text: String,
+    /// Exit code from the `D` mark, if the shell reported one.
+    pub exit_code: Option<i32>,
+    /// Working directory last reported with OSC 7, if any.
+    pub cwd: Option<PathBuf>,
+}
+
+/// Runs commands in a shell that emits OSC 133 prompt marks.
+///
+/// The shell must print `ESC ] 133 ; A` before each prompt, `133;C` when a
+/// command starts and `133;D;<exit code>` when it ends (`B`, marking the
+/// start of input, is optional). Most shells can do this through their
+/// prompt and pre-exec hooks.
+///
+/// ```rust,no_run
+/// # use termwright::prelude::*;
+/// # async fn example(term: &Terminal) -> Result<()> {
+/// let output = term.shell().run_command("ls missing").await?;
+/// assert_eq!(output.exit_code, Some(2));
+/// # Ok(())
+/// # }
+/// ```
+pub struct ShellSession<'a> {
+    terminal: &'a Terminal,
+    timeout: Duration,
+}
+
+impl<'a> ShellSession<'a> {
+    /// Wrap a terminal running a shell.
+    pub fn new(terminal: &'a Terminal) -> Self {
+        Self {
+            terminal,
+            timeout: crate::wait::DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Set how long to wait for the prompt and for each command to finish.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Get the terminal the shell runs in.
+    pub fn terminal(&self) -> &'a Terminal {
+        self.terminal
+    }
+
+    /// Get the working directory the shell last reported with OSC 7.
+    pub fn cwd(&self) -> Option<PathBuf> {
+        self.terminal.shell_command().1
+    }
+
+    /// Wait for the shell to show a prompt.
+    pub async fn wait_for_prompt(&self) -> Result<()> {
+        self.terminal
+            .wait_for(WaitCondition::PromptReady)
+            .timeout(self.timeout)
+            .await
+    }
+
+    /// Type a command at the prompt and wait for the shell to report that
+    /// it finished.
+    pub async fn run_command(&self, cmd: &str) -> Result<CommandOutput> {
+        self.wait_for_prompt().await?;
+
+        let finished = self.terminal.shell_status().commands_finished;
+        self.terminal.type_str(cmd).await?;
+        self.terminal.enter().await?;
+        self.terminal
+            .wait_for(WaitCondition::CommandFinished(finished))
+            .timeout(self.timeout)
+            .await?;
+
+        let (command, cwd) = self.terminal.shell_command();
+        let command = command.unwrap_or_default();
+        Ok(CommandOutput {
+            output_text: command.output_text(),
+            exit_code: command.exit_code,
+            cwd,
+        })
+    }
+}
@@ -0,0 +1,514 @@
+//! Golden snapshot testing.
+//!
+//! [`Terminal::assert_snapshot`](crate::Terminal::assert_snapshot) compares
+//! the screen with a snapshot saved earlier under `snapshots/<name>.snap`.
+//! A snapshot holds the screen text and, optionally, a style layer marking
+//! the colors and attributes of every cell.
+//!
+//! When the screen differs, or there is no snapshot yet, the new screen is
+//! written next to it as `<name>.snap.new`, headed by a side-by-side diff,
+//! and the assertion fails. `termwright snapshot review` accepts or rejects
+//! the pending snapshots; with `TERMWRIGHT_UPDATE=1` set, differences are
+//! accepted as they are found.
+//!
+//! ```text
+//! size: 20x3
+//! cursor: 1,0
+//! --- text ---
+//! Menu
+//! > Open
+//!   Quit
+//! --- styles ---
+//! aaaa
+//! bbbbbb
+//!
+//!
+//! a: bold
+//! b: inverse
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Result, TermwrightError};
+use crate::screen::{Cell, CellAttributes, Color, Screen};
+
+/// Environment variable that accepts differences instead of failing.
+pub const UPDATE_ENV: &str = "TERMWRIGHT_UPDATE";
+
+/// Directory snapshots are kept in by default.
+pub const DEFAULT_DIR: &str = "snapshots";
+
+/// Extension of a snapshot file.
+const EXTENSION: &str = "snap";
+
+/// Extension of a pending snapshot file.
+const PENDING_EXTENSION: &str = "snap.new";
+
+/// Keys marking styled cells in the style layer, in order of first use.
+const STYLE_KEYS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Key shared by styles once every other key is taken.
+const OVERFLOW_KEY: char = '?';
+
+/// How a screen is compared with its golden snapshot.
+#[derive(Debug, Clone)]
+pub struct SnapshotConfig {
+    /// Directory the snapshot files live in.
+    pub dir: PathBuf,
+    /// Also compare colors, attributes and the cursor position.
+    pub styles: bool,
+    /// Overwrite snapshots that differ instead of failing. Defaults to
+    /// whether `TERMWRIGHT_UPDATE` is set.
+    pub update: bool,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from(DEFAULT_DIR),
+            styles: false,
+            update: std::env::var(UPDATE_ENV)
+                .is_ok_and(|value| !matches!(value.as_str(), "" | "0")),
+        }
+    }
+}
+
+impl SnapshotConfig {
+    /// Create a configuration with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the directory the snapshot files live in.
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = dir.into();
+        self
+    }
+
+    /// Set whether colors, attributes and the cursor are compared.
+    pub fn styles(mut self, styles: bool) -> Self {
+        self.styles = styles;
+        self
+    }
+
+    /// Set whether differences overwrite the snapshot instead of failing.
+    pub fn update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Path of the snapshot called `name`.
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.{EXTENSION}"))
+    }
+
+    /// Compare `screen` with the snapshot called `name`.
+    ///
+    /// Names may use letters, digits, `-`, `_` and `.`.
+    pub fn assert(&self, name: &str, screen: &Screen) -> Result<()> {
+        validate_name(name)?;
+        let path = self.path(name);
+        let pending = self.dir.join(format!("{name}.{PENDING_EXTENSION}"));
+        let actual = render(screen, self.styles);
+
+        let expected = match fs::read_to_string(&path) {
+            Ok(contents) => Some(contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        if expected.as_deref() == Some(actual.as_str()) {
+            remove_if_exists(&pending)?;
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        if self.update {
+            fs::write(&path, &actual)?;
+            remove_if_exists(&pending)?;
+            return Ok(());
+        }
+
+        let diff = diff(expected.as_deref().unwrap_or(""), &actual);
+        let heading = match expected {
+            Some(_) => format!("Differs from {}:", path.display()),
+            None => format!("New snapshot; {} does not exist yet.", path.display()),
+        };
+        let comment: String = std::iter::once(heading.as_str())
+            .chain(diff.lines())
+            .map(|line| format!("# {line}").trim_end().to_string() + "\n")
+            .collect();
+        fs::write(&pending, comment + &actual)?;
+
+        Err(TermwrightError::SnapshotMismatch {
+            name: name.to_string(),
+            pending,
+            diff,
+        })
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(TermwrightError::Snapshot(format!(
+            "invalid snapshot name {name:?}; use letters, digits, '-', '_' and '.'"
+        )))
+    }
+}
+
+fn remove_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Render a screen in the snapshot file format.
+///
+/// The text section has one line per row. The style layer, when included,
+/// has one line per row too, with a key under every styled cell, followed
+/// by what each key stands for.
+pub fn render(screen: &Screen, styles: bool) -> String {
+    let mut out = format!("size: {}x{}\n", screen.size.cols, screen.size.rows);
+    if styles {
+        out.push_str(&format!(
+            "cursor: {},{}\n",
+            screen.cursor.row, screen.cursor.col
+        ));
+    }
+    out.push_str("--- text ---\n");
+    for row in 0..screen.size.rows {
+        out.push_str(&screen.line(row).unwrap_or_default());
+        out.push('\n');
+    }
+
+    if styles {
+        out.push_str("--- styles ---\n");
+        let mut keys = STYLE_KEYS.chars();
+        let mut legend: Vec<(char, String)> = Vec::new();
+        let mut assigned: HashMap<String, char> = HashMap::new();
+        for row in screen.raw_cells() {
+            let mut line = String::with_capacity(row.len());
+            for cell in row {
+                let Some(style) = describe(cell) else {
+                    line.push(' ');
+                    continue;
+                };
+                let key = *assigned
+                    .entry(style)
+                    .or_insert_with_key(|style| match keys.next() {
+                        Some(key) => {
+                            legend.push((key, style.clone()));
+                            key
+                        }
+                        None => {
+                            if legend.last().is_none_or(|(key, _)| *key != OVERFLOW_KEY) {
+                                legend.push((OVERFLOW_KEY, "other styles".to_string()));
+                            }
+                            OVERFLOW_KEY
+                        }
+                    });
+                line.push(key);
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out.push('\n');
+        for (key, style) in legend {
+            out.push_str(&format!("{key}: {style}\n"));
+        }
+    }
+    out
+}
+
+/// Describe the colors and attributes of a cell, or `None` for a plain one.
+fn describe(cell: &Cell) -> Option<String> {
+    let CellAttributes {
+        bold,
+        italic,
+        underline,
+        inverse,
+    } = cell.attrs;
+    let mut parts = Vec::new();
+    for (set, name) in [
+        (bold, "bold"),
+        (italic, "italic"),
+        (underline, "underline"),
+        (inverse, "inverse"),
+    ] {
+        if set {
+            parts.push(name.to_string());
+        }
+    }
+    for (color, name) in [(cell.fg, "fg"), (cell.bg, "bg")] {
+        match color {
+            Color::Default => {}
+            Color::Indexed(idx) => parts.push(format!("{name}={idx}")),
+            Color::Rgb(r, g, b) => parts.push(format!("{name}=#{r:02x}{g:02x}{b:02x}")),
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// A line of a diff.
+enum Change<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Lines of `expected` and `actual` lined up along their longest common
+/// subsequence.
+fn changes<'a>(expected: &[&'a str], actual: &[&'a str]) -> Vec<Change<'a>> {
+    let (n, m) = (expected.len(), actual.len());
+    // common[i][j]: length of the longest common subsequence of
+    // expected[i..] and actual[j..].
+    let mut common = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = Vec::new();
+    while i < n || j < m {
+        if i < n && j < m && expected[i] == actual[j] {
+            changes.push(Change::Same(expected[i]));
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || common[i][j + 1] >= common[i + 1][j]) {
+            changes.push(Change::Added(actual[j]));
+            j += 1;
+        } else {
+            changes.push(Change::Removed(expected[i]));
+            i += 1;
+        }
+    }
+    changes
+}
+
+/// Put the lines removed and added between unchanged ones side by side.
+fn pair_up<'a>(
+    rows: &mut Vec<(&'a str, char, &'a str)>,
+    removed: &mut Vec<&'a str>,
+    added: &mut Vec<&'a str>,
+) {
+    for k in 0..removed.len().max(added.len()) {
+        rows.push(match (removed.get(k), added.get(k)) {
+            (Some(left), Some(right)) => (left, '|', right),
+            (Some(left), None) => (left, '<', ""),
+            (None, Some(right)) => ("", '>', right),
+            (None, None) => unreachable!(),
+        });
+    }
+    removed.clear();
+    added.clear();
+}
+
+/// A side-by-side diff of two snapshots.
+///
+/// The marker between the columns is `|` for a changed line, `<` for a line
+/// only in `expected` and `>` for a line only in `actual`.
+pub fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+
+    let mut rows = Vec::new();
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    for change in changes(&expected, &actual) {
+        match change {
+            Change::Same(line) => {
+                pair_up(&mut rows, &mut removed, &mut added);
+                rows.push((line, ' ', line));
+            }
+            Change::Removed(line) => removed.push(line),
+            Change::Added(line) => added.push(line),
+        }
+    }
+    pair_up(&mut rows, &mut removed, &mut added);
+
+    let width = rows
+        .iter()
+        .map(|(left, _, _)| left.chars().count())
+        .chain(std::iter::once("expected".len()))
+        .max()
+        .unwrap_or(0);
+    let mut out = format!("{:<width$}   actual\n", "expected");
+    for (left, marker, right) in rows {
+        let line = format!("{left:<width$} {marker} {right}");
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// A snapshot written by a failed assertion, waiting to be reviewed.
+#[derive(Debug, Clone)]
+pub struct PendingSnapshot {
+    /// Name of the snapshot.
+    pub name: String,
+    /// The snapshot file it would replace, which may not exist yet.
+    pub snapshot: PathBuf,
+    /// The pending `.snap.new` file.
+    pub pending: PathBuf,
+}
+
+impl PendingSnapshot {
+    /// Find the pending snapshots under `dir`, searching subdirectories
+    /// except hidden ones and `target`.
+    pub fn find(dir: impl AsRef<Path>) -> Result<Vec<Self>> {
+        let mut found = Vec::new();
+        let mut dirs = vec![dir.as_ref().to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                if path.is_dir() {
+                    if !file_name.starts_with('.') && file_name != "target" {
+                        dirs.push(path);
+                    }
+                } else if let Some(name) = file_name.strip_suffix(&format!(".{PENDING_EXTENSION}"))
+                {
+                    found.push(Self {
+                        name: name.to_string(),
+                        snapshot: path.with_file_name(format!("{name}.{EXTENSION}")),
+                        pending: path,
+                    });
+                }
+            }
+        }
+        found.sort_by(|a, b| a.pending.cmp(&b.pending));
+        Ok(found)
+    }
+
+    /// The pending snapshot, without the diff heading it.
+    pub fn contents(&self) -> Result<String> {
+        let contents = fs::read_to_string(&self.pending)?;
+        let start = contents
+            .lines()
+            .take_while(|line| line.starts_with('#'))
+            .map(|line| line.len() + 1)
+            .sum::<usize>();
+        Ok(contents[start.min(contents.len())..].to_string())
+    }
+
+    /// A side-by-side diff of the saved snapshot and the pending one.
+    pub fn diff(&self) -> Result<String> {
+        let saved = match fs::read_to_string(&self.snapshot) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(diff(&saved, &self.contents()?))
+    }
+
+    /// Replace the saved snapshot with the pending one.
+    pub fn accept(&self) -> Result<()> {
+        fs::write(&self.snapshot, self.contents()?)?;
+        fs::remove_file(&self.pending)?;
+        Ok(())
+    }
+
+    /// Discard the pending snapshot.
+    pub fn reject(&self) -> Result<()> {
+        fs::remove_file(&self.pending)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(ansi: &str) -> Screen {
+        Screen::from_ansi(ansi, 12, 3)
+    }
+
+    #[test]
+    fn renders_text_and_style_layer() {
+        let screen = screen("Menu\r\n\x1b[7m> Open\x1b[m\r\n  \x1b[1;31mQuit\x1b[m");
+
+        assert_eq!(
+            render(&screen, false),
+            "size: 12x3\n--- text ---\nMenu\n> Open\n  Quit\n"
+        );
+        assert_eq!(
+            render(&screen, true),
+            concat!(
+                "size: 12x3\ncursor: 2,6\n--- text ---\nMenu\n> Open\n  Quit\n",
+                "--- styles ---\n\naaaaaa\n  bbbb\n\n",
+                "a: inverse\nb: bold fg=1\n",
+            )
+        );
+    }
+
+    #[test]
+    fn diff_lines_up_changes_side_by_side() {
+        assert_eq!(
+            diff("a\nb\nc\nd\n", "a\nB\nc\nd\ne\n"),
+            concat!(
+                "expected   actual\n",
+                "a          a\n",
+                "b        | B\n",
+                "c          c\n",
+                "d          d\n",
+                "         > e\n",
+            )
+        );
+    }
+
+    #[test]
+    fn mismatches_are_kept_for_review() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SnapshotConfig::new().dir(dir.path()).update(false);
+
+        let err = config.assert("menu", &screen("one")).unwrap_err();
+        assert!(matches!(err, TermwrightError::SnapshotMismatch { .. }));
+        let pending = PendingSnapshot::find(dir.path()).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].name, "menu");
+        pending[0].accept().unwrap();
+        config.assert("menu", &screen("one")).unwrap();
+
+        let err = config.assert("menu", &screen("two")).unwrap_err();
+        let TermwrightError::SnapshotMismatch { diff, .. } = err else {
+            panic!("expected a mismatch, got {err}");
+        };
+        let changed = |diff: &str| {
+            diff.lines()
+                .any(|line| line.starts_with("one ") && line.ends_with(" | two"))
+        };
+        assert!(changed(&diff), "{diff}");
+        let pending = PendingSnapshot::find(dir.path()).unwrap();
+        assert!(changed(&pending[0].diff().unwrap()));
+        pending[0].reject().unwrap();
+        assert!(PendingSnapshot::find(dir.path()).unwrap().is_empty());
+
+        config
+            .clone()
+            .update(true)
+            .assert("menu", &screen("two"))
+            .unwrap();
+        config.assert("menu", &screen("two")).unwrap();
+
+        assert!(matches!(
+            config.assert("../menu", &screen("two")),
+            Err(TermwrightError::Snapshot(_))
+        ));
+    }
+}
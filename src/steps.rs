@@ -5,7 +5,8 @@ use std::path::{Path, PathBuf};
 use serde::Deserialize;
 
 use termwright::error::{Result, TermwrightError};
-use termwright::terminal::{DEFAULT_COLS, DEFAULT_ROWS};
+use termwright::input::Key;
+use termwright::terminal::{ClipboardSelection, DEFAULT_COLS, DEFAULT_ROWS};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -28,19 +29,58 @@ impl StepsFile {
             .unwrap_or("")
             .to_lowercase();
 
-        if ext == "json" {
-            serde_json::from_str(&contents).map_err(TermwrightError::Json)
+        let steps_file: Self = if ext == "json" {
+            serde_json::from_str(&contents).map_err(TermwrightError::Json)?
         } else if ext == "yaml" || ext == "yml" {
             serde_yaml::from_str(&contents)
-                .map_err(|e| TermwrightError::Protocol(format!("yaml error: {e}")))
+                .map_err(|e| TermwrightError::Protocol(format!("yaml error: {e}")))?
         } else {
             serde_json::from_str(&contents)
                 .or_else(|_| serde_yaml::from_str(&contents))
-                .map_err(|e| TermwrightError::Protocol(format!("steps parse error: {e}")))
+                .map_err(|e| TermwrightError::Protocol(format!("steps parse error: {e}")))?
+        };
+
+        steps_file.validate()?;
+        Ok(steps_file)
+    }
+
+    /// Check step parameters that can be validated before running anything.
+    fn validate(&self) -> Result<()> {
+        for (index, step) in self.steps.iter().enumerate() {
+            let invalid = |e: String| TermwrightError::Protocol(format!("step {}: {e}", index + 1));
+            match step {
+                Step::Press { press } => {
+                    press.key.parse::<Key>().map_err(invalid)?;
+                }
+                Step::ExpectTitle { expect_title }
+                    if expect_title.text.is_some() == expect_title.pattern.is_some() =>
+                {
+                    return Err(invalid(
+                        "expectTitle needs exactly one of text or pattern".to_string(),
+                    ));
+                }
+                Step::SetClipboard {
+                    set_clipboard: SetClipboardStep { selection, .. },
+                }
+                | Step::ExpectClipboard {
+                    expect_clipboard: ExpectClipboardStep { selection, .. },
+                } => {
+                    clipboard_selection(selection.as_deref()).map_err(invalid)?;
+                }
+                _ => {}
+            }
         }
+        Ok(())
     }
 }
 
+/// Parse a step's clipboard selection, defaulting to the system clipboard.
+pub fn clipboard_selection(
+    selection: Option<&str>,
+) -> std::result::Result<ClipboardSelection, String> {
+    selection.unwrap_or("clipboard").parse()
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionConfig {
@@ -59,6 +99,8 @@ pub struct SessionConfig {
     pub no_osc_emulation: bool,
     #[serde(default)]
     pub cwd: Option<PathBuf>,
+    #[serde(default)]
+    pub scrollback: Option<usize>,
 }
 
 impl SessionConfig {
@@ -118,20 +160,15 @@ impl ArtifactsConfig {
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub enum ArtifactMode {
+    #[default]
     OnFailure,
     Always,
     Off,
 }
 
-impl Default for ArtifactMode {
-    fn default() -> Self {
-        ArtifactMode::OnFailure
-    }
-}
-
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
 pub enum Step {
@@ -162,13 +199,40 @@ pub enum Step {
         #[serde(rename = "type")]
         r#type: TypeStep,
     },
+    Paste {
+        paste: PasteStep,
+    },
+    SetClipboard {
+        #[serde(rename = "setClipboard")]
+        set_clipboard: SetClipboardStep,
+    },
     Hotkey {
         hotkey: HotkeyStep,
     },
+    ShellRun {
+        #[serde(rename = "shellRun")]
+        shell_run: ShellRunStep,
+    },
     ExpectText {
         #[serde(rename = "expectText")]
         expect_text: ExpectTextStep,
     },
+    ExpectTitle {
+        #[serde(rename = "expectTitle")]
+        expect_title: ExpectTitleStep,
+    },
+    ExpectBell {
+        #[serde(rename = "expectBell")]
+        expect_bell: ExpectBellStep,
+    },
+    ExpectClipboard {
+        #[serde(rename = "expectClipboard")]
+        expect_clipboard: ExpectClipboardStep,
+    },
+    ExpectLink {
+        #[serde(rename = "expectLink")]
+        expect_link: ExpectLinkStep,
+    },
     ExpectPattern {
         #[serde(rename = "expectPattern")]
         expect_pattern: ExpectPatternStep,
@@ -181,9 +245,16 @@ pub enum Step {
         #[serde(rename = "notExpectPattern")]
         not_expect_pattern: NotExpectPatternStep,
     },
+    ExpectExit {
+        #[serde(rename = "expectExit")]
+        expect_exit: ExpectExitStep,
+    },
     Screenshot {
         screenshot: ScreenshotStep,
     },
+    Snapshot {
+        snapshot: SnapshotStep,
+    },
 }
 
 #[derive(Debug, Deserialize)]
@@ -192,6 +263,8 @@ pub struct WaitForTextStep {
     pub text: String,
     #[serde(default)]
     pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub scrollback: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -200,6 +273,8 @@ pub struct WaitForPatternStep {
     pub pattern: String,
     #[serde(default)]
     pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub scrollback: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -222,6 +297,20 @@ pub struct TypeStep {
     pub text: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasteStep {
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetClipboardStep {
+    pub text: String,
+    #[serde(default)]
+    pub selection: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HotkeyStep {
@@ -232,12 +321,68 @@ pub struct HotkeyStep {
     pub ch: char,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellRunStep {
+    pub command: String,
+    /// Exit code the command must report.
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// Text the command's output must contain.
+    #[serde(default)]
+    pub output: Option<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExpectTextStep {
     pub text: String,
     #[serde(default)]
     pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub scrollback: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectTitleStep {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectBellStep {
+    /// Total number of bells to wait for since the session started.
+    #[serde(default)]
+    pub count: Option<usize>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectClipboardStep {
+    pub text: String,
+    #[serde(default)]
+    pub selection: Option<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectLinkStep {
+    pub text: String,
+    pub uri: String,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -246,6 +391,8 @@ pub struct ExpectPatternStep {
     pub pattern: String,
     #[serde(default)]
     pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub scrollback: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -276,9 +423,28 @@ pub struct NotExpectPatternStep {
     pub pattern: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectExitStep {
+    #[serde(default)]
+    pub code: Option<i32>,
+    #[serde(default)]
+    pub signal: Option<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ScreenshotStep {
     #[serde(default)]
     pub name: Option<String>,
 }
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotStep {
+    pub name: String,
+    #[serde(default)]
+    pub styles: bool,
+}
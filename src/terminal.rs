@@ -2,40 +2,59 @@
 
 use std::collections::HashMap;
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
-use portable_pty::{Child, CommandBuilder, MasterPty, PtySize, native_pty_system};
-use tokio::sync::Mutex;
+use nix::errno::Errno;
+use nix::sys::signal::Signal;
+use portable_pty::{CommandBuilder, MasterPty, PtySize, native_pty_system};
+use tokio::sync::{Mutex, watch};
 use tokio::time::Instant;
 
+use crate::capture::{CapturedOutput, OutputBuffer, OutputMark};
 use crate::error::{Result, TermwrightError};
-use crate::input::{Key, MouseButton};
-use crate::screen::Screen;
-use crate::wait::{DEFAULT_TIMEOUT, WaitBuilder, WaitCondition};
+use crate::input::{
+    CELL_PIXEL_HEIGHT, CELL_PIXEL_WIDTH, Key, MouseAction, MouseButton, MouseEvent,
+    ScrollDirection, drag_events,
+};
+use crate::screen::{Position, Screen, TerminalModes};
+use crate::shell::ShellSession;
+use crate::snapshot::SnapshotConfig;
+use crate::wait::{DEFAULT_TIMEOUT, WaitBuilder, WaitCondition, WaitContext};
 
 mod csi;
+mod emulator;
+mod exit;
+mod hyperlink;
+mod identity;
 mod osc;
+mod prompt;
+mod record;
+mod sync;
 
-use self::{
-    csi::CsiEmulator,
-    osc::{OscEmulator, initial_color_state},
-};
+pub use self::exit::ExitStatus;
+pub use self::identity::TerminalIdentity;
+pub use self::osc::ClipboardSelection;
+
+pub(crate) use self::emulator::{Emulator, EmulatorState};
+pub(crate) use self::osc::Clipboard;
+pub(crate) use self::prompt::{FinishedCommand, ShellStatus};
+use self::record::Recording;
 
 /// Default terminal width.
 pub const DEFAULT_COLS: u16 = 80;
 /// Default terminal height.
 pub const DEFAULT_ROWS: u16 = 24;
-
-fn encode_sgr_mouse(code: u8, row: u16, col: u16, pressed: bool) -> Vec<u8> {
-    // SGR (1006) mouse encoding.
-    // Coordinates are 1-based.
-    let row = row.saturating_add(1);
-    let col = col.saturating_add(1);
-    let suffix = if pressed { 'M' } else { 'm' };
-    format!("\u{1b}[<{};{};{}{}", code, col, row, suffix).into_bytes()
-}
+/// Default number of scrollback lines retained.
+pub const DEFAULT_SCROLLBACK: usize = 1000;
+/// Default time a synchronized update may hold back output.
+pub const DEFAULT_SYNC_TIMEOUT: Duration = Duration::from_secs(1);
+/// Default number of raw output bytes retained.
+pub const DEFAULT_OUTPUT_CAPTURE: usize = 1024 * 1024;
+/// How long an exit waits for the output the process wrote before exiting.
+const EXIT_OUTPUT_GRACE: Duration = Duration::from_millis(100);
 
 /// Configuration for the terminal.
 #[derive(Debug, Clone)]
@@ -50,6 +69,19 @@ pub struct TerminalConfig {
     pub working_dir: Option<PathBuf>,
     /// Default timeout for operations.
     pub timeout: Duration,
+    /// Number of scrollback lines to retain.
+    pub scrollback: usize,
+    /// Terminal the application is told it runs in.
+    pub identity: TerminalIdentity,
+    /// How long output inside a synchronized update is held back before it
+    /// is shown anyway. Zero shows it immediately.
+    pub sync_timeout: Duration,
+    /// Number of raw output bytes to retain. Zero turns capture off.
+    pub output_capture: usize,
+    /// Asciicast file the session is recorded to from the start.
+    pub record_to: Option<PathBuf>,
+    /// Whether the recording includes input sent to the application.
+    pub record_input: bool,
 }
 
 impl Default for TerminalConfig {
@@ -60,6 +92,12 @@ impl Default for TerminalConfig {
             env: HashMap::new(),
             working_dir: None,
             timeout: DEFAULT_TIMEOUT,
+            scrollback: DEFAULT_SCROLLBACK,
+            identity: TerminalIdentity::default(),
+            sync_timeout: DEFAULT_SYNC_TIMEOUT,
+            output_capture: DEFAULT_OUTPUT_CAPTURE,
+            record_to: None,
+            record_input: false,
         }
     }
 }
@@ -115,6 +153,58 @@ impl TerminalBuilder {
         self
     }
 
+    /// Set the number of scrollback lines to retain.
+    pub fn scrollback(mut self, lines: usize) -> Self {
+        self.config.scrollback = lines;
+        self
+    }
+
+    /// Set the terminal identity reported to the application.
+    ///
+    /// This decides how device attribute, version, mode and capability
+    /// queries are answered and the `TERM` injected by default. Applications
+    /// that only turn on the kitty keyboard protocol after querying for it
+    /// with `CSI ? u`, such as helix and crossterm based tools, need
+    /// [`TerminalIdentity::Kitty`]; the default xterm identity leaves that
+    /// query unanswered.
+    pub fn identity(mut self, identity: TerminalIdentity) -> Self {
+        self.config.identity = identity;
+        self
+    }
+
+    /// Set how long a synchronized update (mode 2026) may hold back output.
+    ///
+    /// While an application draws a frame between `CSI ? 2026 h` and
+    /// `CSI ? 2026 l`, snapshots and waits keep seeing the previous frame.
+    /// A frame left open longer than this is shown as it is. Zero turns
+    /// holding back off.
+    pub fn sync_timeout(mut self, timeout: Duration) -> Self {
+        self.config.sync_timeout = timeout;
+        self
+    }
+
+    /// Set how many bytes of raw output to retain for
+    /// [`Terminal::output_since`]. Zero turns capture off.
+    pub fn output_capture(mut self, bytes: usize) -> Self {
+        self.config.output_capture = bytes;
+        self
+    }
+
+    /// Record the session to an asciicast v2 file, starting before the
+    /// command runs.
+    ///
+    /// See [`Terminal::record_start`] to record part of a session instead.
+    pub fn record_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.record_to = Some(path.into());
+        self
+    }
+
+    /// Include input sent to the application in the recording.
+    pub fn record_input(mut self) -> Self {
+        self.config.record_input = true;
+        self
+    }
+
     /// Spawn a command in the terminal.
     pub async fn spawn(self, cmd: &str, args: &[&str]) -> Result<Terminal> {
         Terminal::spawn_with_config(
@@ -150,10 +240,28 @@ pub struct Terminal {
     config: TerminalConfig,
     /// Handle to the reader task.
     _reader_handle: tokio::task::JoinHandle<()>,
-    /// Flag indicating if the process has exited.
-    exited: Arc<Mutex<Option<i32>>>,
-    /// The child process.
-    child: Arc<Mutex<Box<dyn Child + Send + Sync>>>,
+    /// Handle to the task waiting for the process to exit.
+    _waiter_handle: tokio::task::JoinHandle<()>,
+    /// Exit status, set once the process has exited and been reaped.
+    exited: Arc<Mutex<Option<ExitStatus>>>,
+    /// Set as soon as the child is reaped, before `exited`, after which its
+    /// pid may belong to another process and must not be signalled.
+    reaped: Arc<AtomicBool>,
+    /// Modes, clipboard, hyperlinks, prompt marks and held back output
+    /// tracked beside the parser.
+    state: EmulatorState,
+    /// Raw output exactly as read from the PTY.
+    output: Arc<std::sync::Mutex<OutputBuffer>>,
+    /// Asciicast recording in progress, if any.
+    recording: Arc<std::sync::Mutex<Option<Recording>>>,
+    /// Mouse button pressed by [`Terminal::mouse_down`] and not yet released.
+    pressed_button: Mutex<Option<MouseButton>>,
+    /// Screen generation, bumped by the reader task whenever output is
+    /// processed and by the waiter task when the process exits. Waits
+    /// subscribe to it instead of polling.
+    generation: Arc<watch::Sender<u64>>,
+    /// Process id of the child, owned and reaped by the waiter task.
+    pid: Option<u32>,
 }
 
 impl Terminal {
@@ -176,8 +284,8 @@ impl Terminal {
             .openpty(PtySize {
                 rows: config.rows,
                 cols: config.cols,
-                pixel_width: 0,
-                pixel_height: 0,
+                pixel_width: config.cols.saturating_mul(CELL_PIXEL_WIDTH),
+                pixel_height: config.rows.saturating_mul(CELL_PIXEL_HEIGHT),
             })
             .map_err(|e| TermwrightError::SpawnFailed(e.to_string()))?;
 
@@ -192,7 +300,7 @@ impl Terminal {
         // Inject default terminal env vars unless disabled.
         if inject_default_env {
             if !config.env.contains_key("TERM") {
-                cmd_builder.env("TERM", "xterm-256color");
+                cmd_builder.env("TERM", config.identity.term());
             }
             if !config.env.contains_key("COLORTERM") {
                 cmd_builder.env("COLORTERM", "truecolor");
@@ -209,11 +317,12 @@ impl Terminal {
             cmd_builder.cwd(cwd);
         }
 
-        let child = pair
+        let mut child = pair
             .slave
             .spawn_command(cmd_builder)
             .map_err(|e| TermwrightError::SpawnFailed(e.to_string()))?;
 
+        let pid = child.process_id();
         let master = pair.master;
 
         let reader = master
@@ -229,15 +338,38 @@ impl Terminal {
         let parser = Arc::new(Mutex::new(vt100::Parser::new(
             config.rows,
             config.cols,
-            1000, // scrollback lines
+            config.scrollback,
         )));
         let parser_clone = parser.clone();
         let writer_clone = writer.clone();
-        let mut osc = osc_emulation.then(|| OscEmulator::new(initial_color_state()));
-        let mut csi = CsiEmulator::new();
+        let state = EmulatorState::new(config.sync_timeout);
+        let mut emulator = Emulator::new(state.clone(), config.identity, osc_emulation);
+        let output = Arc::new(std::sync::Mutex::new(OutputBuffer::new(
+            config.output_capture,
+        )));
+        let output_clone = output.clone();
+        let recording = match &config.record_to {
+            Some(path) => Some(Recording::create(
+                path,
+                config.cols,
+                config.rows,
+                recording_term(&config),
+                config.record_input,
+            )?),
+            None => None,
+        };
+        let recording = Arc::new(std::sync::Mutex::new(recording));
+        let recording_clone = recording.clone();
 
         let exited = Arc::new(Mutex::new(None));
         let exited_clone = exited.clone();
+        let reaped = Arc::new(AtomicBool::new(false));
+        let reaped_clone = reaped.clone();
+
+        let generation = Arc::new(watch::Sender::new(0u64));
+        let generation_clone = generation.clone();
+        let generation_waiter = generation.clone();
+        let (reader_done, reader_done_rx) = std::sync::mpsc::channel::<()>();
 
         // Background reader task
         let reader_handle = tokio::task::spawn_blocking(move || {
@@ -246,33 +378,28 @@ impl Terminal {
 
             loop {
                 match reader.read(&mut buf) {
-                    Ok(0) => {
-                        // EOF - process exited
-                        let rt = tokio::runtime::Handle::current();
-                        rt.block_on(async {
-                            let mut exited = exited_clone.lock().await;
-                            *exited = Some(0);
-                        });
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Ok(0) | Err(_) => {
+                        // EOF or EIO - the process closed the PTY
                         break;
                     }
                     Ok(n) => {
                         let rt = tokio::runtime::Handle::current();
                         rt.block_on(async {
                             let mut parser = parser_clone.lock().await;
-                            parser.process(&buf[..n]);
-
-                            let cursor = {
-                                let cursor = parser.screen().cursor_position();
-                                crate::screen::Position::new(cursor.0, cursor.1)
-                            };
-
-                            let mut responses = Vec::new();
-                            if let Some(osc) = osc.as_mut() {
-                                responses.extend(osc.process_output(&buf[..n]));
+                            if let Ok(mut output) = output_clone.lock() {
+                                output.push(&buf[..n]);
+                            }
+                            if let Ok(mut recording) = recording_clone.lock() {
+                                if let Some(recording) = recording.as_mut() {
+                                    recording.output(&buf[..n]);
+                                }
                             }
-                            responses.extend(csi.process_output(&buf[..n], cursor));
+                            let responses =
+                                emulator.process(&mut parser, &buf[..n], std::time::Instant::now());
 
                             drop(parser);
+                            generation_clone.send_modify(|g| *g = g.wrapping_add(1));
 
                             if !responses.is_empty() {
                                 let mut writer = writer_clone.lock().await;
@@ -283,16 +410,25 @@ impl Terminal {
                             }
                         });
                     }
-                    Err(_) => {
-                        let rt = tokio::runtime::Handle::current();
-                        rt.block_on(async {
-                            let mut exited = exited_clone.lock().await;
-                            *exited = Some(-1);
-                        });
-                        break;
-                    }
                 }
             }
+            drop(reader_done);
+        });
+
+        // Background waiter task, reaping the child as soon as it exits even
+        // when something else still holds the PTY open.
+        let waiter_handle = tokio::task::spawn_blocking(move || {
+            let status = exit::wait_for_child(child.as_mut());
+            reaped_clone.store(true, Ordering::SeqCst);
+            // Let the reader take in the last output before waits see the
+            // exit, unless another process keeps the PTY open.
+            let _ = reader_done_rx.recv_timeout(EXIT_OUTPUT_GRACE);
+            let rt = tokio::runtime::Handle::current();
+            rt.block_on(async {
+                let mut exited = exited_clone.lock().await;
+                *exited = Some(status);
+            });
+            generation_waiter.send_modify(|g| *g = g.wrapping_add(1));
         });
 
         // Give the process a moment to start
@@ -304,34 +440,255 @@ impl Terminal {
             parser,
             config,
             _reader_handle: reader_handle,
+            _waiter_handle: waiter_handle,
             exited,
-            child: Arc::new(Mutex::new(child)),
+            reaped,
+            state,
+            output,
+            recording,
+            pressed_button: Mutex::new(None),
+            generation,
+            pid,
         })
     }
 
     /// Get a snapshot of the current screen state.
+    ///
+    /// While the application draws a synchronized update, this is the last
+    /// complete frame (see [`TerminalBuilder::sync_timeout`]).
     pub async fn screen(&self) -> Screen {
-        let parser = self.parser.lock().await;
-        Screen::from_vt100(parser.screen())
+        let parser = self.lock_parser().await;
+        self.state.screen(&parser)
+    }
+
+    /// Compare the screen with the golden snapshot `name` under
+    /// `snapshots/`.
+    ///
+    /// When the screen differs, the new one is saved as
+    /// `snapshots/<name>.snap.new` for `termwright snapshot review` and a
+    /// [`TermwrightError::SnapshotMismatch`] with a side-by-side diff is
+    /// returned. Set `TERMWRIGHT_UPDATE=1` to accept differences instead.
+    pub async fn assert_snapshot(&self, name: &str) -> Result<()> {
+        self.assert_snapshot_with(name, &SnapshotConfig::default())
+            .await
+    }
+
+    /// Compare the screen with a golden snapshot, configuring where it is
+    /// kept and whether styles are compared.
+    pub async fn assert_snapshot_with(&self, name: &str, config: &SnapshotConfig) -> Result<()> {
+        config.assert(name, &self.screen().await)
+    }
+
+    /// Get a snapshot of the screen including scrollback history.
+    ///
+    /// Rows above the visible screen hold output that has scrolled off, oldest
+    /// first. See [`Screen::with_history`] for details.
+    pub async fn scrollback(&self) -> Screen {
+        let mut parser = self.lock_parser().await;
+        self.state.scrollback(&mut parser)
+    }
+
+    /// Get the terminal modes the application currently has enabled.
+    pub async fn modes(&self) -> TerminalModes {
+        let parser = self.lock_parser().await;
+        self.state.modes(parser.screen())
+    }
+
+    /// Get the window title set with OSC 0 or OSC 2.
+    pub async fn title(&self) -> String {
+        self.lock_parser().await.screen().title().to_string()
+    }
+
+    /// Get the number of audible and visual bells received so far.
+    ///
+    /// Read this before an action and pass it to
+    /// [`WaitCondition::BellRung`] to wait for the action to ring the bell.
+    pub async fn bell_count(&self) -> usize {
+        let parser = self.lock_parser().await;
+        parser.screen().audible_bell_count() + parser.screen().visual_bell_count()
+    }
+
+    /// Get the contents of a clipboard selection.
+    ///
+    /// Applications write selections with OSC 52, which is only recorded
+    /// while OSC emulation is enabled.
+    pub fn clipboard(&self, selection: ClipboardSelection) -> Option<String> {
+        let clipboard = self.state.clipboard.lock().ok()?;
+        clipboard.get(selection).map(str::to_string)
+    }
+
+    /// Seed a clipboard selection, returned to applications that read it
+    /// with an OSC 52 query.
+    pub fn set_clipboard(&self, selection: ClipboardSelection, text: &str) -> &Self {
+        if let Ok(mut clipboard) = self.state.clipboard.lock() {
+            clipboard.set(selection, text.to_string());
+        }
+        self
+    }
+
+    /// Start driving a shell with prompt integration.
+    ///
+    /// See [`ShellSession`] for the escape sequences the shell must emit.
+    pub fn shell(&self) -> ShellSession<'_> {
+        ShellSession::new(self).timeout(self.config.timeout)
+    }
+
+    /// Prompt state reported by shell integration.
+    pub(crate) fn shell_status(&self) -> ShellStatus {
+        self.state.shell_status()
+    }
+
+    /// The last command delimited by prompt marks, and the working
+    /// directory last reported with OSC 7.
+    pub(crate) fn shell_command(&self) -> (Option<FinishedCommand>, Option<PathBuf>) {
+        self.state
+            .shell
+            .lock()
+            .map(|shell| (shell.last_command(), shell.cwd()))
+            .unwrap_or_default()
+    }
+
+    /// Get the number of synchronized updates (mode 2026) the application
+    /// has finished drawing.
+    pub fn frame_count(&self) -> u64 {
+        self.state.frames()
+    }
+
+    /// Get a mark at the end of the output written so far.
+    pub fn output_mark(&self) -> OutputMark {
+        self.output
+            .lock()
+            .map(|output| output.mark())
+            .unwrap_or_default()
+    }
+
+    /// Get the raw output written after `mark`, with escape sequences intact.
+    ///
+    /// Only the last [`TerminalBuilder::output_capture`] bytes are retained;
+    /// [`CapturedOutput::truncated`] tells whether part of the output after
+    /// the mark has been dropped.
+    pub fn output_since(&self, mark: OutputMark) -> CapturedOutput {
+        self.output
+            .lock()
+            .map(|output| output.since(mark))
+            .unwrap_or_default()
+    }
+
+    /// Start recording the session to an asciicast v2 file, replacing any
+    /// recording in progress.
+    ///
+    /// Output is always recorded; input sent to the application only when
+    /// `input` is true. The recording opens on the screen as it is now.
+    pub async fn record_start(&self, path: impl AsRef<Path>, input: bool) -> Result<()> {
+        // Hold the parser until the recording is in place, so no output
+        // falls between the screen drawn first and the output recorded next.
+        let parser = self.lock_parser().await;
+        let screen = parser.screen();
+        let (rows, cols) = screen.size();
+        let mut recording = Recording::create(
+            path.as_ref(),
+            cols,
+            rows,
+            recording_term(&self.config),
+            input,
+        )?;
+        let mut initial = Vec::new();
+        if screen.alternate_screen() {
+            initial.extend_from_slice(b"\x1b[?1049h");
+        }
+        initial.extend(screen.state_formatted());
+        recording.output(&initial);
+
+        let previous = self
+            .recording
+            .lock()
+            .map(|mut current| current.replace(recording))
+            .unwrap_or_default();
+        drop(parser);
+        if let Some(previous) = previous {
+            previous.finish()?;
+        }
+        Ok(())
+    }
+
+    /// Stop recording, returning the path of the finished recording if one
+    /// was in progress.
+    pub fn record_stop(&self) -> Result<Option<PathBuf>> {
+        let recording = self
+            .recording
+            .lock()
+            .map(|mut current| current.take())
+            .unwrap_or_default();
+        recording.map(Recording::finish).transpose()
+    }
+
+    /// Pass input or a resize on to the recording in progress.
+    fn record(&self, f: impl FnOnce(&mut Recording)) {
+        if let Ok(mut recording) = self.recording.lock() {
+            if let Some(recording) = recording.as_mut() {
+                f(recording);
+            }
+        }
+    }
+
+    /// Lock the parser, first showing output of a synchronized update that
+    /// has been held back for longer than the sync timeout.
+    async fn lock_parser(&self) -> tokio::sync::MutexGuard<'_, vt100::Parser> {
+        let mut parser = self.parser.lock().await;
+        self.state
+            .release_expired(&mut parser, std::time::Instant::now());
+        parser
+    }
+
+    /// When held back output of a synchronized update will be shown.
+    fn sync_deadline(&self) -> Option<Instant> {
+        self.state.sync_deadline().map(Instant::from_std)
+    }
+
+    /// Get the current screen generation.
+    ///
+    /// The generation increases every time output from the process is
+    /// processed and once more when the process exits, so two equal values
+    /// mean nothing has happened in between.
+    pub fn generation(&self) -> u64 {
+        *self.generation.borrow()
     }
 
     /// Type a string of text into the terminal.
     pub async fn type_str(&self, text: &str) -> Result<&Self> {
-        let mut writer = self.writer.lock().await;
-        writer
-            .write_all(text.as_bytes())
-            .map_err(TermwrightError::Pty)?;
-        writer.flush().map_err(TermwrightError::Pty)?;
-        Ok(self)
+        self.send_raw(text.as_bytes()).await
+    }
+
+    /// Paste text into the terminal.
+    ///
+    /// When the application has enabled bracketed paste mode the text is
+    /// wrapped in `ESC [ 200 ~` and `ESC [ 201 ~`, the way a terminal
+    /// emulator delivers a clipboard paste. Any end marker inside the text is
+    /// removed so it cannot terminate the paste early. Without bracketed
+    /// paste the text is typed as-is, like [`Terminal::type_str`].
+    pub async fn paste(&self, text: &str) -> Result<&Self> {
+        if !self.modes().await.bracketed_paste {
+            return self.type_str(text).await;
+        }
+
+        let mut bytes = b"\x1b[200~".to_vec();
+        bytes.extend_from_slice(text.replace("\x1b[201~", "").as_bytes());
+        bytes.extend_from_slice(b"\x1b[201~");
+        self.send_raw(&bytes).await
     }
 
     /// Send a key to the terminal.
+    ///
+    /// The key is encoded for the input modes the application currently has
+    /// enabled, so arrow keys send `ESC O A` under application cursor mode and
+    /// modified keys use the kitty keyboard protocol or modifyOtherKeys when
+    /// the application asked for them. The kitty keyboard query is only
+    /// answered under [`TerminalIdentity::Kitty`], see
+    /// [`TerminalBuilder::identity`].
     pub async fn send_key(&self, key: Key) -> Result<&Self> {
-        let bytes = key.to_escape_sequence();
-        let mut writer = self.writer.lock().await;
-        writer.write_all(&bytes).map_err(TermwrightError::Pty)?;
-        writer.flush().map_err(TermwrightError::Pty)?;
-        Ok(self)
+        let modes = self.modes().await;
+        let bytes = key.to_escape_sequence_with_modes(&modes);
+        self.send_raw(&bytes).await
     }
 
     /// Press Enter key.
@@ -349,6 +706,7 @@ impl Terminal {
         let mut writer = self.writer.lock().await;
         writer.write_all(bytes).map_err(TermwrightError::Pty)?;
         writer.flush().map_err(TermwrightError::Pty)?;
+        self.record(|recording| recording.input(bytes));
         Ok(self)
     }
 
@@ -359,18 +717,42 @@ impl Terminal {
             .resize(PtySize {
                 rows,
                 cols,
-                pixel_width: 0,
-                pixel_height: 0,
+                pixel_width: cols.saturating_mul(CELL_PIXEL_WIDTH),
+                pixel_height: rows.saturating_mul(CELL_PIXEL_HEIGHT),
             })
-            .map_err(|e| {
-                TermwrightError::Pty(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    e.to_string(),
-                ))
-            })?;
+            .map_err(|e| TermwrightError::Pty(std::io::Error::other(e.to_string())))?;
+        self.record(|recording| recording.resize(cols, rows));
         Ok(self)
     }
 
+    /// Send a mouse event, encoded for the mouse mode and encoding the
+    /// application has enabled.
+    ///
+    /// Events the current mouse mode does not report, such as releases in X10
+    /// mode, are dropped.
+    pub async fn send_mouse(&self, event: MouseEvent) -> Result<&Self> {
+        let modes = self.modes().await;
+        let event = match event.action {
+            MouseAction::Press(button) => {
+                *self.pressed_button.lock().await = Some(button);
+                event
+            }
+            MouseAction::Release(None) => MouseEvent {
+                action: MouseAction::Release(self.pressed_button.lock().await.take()),
+                ..event
+            },
+            MouseAction::Release(Some(_)) => {
+                self.pressed_button.lock().await.take();
+                event
+            }
+            _ => event,
+        };
+        match event.encode(&modes) {
+            Some(bytes) => self.send_raw(&bytes).await,
+            None => Ok(self),
+        }
+    }
+
     /// Move the mouse cursor (hover).
     ///
     /// Note: Many TUIs ignore this unless mouse reporting is enabled.
@@ -380,15 +762,9 @@ impl Terminal {
         col: u16,
         held_buttons: Vec<MouseButton>,
     ) -> Result<&Self> {
-        let base_code = held_buttons
-            .first()
-            .copied()
-            .map(|b| b.press_code())
-            .unwrap_or(3);
-
-        let code = base_code.saturating_add(32);
-        let bytes = encode_sgr_mouse(code, row, col, true);
-        self.send_raw(&bytes).await
+        let held = held_buttons.first().copied();
+        self.send_mouse(MouseEvent::new(MouseAction::Motion(held), row, col))
+            .await
     }
 
     /// Click a mouse button at the given cell position.
@@ -399,15 +775,67 @@ impl Terminal {
 
     /// Press a mouse button at the given cell position.
     pub async fn mouse_down(&self, row: u16, col: u16, button: MouseButton) -> Result<&Self> {
-        let code = button.press_code();
-        let bytes = encode_sgr_mouse(code, row, col, true);
-        self.send_raw(&bytes).await
+        self.send_mouse(MouseEvent::new(MouseAction::Press(button), row, col))
+            .await
     }
 
-    /// Release any mouse button at the given cell position.
+    /// Release the pressed mouse button at the given cell position.
     pub async fn mouse_up(&self, row: u16, col: u16) -> Result<&Self> {
-        let bytes = encode_sgr_mouse(3, row, col, false);
-        self.send_raw(&bytes).await
+        self.send_mouse(MouseEvent::new(MouseAction::Release(None), row, col))
+            .await
+    }
+
+    /// Scroll the mouse wheel `amount` notches at the given cell position.
+    pub async fn scroll(
+        &self,
+        row: u16,
+        col: u16,
+        direction: ScrollDirection,
+        amount: u16,
+    ) -> Result<&Self> {
+        for _ in 0..amount {
+            self.send_mouse(MouseEvent::new(MouseAction::Wheel(direction), row, col))
+                .await?;
+        }
+        Ok(self)
+    }
+
+    /// Drag with `button` held from one cell to another.
+    ///
+    /// The button is pressed at `from`, moved to `to` through `steps` motion
+    /// events spread evenly along the way, and released at `to`.
+    pub async fn drag(
+        &self,
+        from: Position,
+        to: Position,
+        button: MouseButton,
+        steps: u16,
+    ) -> Result<&Self> {
+        for event in drag_events(from, to, button, steps) {
+            self.send_mouse(event).await?;
+        }
+        Ok(self)
+    }
+
+    /// Wait for an arbitrary condition, including combinators and predicates.
+    pub fn wait_for(&self, condition: WaitCondition) -> ExpectBuilder<'_> {
+        ExpectBuilder {
+            terminal: self,
+            wait: WaitBuilder::new(condition),
+        }
+    }
+
+    /// Wait for any of several conditions and report which one matched.
+    ///
+    /// Resolves to the index of the first satisfied condition.
+    pub fn expect_any(
+        &self,
+        conditions: impl IntoIterator<Item = WaitCondition>,
+    ) -> ExpectAnyBuilder<'_> {
+        ExpectAnyBuilder {
+            terminal: self,
+            wait: WaitBuilder::new(WaitCondition::Any(conditions.into_iter().collect())),
+        }
     }
 
     /// Wait for specific text to appear on screen.
@@ -458,22 +886,17 @@ impl Terminal {
         }
     }
 
-    /// Wait for the process to exit.
-    pub async fn wait_exit(&self) -> Result<i32> {
+    /// Wait for the process to exit and return its exit status.
+    pub async fn wait_exit(&self) -> Result<ExitStatus> {
         let wait = WaitBuilder::new(WaitCondition::ProcessExit).timeout(self.config.timeout);
+        self.execute_wait(&wait).await?;
+        Ok(self.exit_status().await.unwrap_or_default())
+    }
 
-        let deadline = Instant::now() + wait.get_timeout();
-
-        while Instant::now() < deadline {
-            let exited = self.exited.lock().await;
-            if let Some(code) = *exited {
-                return Ok(code);
-            }
-            drop(exited);
-            tokio::time::sleep(wait.get_poll_interval()).await;
-        }
-
-        Err(wait.timeout_error())
+    /// Get the exit status if the process has exited.
+    pub async fn exit_status(&self) -> Option<ExitStatus> {
+        let exited = self.exited.lock().await;
+        exited.clone()
     }
 
     /// Check if the process has exited.
@@ -483,12 +906,44 @@ impl Terminal {
     }
 
     /// Kill the terminal process.
+    ///
+    /// Sends SIGHUP first and falls back to SIGKILL if the process is still
+    /// alive after a short grace period. The waiter task reaps the child, so
+    /// its exit status remains available through [`Terminal::exit_status`].
     pub async fn kill(&self) -> Result<()> {
-        let mut child = self.child.lock().await;
-        child
-            .kill()
-            .map_err(|e| TermwrightError::SpawnFailed(format!("Failed to kill process: {}", e)))?;
-        Ok(())
+        if self.has_exited().await {
+            return Ok(());
+        }
+
+        let Some(pid) = self.pid else {
+            return Ok(());
+        };
+
+        let kill_error =
+            |e: Errno| TermwrightError::SpawnFailed(format!("Failed to kill process: {}", e));
+
+        // ESRCH means the child exited and was reaped since `reaped` was
+        // checked, so it is already gone.
+        if self.reaped.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        match exit::signal_pid(pid, Signal::SIGHUP) {
+            Err(Errno::ESRCH) => return Ok(()),
+            result => result.map_err(kill_error)?,
+        }
+        // Checked last right before SIGKILL is sent.
+        for _ in 0..5 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            if self.reaped.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+        }
+
+        // Still alive after the grace period, so stop asking nicely.
+        match exit::signal_pid(pid, Signal::SIGKILL) {
+            Err(e) if e != Errno::ESRCH => Err(kill_error(e)),
+            _ => Ok(()),
+        }
     }
 
     /// Get the terminal configuration.
@@ -504,58 +959,76 @@ impl Terminal {
         crate::output::Screenshot::new(screen)
     }
 
+    /// Snapshot the screen a wait should be evaluated against.
+    async fn wait_screen(&self, wait: &WaitBuilder) -> Screen {
+        if wait.get_include_scrollback() {
+            self.scrollback().await
+        } else {
+            self.screen().await
+        }
+    }
+
     /// Execute a wait condition.
-    async fn execute_wait(&self, wait: &WaitBuilder) -> Result<()> {
+    ///
+    /// The condition is re-evaluated only when the reader task reports new
+    /// output (or the process exits), so waits resolve as soon as the screen
+    /// matches and cost nothing while the terminal is quiet. Returns the index
+    /// of the matching branch (see [`WaitCondition::Any`]).
+    async fn execute_wait(&self, wait: &WaitBuilder) -> Result<usize> {
         let condition = wait.condition();
         let deadline = Instant::now() + wait.get_timeout();
-        let poll_interval = wait.get_poll_interval();
-
-        // For screen stability, we need to track the stable duration
-        let stability_duration = if let WaitCondition::ScreenStable(d) = condition {
-            Some(*d)
-        } else {
-            None
-        };
-
-        let mut prev_screen: Option<Screen> = None;
-        let mut stable_since: Option<Instant> = None;
-
-        while Instant::now() < deadline {
-            let screen = self.screen().await;
-
-            // Check for process exit if that's what we're waiting for
-            if matches!(condition, WaitCondition::ProcessExit) {
-                if self.has_exited().await {
-                    return Ok(());
-                }
-            } else if let Some(stability_dur) = stability_duration {
-                // For screen stability, check if screen hasn't changed
-                let is_stable = prev_screen
-                    .as_ref()
-                    .map(|prev| prev.text() == screen.text())
-                    .unwrap_or(false);
-
-                if is_stable {
-                    if let Some(since) = stable_since {
-                        if since.elapsed() >= stability_dur {
-                            return Ok(());
-                        }
-                    } else {
-                        stable_since = Some(Instant::now());
-                    }
-                } else {
-                    stable_since = None;
-                }
+        let stability = condition.stability_durations();
+        let mut changes = self.generation.subscribe();
+
+        // For screen stability, track when output last changed the screen,
+        // so style and cursor changes count as well as text.
+        let mut stable_since = Instant::now();
+        let mut seen = *changes.borrow_and_update();
+
+        loop {
+            // Mark the current generation as seen before sampling, so output
+            // that arrives while we evaluate still wakes the next iteration.
+            // New output, or a held back frame about to be shown, changes the
+            // screen.
+            let generation = *changes.borrow_and_update();
+            let released = self.sync_deadline().is_some_and(|at| at <= Instant::now());
+            if generation != seen || released {
+                stable_since = Instant::now();
+                seen = generation;
+            }
 
-                prev_screen = Some(screen);
-            } else if condition.is_satisfied(&screen, prev_screen.as_ref()) {
-                return Ok(());
+            let screen = self.wait_screen(wait).await;
+
+            let context = WaitContext {
+                exited: self.has_exited().await,
+                stable_for: stable_since.elapsed(),
+                clipboard: self.state.clipboard(),
+                shell: self.shell_status(),
+                output: self.output.clone(),
+                output_patterns: wait.output_patterns(),
+            };
+            if let Some(branch) = condition.matching_branch(&screen, &context) {
+                return Ok(branch);
+            }
+            if Instant::now() >= deadline {
+                return Err(wait.timeout_error_with_screen(screen));
             }
 
-            tokio::time::sleep(poll_interval).await;
+            // Wake for the next stability threshold, or when a synchronized
+            // update times out, even if nothing changes.
+            let wake_at = stability
+                .iter()
+                .map(|duration| stable_since + *duration)
+                .chain(self.sync_deadline())
+                .filter(|at| *at > Instant::now())
+                .min()
+                .map_or(deadline, |at| at.min(deadline));
+
+            tokio::select! {
+                _ = changes.changed() => {}
+                _ = tokio::time::sleep_until(wake_at) => {}
+            }
         }
-
-        Err(wait.timeout_error())
     }
 }
 
@@ -572,9 +1045,15 @@ impl<'a> ExpectBuilder<'a> {
         self
     }
 
+    /// Also search output that has scrolled off the visible screen.
+    pub fn include_scrollback(mut self) -> Self {
+        self.wait = self.wait.include_scrollback(true);
+        self
+    }
+
     /// Execute the wait and return when the condition is met.
     pub async fn await_condition(self) -> Result<()> {
-        self.terminal.execute_wait(&self.wait).await
+        self.terminal.execute_wait(&self.wait).await.map(|_| ())
     }
 }
 
@@ -589,6 +1068,50 @@ impl<'a> std::future::IntoFuture for ExpectBuilder<'a> {
     }
 }
 
+/// Builder for [`Terminal::expect_any`], resolving to the matched branch.
+pub struct ExpectAnyBuilder<'a> {
+    terminal: &'a Terminal,
+    wait: WaitBuilder,
+}
+
+impl<'a> ExpectAnyBuilder<'a> {
+    /// Set the timeout for this wait operation.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.wait = self.wait.timeout(timeout);
+        self
+    }
+
+    /// Also search output that has scrolled off the visible screen.
+    pub fn include_scrollback(mut self) -> Self {
+        self.wait = self.wait.include_scrollback(true);
+        self
+    }
+
+    /// Execute the wait and return the index of the condition that matched.
+    pub async fn await_condition(self) -> Result<usize> {
+        self.terminal.execute_wait(&self.wait).await
+    }
+}
+
+impl<'a> std::future::IntoFuture for ExpectAnyBuilder<'a> {
+    type Output = Result<usize>;
+    type IntoFuture =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Self::Output> + Send + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.await_condition())
+    }
+}
+
+/// `TERM` stored in recording headers: the one the application was given,
+/// or the one matching the terminal identity.
+fn recording_term(config: &TerminalConfig) -> &str {
+    config
+        .env
+        .get("TERM")
+        .map_or(config.identity.term(), String::as_str)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -600,6 +1123,13 @@ mod tests {
         assert_eq!(config.rows, DEFAULT_ROWS);
     }
 
+    #[test]
+    fn test_builder_scrollback() {
+        let builder = TerminalBuilder::new().scrollback(5000);
+        assert_eq!(builder.config.scrollback, 5000);
+        assert_eq!(TerminalConfig::default().scrollback, DEFAULT_SCROLLBACK);
+    }
+
     #[test]
     fn test_builder_size() {
         let builder = TerminalBuilder::new().size(120, 40);
@@ -1,10 +1,14 @@
-//! CSI query emulation for PTY-hosted applications.
+//! CSI and DCS query emulation for PTY-hosted applications.
 
-use crate::screen::Position;
+use super::identity::TerminalIdentity;
+use crate::screen::{CursorStyle, MouseEncoding, MouseMode, Position, TerminalModes};
 
 const ESC: u8 = 0x1b;
 const CSI_C1: u8 = 0x9b;
 
+/// Maximum depth of the kitty keyboard flags stack; older entries are dropped.
+const KITTY_STACK_LIMIT: usize = 16;
+
 #[derive(Debug, Default)]
 enum ParserState {
     #[default]
@@ -13,11 +17,40 @@ enum ParserState {
     Csi {
         buf: Vec<u8>,
     },
+    Dcs {
+        buf: Vec<u8>,
+    },
+}
+
+/// Modes the application has requested that vt100 does not track.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputModes {
+    /// Active kitty keyboard protocol flags.
+    pub kitty_flags: u8,
+    /// xterm modifyOtherKeys level (0, 1 or 2).
+    pub modify_other_keys: u8,
+    /// Mouse encoding set by mode 1015 or 1016, overriding the one vt100
+    /// reports.
+    pub mouse_encoding: Option<MouseEncoding>,
+    /// Cursor style set with DECSCUSR.
+    pub cursor_style: CursorStyle,
 }
 
 #[derive(Debug, Default)]
 pub struct CsiEmulator {
     parser_state: ParserState,
+    identity: TerminalIdentity,
+    /// Modes reported by vt100, used to answer DECRQM.
+    screen_modes: TerminalModes,
+    /// DECOM, which vt100 honours but does not expose.
+    origin_mode: bool,
+    /// Kitty keyboard flags stack; the last entry is active.
+    kitty_stack: Vec<u8>,
+    modify_other_keys: u8,
+    mouse_encoding: Option<MouseEncoding>,
+    cursor_style: CursorStyle,
+    /// Synchronized output (mode 2026).
+    synchronized_output: bool,
 }
 
 impl CsiEmulator {
@@ -25,6 +58,28 @@ impl CsiEmulator {
         Self::default()
     }
 
+    /// Answer device attribute and version queries as `identity`.
+    pub fn with_identity(mut self, identity: TerminalIdentity) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Update the modes vt100 reports, before processing the output that
+    /// produced them.
+    pub fn set_screen_modes(&mut self, modes: TerminalModes) {
+        self.screen_modes = modes;
+    }
+
+    /// Input modes currently enabled by the application.
+    pub fn input_modes(&self) -> InputModes {
+        InputModes {
+            kitty_flags: self.kitty_stack.last().copied().unwrap_or(0),
+            modify_other_keys: self.modify_other_keys,
+            mouse_encoding: self.mouse_encoding,
+            cursor_style: self.cursor_style,
+        }
+    }
+
     pub fn process_output(&mut self, bytes: &[u8], cursor: Position) -> Vec<Vec<u8>> {
         let mut responses = Vec::new();
 
@@ -38,15 +93,16 @@ impl CsiEmulator {
                     }
                 }
                 ParserState::Esc => {
-                    if byte == b'[' {
-                        self.parser_state = ParserState::Csi { buf: Vec::new() };
-                    } else {
-                        self.parser_state = ParserState::Ground;
-                    }
+                    self.parser_state = match byte {
+                        b'[' => ParserState::Csi { buf: Vec::new() },
+                        b'P' => ParserState::Dcs { buf: Vec::new() },
+                        _ => ParserState::Ground,
+                    };
                 }
                 ParserState::Csi { buf } => {
                     if (0x40..=0x7e).contains(&byte) {
-                        if let Some(response) = handle_csi_query(buf, byte, cursor) {
+                        let params = std::mem::take(buf);
+                        if let Some(response) = self.handle_csi(&params, byte, cursor) {
                             responses.push(response);
                         }
                         self.parser_state = ParserState::Ground;
@@ -56,19 +112,241 @@ impl CsiEmulator {
                         self.parser_state = ParserState::Ground;
                     }
                 }
+                ParserState::Dcs { buf } => {
+                    // ESC starts the ST that ends the string; its `\` is
+                    // then consumed as an ordinary escape sequence.
+                    if byte == ESC {
+                        let payload = std::mem::take(buf);
+                        responses.extend(self.handle_dcs(&payload));
+                        self.parser_state = ParserState::Esc;
+                    } else {
+                        buf.push(byte);
+                    }
+                }
             }
         }
 
         responses
     }
-}
 
-fn handle_csi_query(params: &[u8], final_byte: u8, cursor: Position) -> Option<Vec<u8>> {
-    if final_byte != b'n' {
-        return None;
+    fn handle_csi(&mut self, params: &[u8], final_byte: u8, cursor: Position) -> Option<Vec<u8>> {
+        if final_byte == b'q' {
+            if let Some(ps) = params.strip_suffix(b" ") {
+                self.set_cursor_style(ps);
+                return None;
+            }
+        }
+
+        let params = std::str::from_utf8(params).ok()?.trim();
+        match final_byte {
+            b'n' => cursor_report(params, cursor),
+            b'c' => self.device_attributes(params),
+            b'q' if matches!(params, ">" | ">0") => self.version_report(),
+            b'p' => self.mode_report(params.strip_suffix('$')?),
+            b'u' => self.handle_kitty_keyboard(params),
+            b'm' => self.handle_modify_other_keys(params),
+            b'h' | b'l' => {
+                self.handle_private_mode(params, final_byte == b'h');
+                None
+            }
+            _ => None,
+        }
     }
 
-    let params = std::str::from_utf8(params).ok()?.trim();
+    /// Track the mouse encodings vt100 does not know about. Every encoding
+    /// mode replaces the previous one, so 1005 and 1006 clear the override
+    /// and let vt100's state apply again.
+    fn handle_private_mode(&mut self, params: &str, set: bool) {
+        let Some(modes) = params.strip_prefix('?') else {
+            return;
+        };
+        for mode in modes.split(';') {
+            let encoding = match mode {
+                "6" => {
+                    self.origin_mode = set;
+                    continue;
+                }
+                "2026" => {
+                    self.synchronized_output = set;
+                    continue;
+                }
+                "1015" => MouseEncoding::Urxvt,
+                "1016" => MouseEncoding::SgrPixels,
+                "1005" | "1006" if set => {
+                    self.mouse_encoding = None;
+                    continue;
+                }
+                _ => continue,
+            };
+            if set {
+                self.mouse_encoding = Some(encoding);
+            } else if self.mouse_encoding == Some(encoding) {
+                self.mouse_encoding = None;
+            }
+        }
+    }
+
+    /// DECSCUSR (`CSI Ps SP q`); an empty parameter means 0.
+    fn set_cursor_style(&mut self, ps: &[u8]) {
+        let ps = std::str::from_utf8(ps).unwrap_or_default().trim();
+        let ps = if ps.is_empty() {
+            Some(0)
+        } else {
+            ps.parse().ok()
+        };
+        if let Some(style) = ps.and_then(CursorStyle::from_decscusr) {
+            self.cursor_style = style;
+        }
+    }
+
+    /// DA1 (`CSI c`) and DA2 (`CSI > c`).
+    fn device_attributes(&self, params: &str) -> Option<Vec<u8>> {
+        let reply = match params {
+            "" | "0" => format!("\u{1b}[?{}c", self.identity.primary_attributes()),
+            ">" | ">0" => format!("\u{1b}[>{}c", self.identity.secondary_attributes()),
+            _ => return None,
+        };
+        Some(reply.into_bytes())
+    }
+
+    /// XTVERSION (`CSI > q`).
+    fn version_report(&self) -> Option<Vec<u8>> {
+        let version = self.identity.version()?;
+        Some(format!("\u{1b}P>|{version}\u{1b}\\").into_bytes())
+    }
+
+    /// DECRQM (`CSI ? Pm $ p`), answered for the private modes vt100 and
+    /// this emulator track; everything else is reported as not recognized.
+    fn mode_report(&self, params: &str) -> Option<Vec<u8>> {
+        if !self.identity.answers_extended_queries() {
+            return None;
+        }
+
+        let Some(mode) = params.strip_prefix('?') else {
+            // No ANSI modes are emulated.
+            return Some(format!("\u{1b}[{params};0$y").into_bytes());
+        };
+        let modes = &self.screen_modes;
+        let encoding = self.mouse_encoding.unwrap_or(modes.mouse_encoding);
+        let set = match mode {
+            "1" => Some(modes.application_cursor),
+            "6" => Some(self.origin_mode),
+            "9" => Some(modes.mouse_mode == MouseMode::Press),
+            "25" => Some(!modes.cursor_hidden),
+            "47" | "1049" => Some(modes.alternate_screen),
+            "1000" => Some(modes.mouse_mode == MouseMode::PressRelease),
+            "1002" => Some(modes.mouse_mode == MouseMode::ButtonMotion),
+            "1003" => Some(modes.mouse_mode == MouseMode::AnyMotion),
+            "1005" => Some(encoding == MouseEncoding::Utf8),
+            "1006" => Some(encoding == MouseEncoding::Sgr),
+            "1015" => Some(encoding == MouseEncoding::Urxvt),
+            "1016" => Some(encoding == MouseEncoding::SgrPixels),
+            "2004" => Some(modes.bracketed_paste),
+            "2026" => Some(self.synchronized_output),
+            _ => None,
+        };
+        let state = match set {
+            Some(true) => 1,
+            Some(false) => 2,
+            None => 0,
+        };
+        Some(format!("\u{1b}[?{mode};{state}$y").into_bytes())
+    }
+
+    /// XTGETTCAP (`DCS + q names ST`), where names are hex-encoded and
+    /// separated by `;`, each getting its own reply, and the DECRQSS query
+    /// for the cursor style (`DCS $ q SP q ST`).
+    fn handle_dcs(&self, payload: &[u8]) -> Vec<Vec<u8>> {
+        if !self.identity.answers_extended_queries() {
+            return Vec::new();
+        }
+        if payload == b"$q q" {
+            let ps = self.cursor_style.decscusr();
+            return vec![format!("\u{1b}P1$r{ps} q\u{1b}\\").into_bytes()];
+        }
+        let Some(names) = payload.strip_prefix(b"+q") else {
+            return Vec::new();
+        };
+
+        names
+            .split(|&byte| byte == b';')
+            .map(|hex_name| {
+                let hex_name = String::from_utf8_lossy(hex_name);
+                let value = decode_hex(&hex_name).and_then(|name| self.identity.capability(&name));
+                match value {
+                    Some(value) => {
+                        format!("\u{1b}P1+r{hex_name}={}\u{1b}\\", encode_hex(value))
+                    }
+                    None => format!("\u{1b}P0+r{hex_name}\u{1b}\\"),
+                }
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    /// Kitty keyboard protocol: `CSI ? u` query, `CSI > flags u` push,
+    /// `CSI < n u` pop and `CSI = flags ; mode u` set.
+    fn handle_kitty_keyboard(&mut self, params: &str) -> Option<Vec<u8>> {
+        let (prefix, rest) = params.split_at(params.len().min(1));
+        let mut args = rest.split(';').map(|arg| arg.parse::<u8>().ok());
+        let first = args.next().flatten();
+
+        match prefix {
+            "?" => {
+                if !self.identity.answers_kitty_keyboard_query() {
+                    return None;
+                }
+                let flags = self.input_modes().kitty_flags;
+                return Some(format!("\u{1b}[?{flags}u").into_bytes());
+            }
+            ">" => {
+                if self.kitty_stack.len() == KITTY_STACK_LIMIT {
+                    self.kitty_stack.remove(0);
+                }
+                self.kitty_stack.push(first.unwrap_or(0));
+            }
+            "<" => {
+                let count = usize::from(first.unwrap_or(1).max(1));
+                let keep = self.kitty_stack.len().saturating_sub(count);
+                self.kitty_stack.truncate(keep);
+            }
+            "=" => {
+                let flags = first.unwrap_or(0);
+                let current = self.input_modes().kitty_flags;
+                let updated = match args.next().flatten().unwrap_or(1) {
+                    2 => current | flags,
+                    3 => current & !flags,
+                    _ => flags,
+                };
+                match self.kitty_stack.last_mut() {
+                    Some(top) => *top = updated,
+                    None => self.kitty_stack.push(updated),
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// xterm modifyOtherKeys: `CSI > 4 ; level m` sets the level, `CSI > 4 m`
+    /// resets it and `CSI ? 4 m` queries it.
+    fn handle_modify_other_keys(&mut self, params: &str) -> Option<Vec<u8>> {
+        if params == "?4" {
+            let level = self.modify_other_keys;
+            return Some(format!("\u{1b}[>4;{level}m").into_bytes());
+        }
+
+        let rest = params.strip_prefix(">4")?;
+        self.modify_other_keys = match rest.strip_prefix(';') {
+            Some(level) => level.parse::<u8>().ok()?.min(2),
+            None if rest.is_empty() => 0,
+            None => return None,
+        };
+        None
+    }
+}
+
+fn cursor_report(params: &str, cursor: Position) -> Option<Vec<u8>> {
     let row = cursor.row.saturating_add(1);
     let col = cursor.col.saturating_add(1);
 
@@ -79,9 +357,25 @@ fn handle_csi_query(params: &[u8], final_byte: u8, cursor: Position) -> Option<V
     }
 }
 
+fn decode_hex(hex: &str) -> Option<String> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+fn encode_hex(text: &str) -> String {
+    text.bytes().map(|byte| format!("{byte:02X}")).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::screen::CursorShape;
 
     #[test]
     fn responds_to_csi_6n() {
@@ -121,4 +415,173 @@ mod tests {
                 .is_empty()
         );
     }
+
+    #[test]
+    fn tracks_kitty_keyboard_stack() {
+        let mut csi = CsiEmulator::new().with_identity(TerminalIdentity::Kitty);
+        let cursor = Position::new(0, 0);
+        assert_eq!(
+            csi.process_output(b"\x1b[?u", cursor),
+            vec![b"\x1b[?0u".to_vec()]
+        );
+
+        csi.process_output(b"\x1b[>1u", cursor);
+        csi.process_output(b"\x1b[>9u", cursor);
+        assert_eq!(csi.input_modes().kitty_flags, 9);
+        assert_eq!(
+            csi.process_output(b"\x1b[?u", cursor),
+            vec![b"\x1b[?9u".to_vec()]
+        );
+
+        csi.process_output(b"\x1b[=2;3u", cursor);
+        assert_eq!(csi.input_modes().kitty_flags, 9);
+        csi.process_output(b"\x1b[=8;3u", cursor);
+        assert_eq!(csi.input_modes().kitty_flags, 1);
+
+        csi.process_output(b"\x1b[<u", cursor);
+        assert_eq!(csi.input_modes().kitty_flags, 1);
+        csi.process_output(b"\x1b[<5u", cursor);
+        assert_eq!(csi.input_modes().kitty_flags, 0);
+    }
+
+    #[test]
+    fn answers_kitty_keyboard_query_only_as_kitty() {
+        let cursor = Position::new(0, 0);
+        for identity in [TerminalIdentity::Xterm, TerminalIdentity::Minimal] {
+            let mut csi = CsiEmulator::new().with_identity(identity);
+            csi.process_output(b"\x1b[>1u", cursor);
+            assert!(csi.process_output(b"\x1b[?u", cursor).is_empty());
+            assert_eq!(csi.input_modes().kitty_flags, 1);
+        }
+    }
+
+    #[test]
+    fn tracks_modify_other_keys() {
+        let mut csi = CsiEmulator::new();
+        let cursor = Position::new(0, 0);
+        csi.process_output(b"\x1b[>4;2m", cursor);
+        assert_eq!(csi.input_modes().modify_other_keys, 2);
+        assert_eq!(
+            csi.process_output(b"\x1b[?4m", cursor),
+            vec![b"\x1b[>4;2m".to_vec()]
+        );
+        // Plain SGR sequences are left alone.
+        csi.process_output(b"\x1b[1;31m", cursor);
+        assert_eq!(csi.input_modes().modify_other_keys, 2);
+        csi.process_output(b"\x1b[>4m", cursor);
+        assert_eq!(csi.input_modes().modify_other_keys, 0);
+    }
+
+    #[test]
+    fn tracks_extended_mouse_encodings() {
+        let mut csi = CsiEmulator::new();
+        let cursor = Position::new(0, 0);
+        csi.process_output(b"\x1b[?1000;1015h", cursor);
+        assert_eq!(csi.input_modes().mouse_encoding, Some(MouseEncoding::Urxvt));
+        csi.process_output(b"\x1b[?1016h", cursor);
+        assert_eq!(
+            csi.input_modes().mouse_encoding,
+            Some(MouseEncoding::SgrPixels)
+        );
+        csi.process_output(b"\x1b[?1015l", cursor);
+        assert_eq!(
+            csi.input_modes().mouse_encoding,
+            Some(MouseEncoding::SgrPixels)
+        );
+        csi.process_output(b"\x1b[?1006h", cursor);
+        assert_eq!(csi.input_modes().mouse_encoding, None);
+    }
+
+    #[test]
+    fn answers_device_attributes_per_identity() {
+        let cursor = Position::new(0, 0);
+        let mut xterm = CsiEmulator::new();
+        assert_eq!(
+            xterm.process_output(b"\x1b[c\x1b[>0c\x1b[>q", cursor),
+            vec![
+                b"\x1b[?64;1;2;6;9;15;16;17;18;21;22;28c".to_vec(),
+                b"\x1b[>41;388;0c".to_vec(),
+                b"\x1bP>|XTerm(388)\x1b\\".to_vec(),
+            ]
+        );
+
+        let mut kitty = CsiEmulator::new().with_identity(TerminalIdentity::Kitty);
+        assert_eq!(
+            kitty.process_output(b"\x1b[>q", cursor),
+            vec![b"\x1bP>|kitty(0.40.0)\x1b\\".to_vec()]
+        );
+
+        let mut minimal = CsiEmulator::new().with_identity(TerminalIdentity::Minimal);
+        assert_eq!(
+            minimal.process_output(b"\x1b[>q\x1b[?2004$p\x1bP+q544e\x1b\\\x1b[0c", cursor),
+            vec![b"\x1b[?1;2c".to_vec()]
+        );
+    }
+
+    #[test]
+    fn reports_tracked_modes_with_decrqm() {
+        let cursor = Position::new(0, 0);
+        let mut csi = CsiEmulator::new();
+        csi.set_screen_modes(TerminalModes {
+            bracketed_paste: true,
+            mouse_mode: MouseMode::ButtonMotion,
+            ..TerminalModes::default()
+        });
+        csi.process_output(b"\x1b[?6h\x1b[?1016h\x1b[?2026h", cursor);
+
+        assert_eq!(
+            csi.process_output(
+                b"\x1b[?2004$p\x1b[?25$p\x1b[?1002$p\x1b[?1000$p\x1b[?6$p\x1b[?1016$p\x1b[?2026$p\x1b[?7727$p\x1b[4$p",
+                cursor
+            ),
+            vec![
+                b"\x1b[?2004;1$y".to_vec(),
+                b"\x1b[?25;1$y".to_vec(),
+                b"\x1b[?1002;1$y".to_vec(),
+                b"\x1b[?1000;2$y".to_vec(),
+                b"\x1b[?6;1$y".to_vec(),
+                b"\x1b[?1016;1$y".to_vec(),
+                b"\x1b[?2026;1$y".to_vec(),
+                b"\x1b[?7727;0$y".to_vec(),
+                b"\x1b[4;0$y".to_vec(),
+            ]
+        );
+    }
+
+    #[test]
+    fn answers_xtgettcap_per_name() {
+        let mut csi = CsiEmulator::new().with_identity(TerminalIdentity::Kitty);
+        // TN;Co;xx
+        let responses = csi.process_output(b"\x1bP+q544e;436f;7878\x1b\\", Position::new(0, 0));
+        assert_eq!(
+            responses,
+            vec![
+                b"\x1bP1+r544e=787465726D2D6B69747479\x1b\\".to_vec(),
+                b"\x1bP1+r436f=323536\x1b\\".to_vec(),
+                b"\x1bP0+r7878\x1b\\".to_vec(),
+            ]
+        );
+    }
+
+    #[test]
+    fn tracks_decscusr_and_answers_decrqss() {
+        let cursor = Position::new(0, 0);
+        let mut csi = CsiEmulator::new();
+        assert_eq!(csi.input_modes().cursor_style, CursorStyle::default());
+
+        assert!(csi.process_output(b"\x1b[6 q", cursor).is_empty());
+        let style = csi.input_modes().cursor_style;
+        assert_eq!(style.shape, CursorShape::Bar);
+        assert!(!style.blinking);
+
+        csi.process_output(b"\x1b[3 q\x1b[9 q", cursor);
+        assert_eq!(csi.input_modes().cursor_style.shape, CursorShape::Underline);
+        assert_eq!(
+            csi.process_output(b"\x1bP$q q\x1b\\", cursor),
+            vec![b"\x1bP1$r3 q\x1b\\".to_vec()]
+        );
+
+        csi.process_output(b"\x1b[ q", cursor);
+        assert_eq!(csi.input_modes().cursor_style, CursorStyle::default());
+    }
 }
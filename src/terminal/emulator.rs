@@ -0,0 +1,344 @@
+//! The emulation pipeline output passes through on its way to the screen.
+//!
+//! vt100 does the heavy lifting; around it, synchronized updates are held
+//! back, hyperlinks and prompt marks are tracked, and the OSC and CSI
+//! emulators answer queries and follow the modes vt100 ignores. A live
+//! [`Terminal`](super::Terminal) and a [`Replay`](crate::replay::Replay)
+//! feed their output through the same pipeline.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::csi::{CsiEmulator, InputModes};
+use super::hyperlink::HyperlinkTracker;
+use super::identity::TerminalIdentity;
+use super::osc::{Clipboard, OscEmulator, initial_color_state};
+use super::prompt::{PromptTracker, ShellStatus};
+use super::sync::SyncOutput;
+use crate::screen::{Position, Screen, TerminalModes};
+
+/// State tracked beside the vt100 parser, shared between the side feeding
+/// output and the side taking snapshots.
+#[derive(Debug, Clone)]
+pub(crate) struct EmulatorState {
+    /// Input modes requested by the application that vt100 does not track.
+    pub input_modes: Arc<Mutex<InputModes>>,
+    /// Clipboard selections written by OSC 52 or seeded by tests.
+    pub clipboard: Arc<Mutex<Clipboard>>,
+    /// Cells printed inside OSC 8 hyperlinks.
+    pub hyperlinks: Arc<Mutex<HyperlinkTracker>>,
+    /// Prompt marks and working directory reported by shell integration.
+    pub shell: Arc<Mutex<PromptTracker>>,
+    /// Output held back while a synchronized update is drawn.
+    pub sync: Arc<Mutex<SyncOutput>>,
+    /// Emulators following only the output that is shown.
+    shown: Arc<Mutex<ShownEmulators>>,
+}
+
+/// OSC and CSI emulators fed the output as it is shown, so the clipboard
+/// and cursor style that snapshots and waits see never come from a
+/// synchronized update still being drawn. Their replies are discarded.
+#[derive(Debug, Default)]
+struct ShownEmulators {
+    osc: Option<OscEmulator>,
+    csi: CsiEmulator,
+}
+
+impl EmulatorState {
+    pub fn new(sync_timeout: Duration) -> Self {
+        Self {
+            input_modes: Arc::default(),
+            clipboard: Arc::default(),
+            hyperlinks: Arc::new(Mutex::new(HyperlinkTracker::new())),
+            shell: Arc::new(Mutex::new(PromptTracker::new())),
+            sync: Arc::new(Mutex::new(SyncOutput::new(sync_timeout))),
+            shown: Arc::default(),
+        }
+    }
+
+    /// Feed output that is ready to be shown to the parser and the state
+    /// tracked beside it.
+    fn show(&self, parser: &mut vt100::Parser, bytes: &[u8]) {
+        match self.hyperlinks.lock() {
+            Ok(mut hyperlinks) => hyperlinks.process(parser, bytes),
+            Err(_) => parser.process(bytes),
+        }
+        if let Ok(mut shown) = self.shown.lock() {
+            if let Some(osc) = shown.osc.as_mut() {
+                osc.process_output(bytes);
+            }
+            shown.csi.process_output(bytes, Position::new(0, 0));
+        }
+        if let Ok(mut shell) = self.shell.lock() {
+            shell.process_output(bytes, parser.screen().size().1);
+        }
+    }
+
+    /// Show output of a synchronized update that has been held back for
+    /// longer than the sync timeout at `now`.
+    pub fn release_expired(&self, parser: &mut vt100::Parser, now: Instant) {
+        let expired = self
+            .sync
+            .lock()
+            .ok()
+            .and_then(|mut sync| sync.release_expired(now));
+        if let Some(bytes) = expired {
+            self.show(parser, &bytes);
+        }
+    }
+
+    /// When held back output of a synchronized update will be shown.
+    pub fn sync_deadline(&self) -> Option<Instant> {
+        self.sync.lock().ok()?.deadline()
+    }
+
+    /// Number of synchronized updates the application finished drawing.
+    pub fn frames(&self) -> u64 {
+        self.sync
+            .lock()
+            .map(|sync| sync.frames())
+            .unwrap_or_default()
+    }
+
+    pub fn clipboard(&self) -> Clipboard {
+        self.clipboard
+            .lock()
+            .map(|clipboard| clipboard.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn shell_status(&self) -> ShellStatus {
+        self.shell
+            .lock()
+            .map(|shell| shell.status())
+            .unwrap_or_default()
+    }
+
+    /// Snapshot the visible screen.
+    pub fn screen(&self, parser: &vt100::Parser) -> Screen {
+        let mut screen = Screen::from_vt100(parser.screen());
+        self.apply_input_modes(&mut screen.modes);
+        self.apply_hyperlinks(parser.screen(), &mut screen, 0);
+        screen
+    }
+
+    /// Snapshot the screen with the scrollback history above it.
+    pub fn scrollback(&self, parser: &mut vt100::Parser) -> Screen {
+        let mut screen = Screen::with_history(parser);
+        self.apply_input_modes(&mut screen.modes);
+        let history = screen.size.rows - parser.screen().size().0;
+        self.apply_hyperlinks(parser.screen(), &mut screen, history);
+        screen
+    }
+
+    pub fn modes(&self, vt_screen: &vt100::Screen) -> TerminalModes {
+        let mut modes = TerminalModes::from_vt100(vt_screen);
+        self.apply_input_modes(&mut modes);
+        modes
+    }
+
+    /// Fill in the input modes tracked by the CSI emulator.
+    fn apply_input_modes(&self, modes: &mut TerminalModes) {
+        if let Ok(input_modes) = self.input_modes.lock() {
+            modes.kitty_keyboard_flags = input_modes.kitty_flags;
+            modes.modify_other_keys = input_modes.modify_other_keys;
+            if let Some(encoding) = input_modes.mouse_encoding {
+                modes.mouse_encoding = encoding;
+            }
+        }
+        if let Ok(shown) = self.shown.lock() {
+            modes.cursor_style = shown.csi.input_modes().cursor_style;
+        }
+    }
+
+    /// Mark the cells printed inside OSC 8 hyperlinks, offsetting rows by
+    /// the number of history lines above the visible screen.
+    fn apply_hyperlinks(&self, vt_screen: &vt100::Screen, screen: &mut Screen, history: u16) {
+        if let Ok(hyperlinks) = self.hyperlinks.lock() {
+            for (mut position, link) in hyperlinks.links(vt_screen) {
+                position.row += history;
+                screen.set_hyperlink(position, link);
+            }
+        }
+    }
+}
+
+/// Feeds output through the pipeline, updating the shared state.
+pub(crate) struct Emulator {
+    state: EmulatorState,
+    osc: Option<OscEmulator>,
+    /// Clipboard as the application has drawn it, including writes inside
+    /// an open synchronized update, for answering OSC 52 reads.
+    clipboard: Arc<Mutex<Clipboard>>,
+    csi: CsiEmulator,
+    /// Parser that also sees the output of an open synchronized update, so
+    /// queries inside the frame are answered from the state the application
+    /// has drawn rather than the state still shown.
+    shadow: Option<vt100::Parser>,
+}
+
+impl Emulator {
+    pub fn new(state: EmulatorState, identity: TerminalIdentity, osc_emulation: bool) -> Self {
+        let clipboard = Arc::new(Mutex::new(state.clipboard()));
+        let osc = osc_emulation
+            .then(|| OscEmulator::new(initial_color_state()).with_clipboard(clipboard.clone()));
+        if let Ok(mut shown) = state.shown.lock() {
+            shown.osc = osc_emulation.then(|| {
+                OscEmulator::new(initial_color_state()).with_clipboard(state.clipboard.clone())
+            });
+        }
+        Self {
+            state,
+            osc,
+            clipboard,
+            csi: CsiEmulator::new().with_identity(identity),
+            shadow: None,
+        }
+    }
+
+    /// Process output written by the application at `now`, returning the
+    /// replies to any queries it contained.
+    pub fn process(
+        &mut self,
+        parser: &mut vt100::Parser,
+        bytes: &[u8],
+        now: Instant,
+    ) -> Vec<Vec<u8>> {
+        // Outside a frame the drawn clipboard is the shown one, which tests
+        // may also have seeded.
+        if self.shadow.is_none() {
+            if let Ok(mut clipboard) = self.clipboard.lock() {
+                *clipboard = self.state.clipboard();
+            }
+        }
+
+        let (ready, held) = match self.state.sync.lock() {
+            Ok(mut sync) => (sync.process(bytes, now), sync.held()),
+            Err(_) => (bytes.to_vec(), 0),
+        };
+        self.state.show(parser, &ready);
+        self.follow_held(parser, bytes, held);
+
+        let drawn = self.shadow.as_ref().unwrap_or(parser).screen();
+        let cursor = {
+            let cursor = drawn.cursor_position();
+            Position::new(cursor.0, cursor.1)
+        };
+
+        let mut responses = Vec::new();
+        if let Some(osc) = self.osc.as_mut() {
+            responses.extend(osc.process_output(bytes));
+        }
+        self.csi.set_screen_modes(TerminalModes::from_vt100(drawn));
+        responses.extend(self.csi.process_output(bytes, cursor));
+        if let Ok(mut input_modes) = self.state.input_modes.lock() {
+            *input_modes = self.csi.input_modes();
+        }
+
+        responses
+    }
+
+    /// Keep the shadow parser in step with the `held` bytes of an open
+    /// frame, the last of which came from `bytes`.
+    ///
+    /// The shadow starts from what the parser shows when the frame opens.
+    /// Scroll regions and saved cursors are not carried over.
+    fn follow_held(&mut self, parser: &vt100::Parser, bytes: &[u8], held: usize) {
+        if held == 0 {
+            self.shadow = None;
+            return;
+        }
+        match self.shadow.as_mut() {
+            Some(shadow) if held > bytes.len() => {
+                let (rows, cols) = parser.screen().size();
+                if shadow.screen().size() != (rows, cols) {
+                    shadow.set_size(rows, cols);
+                }
+                shadow.process(bytes);
+            }
+            // The frame opened in these bytes: everything before it is shown.
+            _ => {
+                let screen = parser.screen();
+                let (rows, cols) = screen.size();
+                let mut shadow = vt100::Parser::new(rows, cols, 0);
+                if screen.alternate_screen() {
+                    shadow.process(b"\x1b[?1049h");
+                }
+                shadow.process(&screen.state_formatted());
+                shadow.process(&bytes[bytes.len().saturating_sub(held)..]);
+                self.shadow = Some(shadow);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_queries_inside_a_frame_from_the_drawn_state() {
+        let state = EmulatorState::new(Duration::from_secs(1));
+        let mut emulator = Emulator::new(state.clone(), TerminalIdentity::default(), false);
+        let mut parser = vt100::Parser::new(10, 20, 0);
+        let now = Instant::now();
+
+        let responses = emulator.process(&mut parser, b"\x1b[?2026h\x1b[3;5H\x1b[6n", now);
+        assert_eq!(responses, vec![b"\x1b[3;5R".to_vec()]);
+        assert_eq!(state.screen(&parser).cursor, Position::new(0, 0));
+
+        // The frame goes on across writes.
+        let responses = emulator.process(&mut parser, b"\x1b[?2004h\x1b[?2004$p\x1b[6n", now);
+        assert_eq!(
+            responses,
+            vec![b"\x1b[?2004;1$y".to_vec(), b"\x1b[3;5R".to_vec()]
+        );
+        assert!(!state.screen(&parser).modes.bracketed_paste);
+
+        let responses = emulator.process(&mut parser, b"\x1b[?2026lx\x1b[6n", now);
+        assert_eq!(responses, vec![b"\x1b[3;6R".to_vec()]);
+        assert_eq!(state.screen(&parser).cursor, Position::new(2, 5));
+    }
+
+    #[test]
+    fn waits_see_frame_state_only_once_it_is_shown() {
+        use crate::screen::CursorShape;
+        use crate::terminal::ClipboardSelection;
+        use crate::wait::{WaitCondition, WaitContext};
+
+        let state = EmulatorState::new(Duration::from_secs(1));
+        let mut emulator = Emulator::new(state.clone(), TerminalIdentity::default(), true);
+        let mut parser = vt100::Parser::new(10, 20, 0);
+        let now = Instant::now();
+        let conditions = [
+            WaitCondition::ClipboardContains(ClipboardSelection::Clipboard, "hi".into()),
+            WaitCondition::CommandFinished(0),
+            WaitCondition::CursorStyle(CursorShape::Bar),
+        ];
+        let matching = |parser: &vt100::Parser| {
+            let context = WaitContext {
+                clipboard: state.clipboard(),
+                shell: state.shell_status(),
+                ..WaitContext::default()
+            };
+            let screen = state.screen(parser);
+            conditions
+                .iter()
+                .filter(|condition| condition.matching_branch(&screen, &context).is_some())
+                .count()
+        };
+
+        emulator.process(&mut parser, b"\x1b]133;C\x07\x1b[?2026h", now);
+        // The clipboard read inside the frame still sees the write.
+        let responses = emulator.process(
+            &mut parser,
+            b"\x1b]52;c;aGk=\x07\x1b]52;c;?\x07\x1b]133;D;0\x07\x1b[6 q",
+            now,
+        );
+        assert_eq!(responses, vec![b"\x1b]52;c;aGk=\x07".to_vec()]);
+        assert_eq!(matching(&parser), 0);
+
+        emulator.process(&mut parser, b"\x1b[?2026l", now);
+        assert_eq!(matching(&parser), conditions.len());
+    }
+}
@@ -0,0 +1,129 @@
+//! Exit status reporting for the spawned child process.
+
+use nix::errno::Errno;
+use nix::sys::signal::{Signal, kill};
+use nix::sys::wait::{WaitStatus, waitpid};
+use nix::unistd::Pid;
+use portable_pty::Child;
+use serde::{Deserialize, Serialize};
+
+/// How the spawned process terminated.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExitStatus {
+    /// Exit code, if the process exited normally.
+    pub code: Option<i32>,
+    /// Name of the terminating signal (e.g. `SIGSEGV`), if killed by a signal.
+    pub signal: Option<String>,
+    /// Whether the process dumped core when it was killed.
+    pub core_dumped: bool,
+}
+
+impl ExitStatus {
+    /// Create a status for a process that exited with the given code.
+    pub fn from_code(code: i32) -> Self {
+        Self {
+            code: Some(code),
+            signal: None,
+            core_dumped: false,
+        }
+    }
+
+    /// Create a status for a process terminated by the given signal number.
+    pub fn from_signal(signal: i32, core_dumped: bool) -> Self {
+        Self {
+            code: None,
+            signal: Some(signal_name(signal)),
+            core_dumped,
+        }
+    }
+
+    /// Returns true if the process exited with code 0.
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+impl std::fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.code, &self.signal) {
+            (Some(code), _) => write!(f, "exited with code {code}"),
+            (None, Some(signal)) if self.core_dumped => {
+                write!(f, "killed by {signal} (core dumped)")
+            }
+            (None, Some(signal)) => write!(f, "killed by {signal}"),
+            (None, None) => write!(f, "exited with unknown status"),
+        }
+    }
+}
+
+impl From<portable_pty::ExitStatus> for ExitStatus {
+    /// Convert a status that only carries the signal's description, so the
+    /// signal is not known.
+    fn from(status: portable_pty::ExitStatus) -> Self {
+        if status.success() {
+            Self::from_code(0)
+        } else {
+            Self {
+                code: Some(status.exit_code() as i32),
+                ..Self::default()
+            }
+        }
+    }
+}
+
+/// Block until the child terminates and reap it.
+pub(crate) fn wait_for_child(child: &mut (dyn Child + Send + Sync)) -> ExitStatus {
+    // `Child::wait` reports a signal only by its description, so reap the
+    // process directly to keep the signal and core dump flag.
+    if let Some(pid) = child.process_id() {
+        loop {
+            match waitpid(Pid::from_raw(pid as i32), None) {
+                Ok(WaitStatus::Exited(_, code)) => return ExitStatus::from_code(code),
+                Ok(WaitStatus::Signaled(_, signal, core_dumped)) => {
+                    return ExitStatus::from_signal(signal as i32, core_dumped);
+                }
+                Ok(_) | Err(Errno::EINTR) => continue,
+                Err(_) => break,
+            }
+        }
+    }
+    child.wait().map(ExitStatus::from).unwrap_or_default()
+}
+
+/// Send a signal to the process with the given pid.
+pub(crate) fn signal_pid(pid: u32, signal: Signal) -> nix::Result<()> {
+    kill(Pid::from_raw(pid as i32), signal)
+}
+
+/// Map a signal number to its conventional name.
+pub fn signal_name(signal: i32) -> String {
+    match Signal::try_from(signal) {
+        Ok(signal) => signal.as_str().to_string(),
+        Err(_) => format!("SIG{signal}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_exit_code() {
+        assert_eq!(ExitStatus::from_code(2).to_string(), "exited with code 2");
+        assert!(ExitStatus::from_code(0).success());
+        assert!(!ExitStatus::from_code(2).success());
+    }
+
+    #[test]
+    fn display_signal() {
+        let status = ExitStatus::from_signal(Signal::SIGSEGV as i32, true);
+        assert_eq!(status.signal.as_deref(), Some("SIGSEGV"));
+        assert_eq!(status.to_string(), "killed by SIGSEGV (core dumped)");
+        assert!(!status.success());
+    }
+
+    #[test]
+    fn unknown_signal_name() {
+        assert_eq!(signal_name(200), "SIG200");
+    }
+}
@@ -0,0 +1,646 @@
+//! OSC 8 hyperlink tracking.
+//!
+//! vt100 ignores OSC 8, so the tracker watches the output for links opening
+//! and closing and records which cells are printed while a link is open.
+//! While a link is open or any linked cell is still on screen, control bytes
+//! and escape sequences are fed to the parser one at a time and runs of text
+//! that fit on the cursor's row in one piece, so every printed character can
+//! be attributed to its cell; otherwise output goes to the parser in one
+//! piece.
+//!
+//! Linked cells follow the lines vt100 scrolls, inserts and deletes, inside
+//! the scroll region set with DECSTBM, and the cells it inserts and deletes
+//! on a row. A linked cell that is moved any other way is kept only as long
+//! as the cell it was recorded in still holds the same character.
+
+use std::collections::HashMap;
+
+use crate::screen::{Hyperlink, Position};
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+#[derive(Debug, Default)]
+enum ParserState {
+    #[default]
+    Ground,
+    Esc,
+    EscIntermediate,
+    Csi {
+        params: Vec<u8>,
+    },
+    Osc {
+        buf: Vec<u8>,
+    },
+    /// DCS, SOS, PM or APC payload, skipped until ST.
+    Str,
+}
+
+/// A sequence completed by the byte just parsed.
+#[derive(Debug)]
+enum Sequence {
+    /// OSC 8 changed the active link.
+    Link,
+    /// A control sequence, with its parameter bytes and final byte.
+    Csi { params: Vec<u8>, action: u8 },
+    /// An escape sequence without intermediates.
+    Esc(u8),
+}
+
+/// A linked cell and the contents it had when the link was recorded, so
+/// cells that have since been overwritten or erased can be dropped.
+#[derive(Debug, Clone)]
+struct Mark {
+    link: Hyperlink,
+    contents: String,
+}
+
+#[derive(Debug, Default)]
+pub struct HyperlinkTracker {
+    state: ParserState,
+    active: Option<Hyperlink>,
+    /// Linked cells on the primary and alternate screens.
+    marks: [HashMap<(u16, u16), Mark>; 2],
+    /// Scroll regions of the primary and alternate screens, as first and
+    /// last row; `None` for the whole screen.
+    regions: [Option<(u16, u16)>; 2],
+    /// Rows of the screen when output was last processed, to follow
+    /// resizes.
+    rows: u16,
+}
+
+impl HyperlinkTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed output to the parser, recording the cells printed inside links.
+    pub fn process(&mut self, parser: &mut vt100::Parser, bytes: &[u8]) {
+        self.resize(parser.screen().size().0);
+        let mut flushed = 0;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let byte = bytes[i];
+            if !self.tracking() {
+                match self.advance(byte) {
+                    // A link opened; print everything before it in one go and
+                    // follow the rest byte by byte.
+                    Some(Sequence::Link) => {
+                        parser.process(&bytes[flushed..=i]);
+                        flushed = i + 1;
+                    }
+                    // DECSTBM sets the region of the screen in use, so show
+                    // everything before it first.
+                    Some(sequence @ Sequence::Csi { action: b'r', .. }) => {
+                        parser.process(&bytes[flushed..=i]);
+                        flushed = i + 1;
+                        let screen = parser.screen();
+                        self.apply(&sequence, screen, screen.cursor_position());
+                    }
+                    // Resets can clear regions; nothing else matters with no
+                    // linked cells to move.
+                    Some(sequence @ (Sequence::Esc(b'c') | Sequence::Csi { action: b'h', .. })) => {
+                        let screen = parser.screen();
+                        self.apply(&sequence, screen, screen.cursor_position());
+                    }
+                    _ => {}
+                }
+                i += 1;
+                continue;
+            }
+
+            let before = parser.screen().cursor_position();
+            let run = match self.state {
+                ParserState::Ground => bytes[i..]
+                    .iter()
+                    .take_while(|byte| **byte >= 0x20 && **byte != 0x7f)
+                    .count(),
+                _ => 0,
+            };
+            // UTF-8 takes at least as many bytes as columns, so a run with
+            // fewer bytes than the columns left cannot wrap.
+            if run > 1 && usize::from(before.1) + run < usize::from(parser.screen().size().1) {
+                parser.process(&bytes[i..i + run]);
+                i += run;
+                flushed = i;
+                self.record(parser.screen(), before);
+                continue;
+            }
+
+            parser.process(&[byte]);
+            i += 1;
+            flushed = i;
+            let sequence = self.advance(byte);
+
+            let screen = parser.screen();
+            if run > 0 {
+                self.record(screen, before);
+            } else if matches!(byte, b'\n' | 0x0b | 0x0c) {
+                self.line_feed(screen, before.0);
+            } else if let Some(sequence) = sequence {
+                self.apply(&sequence, screen, before);
+            }
+        }
+
+        parser.process(&bytes[flushed..]);
+        self.prune(parser.screen());
+    }
+
+    /// Linked cells on the visible screen.
+    pub fn links(&self, screen: &vt100::Screen) -> Vec<(Position, Hyperlink)> {
+        let mut links: Vec<_> = self.marks[usize::from(screen.alternate_screen())]
+            .iter()
+            .filter(|((row, col), mark)| still_holds(screen, *row, *col, mark))
+            .map(|((row, col), mark)| (Position::new(*row, *col), mark.link.clone()))
+            .collect();
+        links.sort_by_key(|(position, _)| (position.row, position.col));
+        links
+    }
+
+    fn tracking(&self) -> bool {
+        self.active.is_some() || self.marks.iter().any(|marks| !marks.is_empty())
+    }
+
+    /// Attribute the characters just printed to their cells.
+    fn record(&mut self, screen: &vt100::Screen, before: (u16, u16)) {
+        let (row, cursor_col) = screen.cursor_position();
+        if (row, cursor_col) == before || cursor_col == 0 {
+            // Nothing was printed, e.g. a combining character or the middle
+            // of a UTF-8 sequence.
+            return;
+        }
+
+        let cols = if row == before.0 && cursor_col > before.1 {
+            before.1..cursor_col
+        } else {
+            // The character wrapped onto the next row.
+            let mut col = cursor_col - 1;
+            if col > 0
+                && screen
+                    .cell(row, col)
+                    .is_some_and(|cell| cell.is_wide_continuation())
+            {
+                col -= 1;
+            }
+            if row == before.0 {
+                // The row did not change, so the wrap scrolled the screen.
+                self.line_feed(screen, row);
+            }
+            col..cursor_col
+        };
+
+        let marks = &mut self.marks[usize::from(screen.alternate_screen())];
+        for col in cols {
+            match &self.active {
+                Some(link) => {
+                    let contents = screen
+                        .cell(row, col)
+                        .map(|cell| cell.contents())
+                        .unwrap_or_default();
+                    marks.insert(
+                        (row, col),
+                        Mark {
+                            link: link.clone(),
+                            contents,
+                        },
+                    );
+                }
+                None => {
+                    marks.remove(&(row, col));
+                }
+            }
+        }
+    }
+
+    /// Follow a line feed from `row`, which scrolls the scroll region when
+    /// it leaves its last row.
+    fn line_feed(&mut self, screen: &vt100::Screen, row: u16) {
+        let (_, bottom) = self.region(screen);
+        if row == bottom {
+            self.scroll_up(screen, 1);
+        }
+    }
+
+    /// Follow what a completed sequence did to the screen, with the cursor
+    /// where it was `before` the sequence's last byte.
+    fn apply(&mut self, sequence: &Sequence, screen: &vt100::Screen, before: (u16, u16)) {
+        let (params, action) = match sequence {
+            Sequence::Link => return,
+            // RI, which scrolls down from the top of the scroll region and
+            // from the top row.
+            Sequence::Esc(b'M') => {
+                let (top, bottom) = self.region(screen);
+                if before.0 == top || before.0 == 0 {
+                    self.move_rows(screen, |rows| {
+                        rows.remove(usize::from(bottom));
+                        rows.insert(usize::from(top), None);
+                    });
+                }
+                return;
+            }
+            // RIS
+            Sequence::Esc(b'c') => {
+                self.regions = [None, None];
+                return;
+            }
+            Sequence::Esc(_) => return,
+            Sequence::Csi { params, action } => (params, *action),
+        };
+
+        let Some(params) = numbers(params) else {
+            // Entering the alternate screen with mode 1049 clears it.
+            let entered = action == b'h'
+                && params
+                    .strip_prefix(b"?")
+                    .and_then(numbers)
+                    .is_some_and(|modes| modes.contains(&1049));
+            if entered {
+                self.regions[1] = None;
+            }
+            return;
+        };
+        let count = match params.first() {
+            None | Some(0) => 1,
+            Some(count) => *count,
+        };
+        let (size_rows, size_cols) = screen.size();
+        let (top, bottom) = self.region(screen);
+        let (row, col) = before;
+
+        match action {
+            // SU
+            b'S' => self.scroll_up(screen, count),
+            // SD
+            b'T' => self.move_rows(screen, |rows| {
+                for _ in 0..count.min(size_rows) {
+                    rows.remove(usize::from(bottom));
+                    rows.insert(usize::from(top), None);
+                }
+            }),
+            // IL
+            b'L' => self.move_rows(screen, |rows| {
+                for _ in 0..count.min(size_rows) {
+                    rows.remove(usize::from(bottom));
+                    rows.insert(usize::from(row), None);
+                }
+            }),
+            // DL
+            b'M' => self.move_rows(screen, |rows| {
+                for _ in 0..count.min(size_rows - row) {
+                    rows.insert(usize::from(bottom) + 1, None);
+                    rows.remove(usize::from(row));
+                }
+            }),
+            // ICH
+            b'@' => self.move_cols(screen, row, |cell| {
+                if cell < col {
+                    Some(cell)
+                } else {
+                    Some(cell.saturating_add(count)).filter(|cell| *cell < size_cols)
+                }
+            }),
+            // DCH
+            b'P' => {
+                let count = count.min(size_cols.saturating_sub(col));
+                self.move_cols(screen, row, |cell| {
+                    if cell < col {
+                        Some(cell)
+                    } else {
+                        cell.checked_sub(count).filter(|cell| *cell >= col)
+                    }
+                })
+            }
+            // DECSTBM
+            b'r' => {
+                let top = params.first().copied().filter(|top| *top > 0).unwrap_or(1) - 1;
+                let bottom = params
+                    .get(1)
+                    .copied()
+                    .filter(|bottom| *bottom > 0)
+                    .unwrap_or(size_rows)
+                    .min(size_rows)
+                    - 1;
+                self.regions[usize::from(screen.alternate_screen())] =
+                    (top < bottom && (top, bottom) != (0, size_rows - 1)).then_some((top, bottom));
+            }
+            _ => {}
+        }
+    }
+
+    /// The first and last row of the scroll region of the screen in use.
+    fn region(&self, screen: &vt100::Screen) -> (u16, u16) {
+        self.regions[usize::from(screen.alternate_screen())]
+            .unwrap_or((0, screen.size().0.saturating_sub(1)))
+    }
+
+    /// Follow a resize to `rows`, which moves the end of a scroll region
+    /// reaching the last row along with it.
+    fn resize(&mut self, rows: u16) {
+        let previous = std::mem::replace(&mut self.rows, rows);
+        if previous == rows || rows == 0 {
+            return;
+        }
+        for region in &mut self.regions {
+            if let Some((top, bottom)) = *region {
+                let bottom = if bottom + 1 == previous {
+                    rows - 1
+                } else {
+                    bottom.min(rows - 1)
+                };
+                *region = (top < bottom).then_some((top, bottom));
+            }
+        }
+    }
+
+    /// Scroll the scroll region up by `count` lines.
+    fn scroll_up(&mut self, screen: &vt100::Screen, count: u16) {
+        let (top, bottom) = self.region(screen);
+        let rows = screen.size().0;
+        self.move_rows(screen, |moved| {
+            for _ in 0..count.min(rows - top) {
+                moved.insert(usize::from(bottom) + 1, None);
+                moved.remove(usize::from(top));
+            }
+        });
+    }
+
+    /// Move the linked cells of the screen in use to follow its rows.
+    ///
+    /// `shift` is handed the rows of the screen, each holding the row it
+    /// was before, and moves them the way vt100 moves its own, with `None`
+    /// for blank rows coming in.
+    fn move_rows(&mut self, screen: &vt100::Screen, shift: impl FnOnce(&mut Vec<Option<u16>>)) {
+        let mut rows: Vec<_> = (0..screen.size().0).map(Some).collect();
+        shift(&mut rows);
+        let moved: HashMap<u16, u16> = rows
+            .iter()
+            .enumerate()
+            .filter_map(|(row, was)| Some(((*was)?, row as u16)))
+            .collect();
+
+        let marks = &mut self.marks[usize::from(screen.alternate_screen())];
+        *marks = marks
+            .drain()
+            .filter_map(|((row, col), mark)| Some(((*moved.get(&row)?, col), mark)))
+            .collect();
+    }
+
+    /// Move the linked cells on `row` of the screen in use to the column
+    /// `shift` gives, dropping them when it gives `None`.
+    fn move_cols(&mut self, screen: &vt100::Screen, row: u16, shift: impl Fn(u16) -> Option<u16>) {
+        let marks = &mut self.marks[usize::from(screen.alternate_screen())];
+        *marks = marks
+            .drain()
+            .filter_map(|((mark_row, col), mark)| {
+                if mark_row != row {
+                    return Some(((mark_row, col), mark));
+                }
+                Some(((row, shift(col)?), mark))
+            })
+            .collect();
+    }
+
+    /// Forget linked cells that have been overwritten or erased.
+    fn prune(&mut self, screen: &vt100::Screen) {
+        self.marks[usize::from(screen.alternate_screen())]
+            .retain(|(row, col), mark| still_holds(screen, *row, *col, mark));
+    }
+
+    /// Advance the escape sequence parser, returning the sequence the byte
+    /// completed, if any.
+    fn advance(&mut self, byte: u8) -> Option<Sequence> {
+        match &mut self.state {
+            ParserState::Ground => {
+                if byte == ESC {
+                    self.state = ParserState::Esc;
+                }
+            }
+            ParserState::Esc => {
+                self.state = match byte {
+                    b'[' => ParserState::Csi { params: Vec::new() },
+                    b']' => ParserState::Osc { buf: Vec::new() },
+                    b'P' | b'X' | b'^' | b'_' => ParserState::Str,
+                    0x20..=0x2f => ParserState::EscIntermediate,
+                    ESC => ParserState::Esc,
+                    _ => {
+                        self.state = ParserState::Ground;
+                        return Some(Sequence::Esc(byte));
+                    }
+                };
+            }
+            ParserState::EscIntermediate => {
+                if !(0x20..=0x2f).contains(&byte) {
+                    self.state = ParserState::Ground;
+                }
+            }
+            ParserState::Csi { params } => {
+                if byte == ESC {
+                    self.state = ParserState::Esc;
+                } else if (0x40..=0x7e).contains(&byte) {
+                    let params = std::mem::take(params);
+                    self.state = ParserState::Ground;
+                    return Some(Sequence::Csi {
+                        params,
+                        action: byte,
+                    });
+                } else {
+                    params.push(byte);
+                }
+            }
+            ParserState::Osc { buf } => {
+                if byte == BEL || byte == ESC {
+                    // ESC ends the payload; the `\` of ST is then consumed
+                    // as an ordinary escape sequence.
+                    let link = parse_osc8(buf);
+                    self.state = if byte == ESC {
+                        ParserState::Esc
+                    } else {
+                        ParserState::Ground
+                    };
+                    if let Some(link) = link {
+                        self.active = link;
+                        return Some(Sequence::Link);
+                    }
+                } else {
+                    buf.push(byte);
+                }
+            }
+            ParserState::Str => {
+                if byte == ESC {
+                    self.state = ParserState::Esc;
+                }
+            }
+        }
+        None
+    }
+}
+
+fn still_holds(screen: &vt100::Screen, row: u16, col: u16, mark: &Mark) -> bool {
+    screen
+        .cell(row, col)
+        .is_some_and(|cell| cell.contents() == mark.contents)
+}
+
+/// Numeric parameters of a control sequence, taking the first of any
+/// subparameters, or `None` when it has a private marker or intermediates.
+fn numbers(params: &[u8]) -> Option<Vec<u16>> {
+    if !params
+        .iter()
+        .all(|byte| byte.is_ascii_digit() || matches!(byte, b';' | b':'))
+    {
+        return None;
+    }
+    Some(
+        params
+            .split(|byte| *byte == b';')
+            .map(|param| {
+                param
+                    .iter()
+                    .take_while(|byte| byte.is_ascii_digit())
+                    .fold(0u16, |n, digit| {
+                        n.saturating_mul(10).saturating_add(u16::from(digit - b'0'))
+                    })
+            })
+            .collect(),
+    )
+}
+
+/// Parse `8 ; params ; uri`, returning the link it opens or `None` when it
+/// closes the active link.
+fn parse_osc8(buf: &[u8]) -> Option<Option<Hyperlink>> {
+    let command = std::str::from_utf8(buf).ok()?;
+    let (params, uri) = command.strip_prefix("8;")?.split_once(';')?;
+    if uri.is_empty() {
+        return Some(None);
+    }
+    let id = params
+        .split(':')
+        .find_map(|param| param.strip_prefix("id="))
+        .filter(|id| !id.is_empty())
+        .map(str::to_string);
+    Some(Some(Hyperlink {
+        uri: uri.to_string(),
+        id,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(chunks: &[&[u8]]) -> (vt100::Parser, HyperlinkTracker) {
+        track_on(3, chunks)
+    }
+
+    fn track_on(rows: u16, chunks: &[&[u8]]) -> (vt100::Parser, HyperlinkTracker) {
+        let mut parser = vt100::Parser::new(rows, 10, 0);
+        let mut tracker = HyperlinkTracker::new();
+        for chunk in chunks {
+            tracker.process(&mut parser, chunk);
+        }
+        (parser, tracker)
+    }
+
+    fn linked_cols(parser: &vt100::Parser, tracker: &HyperlinkTracker) -> Vec<(u16, u16)> {
+        tracker
+            .links(parser.screen())
+            .into_iter()
+            .map(|(position, _)| (position.row, position.col))
+            .collect()
+    }
+
+    #[test]
+    fn records_cells_printed_inside_a_link() {
+        let (parser, tracker) =
+            track(&[b"see \x1b]8;id=a;https://x.test\x1b\\docs\x1b]8;;\x1b\\ ok"]);
+
+        assert_eq!(parser.screen().contents(), "see docs ok");
+        let links = tracker.links(parser.screen());
+        assert_eq!(
+            links.iter().map(|(p, _)| p.col).collect::<Vec<_>>(),
+            vec![4, 5, 6, 7]
+        );
+        assert_eq!(links[0].1.uri, "https://x.test");
+        assert_eq!(links[0].1.id.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn links_split_across_chunks_and_bel_terminators() {
+        let (parser, tracker) = track(&[b"\x1b]8;;file:///tmp", b"/a\x07a", b"b\x1b]8;;\x07c"]);
+
+        assert_eq!(linked_cols(&parser, &tracker), vec![(0, 0), (0, 1)]);
+        assert_eq!(tracker.links(parser.screen())[0].1.uri, "file:///tmp/a");
+    }
+
+    #[test]
+    fn overwritten_and_scrolled_cells_follow_the_screen() {
+        let (mut parser, mut tracker) = track(&[b"\x1b]8;;u\x07ab\x1b]8;;\x07\r\nx"]);
+        assert_eq!(linked_cols(&parser, &tracker), vec![(0, 0), (0, 1)]);
+
+        tracker.process(&mut parser, b"\x1b[1;2HZ");
+        assert_eq!(linked_cols(&parser, &tracker), vec![(0, 0)]);
+
+        tracker.process(&mut parser, b"\x1b[3;1H\n");
+        assert!(linked_cols(&parser, &tracker).is_empty());
+        assert!(!tracker.tracking());
+    }
+
+    #[test]
+    fn links_follow_scroll_regions_and_scroll_sequences() {
+        let (mut parser, mut tracker) = track_on(
+            5,
+            &[b"\x1b[3;1H\x1b]8;;u\x07ab\x1b[5;1Hcd\x1b]8;;\x07\x1b[2;4r"],
+        );
+        assert_eq!(
+            linked_cols(&parser, &tracker),
+            vec![(2, 0), (2, 1), (4, 0), (4, 1)]
+        );
+
+        // A line feed on the last row of the region scrolls only the region.
+        tracker.process(&mut parser, b"\x1b[4;1H\n");
+        assert_eq!(
+            linked_cols(&parser, &tracker),
+            vec![(1, 0), (1, 1), (4, 0), (4, 1)]
+        );
+
+        tracker.process(&mut parser, b"\x1b[T");
+        assert_eq!(
+            linked_cols(&parser, &tracker),
+            vec![(2, 0), (2, 1), (4, 0), (4, 1)]
+        );
+        tracker.process(&mut parser, b"\x1b[S");
+        assert_eq!(
+            linked_cols(&parser, &tracker),
+            vec![(1, 0), (1, 1), (4, 0), (4, 1)]
+        );
+
+        tracker.process(&mut parser, b"\x1b[r\x1b[2S");
+        assert_eq!(linked_cols(&parser, &tracker), vec![(2, 0), (2, 1)]);
+        assert_eq!(parser.screen().contents(), "\n\ncd");
+    }
+
+    #[test]
+    fn links_follow_inserted_and_deleted_lines_and_cells() {
+        let (mut parser, mut tracker) = track(&[b"\x1b[2;3H\x1b]8;;u\x07ab\x1b]8;;\x07"]);
+        assert_eq!(linked_cols(&parser, &tracker), vec![(1, 2), (1, 3)]);
+
+        tracker.process(&mut parser, b"\x1b[1;1H\x1b[L");
+        assert_eq!(linked_cols(&parser, &tracker), vec![(2, 2), (2, 3)]);
+        tracker.process(&mut parser, b"\x1b[1;1H\x1b[M");
+        assert_eq!(linked_cols(&parser, &tracker), vec![(1, 2), (1, 3)]);
+
+        tracker.process(&mut parser, b"\x1b[2;1H\x1b[2@");
+        assert_eq!(linked_cols(&parser, &tracker), vec![(1, 4), (1, 5)]);
+
+        // RI scrolls down only from the top row.
+        tracker.process(&mut parser, b"\x1b[2;1H\x1bM");
+        assert_eq!(linked_cols(&parser, &tracker), vec![(1, 4), (1, 5)]);
+        tracker.process(&mut parser, b"\x1bM");
+        assert_eq!(linked_cols(&parser, &tracker), vec![(2, 4), (2, 5)]);
+
+        tracker.process(&mut parser, b"\x1b[3;5H\x1b[P");
+        assert_eq!(linked_cols(&parser, &tracker), vec![(2, 4)]);
+        assert_eq!(parser.screen().contents(), "\n\n    b");
+    }
+}
@@ -0,0 +1,85 @@
+//! How the terminal identifies itself to applications.
+
+/// The terminal an application is told it is running in.
+///
+/// The identity decides how device attribute (DA1, DA2), version
+/// (XTVERSION), mode (DECRQM), capability (XTGETTCAP) and kitty keyboard
+/// queries are answered, and which `TERM` is set when default environment
+/// variables are injected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TerminalIdentity {
+    /// An xterm-compatible terminal with 256 colors and truecolor
+    /// (`TERM=xterm-256color`).
+    #[default]
+    Xterm,
+    /// kitty (`TERM=xterm-kitty`). Applications using terminfo need the
+    /// kitty terminfo entry installed.
+    Kitty,
+    /// A bare VT100 that only answers device attribute and cursor position
+    /// queries (`TERM=xterm-256color`).
+    Minimal,
+}
+
+impl TerminalIdentity {
+    /// The `TERM` value matching this identity.
+    pub fn term(self) -> &'static str {
+        match self {
+            TerminalIdentity::Xterm | TerminalIdentity::Minimal => "xterm-256color",
+            TerminalIdentity::Kitty => "xterm-kitty",
+        }
+    }
+
+    /// Parameters of the primary device attributes reply, `CSI ? … c`.
+    pub(crate) fn primary_attributes(self) -> &'static str {
+        match self {
+            // The VT420 feature list xterm itself reports.
+            TerminalIdentity::Xterm => "64;1;2;6;9;15;16;17;18;21;22;28",
+            TerminalIdentity::Kitty => "62;",
+            TerminalIdentity::Minimal => "1;2",
+        }
+    }
+
+    /// Parameters of the secondary device attributes reply, `CSI > … c`.
+    pub(crate) fn secondary_attributes(self) -> &'static str {
+        match self {
+            TerminalIdentity::Xterm => "41;388;0",
+            TerminalIdentity::Kitty => "1;4000;29",
+            TerminalIdentity::Minimal => "0;0;0",
+        }
+    }
+
+    /// Name and version reported by XTVERSION, `DCS > | … ST`.
+    pub(crate) fn version(self) -> Option<&'static str> {
+        match self {
+            TerminalIdentity::Xterm => Some("XTerm(388)"),
+            TerminalIdentity::Kitty => Some("kitty(0.40.0)"),
+            TerminalIdentity::Minimal => None,
+        }
+    }
+
+    /// Whether DECRQM and XTGETTCAP queries are answered.
+    pub(crate) fn answers_extended_queries(self) -> bool {
+        self != TerminalIdentity::Minimal
+    }
+
+    /// Whether the kitty keyboard protocol query, `CSI ? u`, is answered.
+    /// Applications detect the protocol from the reply, so only kitty
+    /// advertises it.
+    pub(crate) fn answers_kitty_keyboard_query(self) -> bool {
+        self == TerminalIdentity::Kitty
+    }
+
+    /// Terminfo capability value reported by XTGETTCAP.
+    pub(crate) fn capability(self, name: &str) -> Option<&'static str> {
+        let value = match name {
+            "TN" | "name" => self.term(),
+            "Co" | "colors" => "256",
+            "RGB" => "8",
+            "Ss" => "\u{1b}[%p1%d q",
+            "Se" => "\u{1b}[2 q",
+            "Smulx" if self == TerminalIdentity::Kitty => "\u{1b}[4:%p1%dm",
+            _ => return None,
+        };
+        Some(value)
+    }
+}
@@ -1,4 +1,10 @@
-//! OSC color query emulation for PTY-hosted applications.
+//! OSC color query and clipboard emulation for PTY-hosted applications.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use base64::Engine;
 
 const ESC: u8 = 0x1b;
 const BEL: u8 = 0x07;
@@ -72,9 +78,83 @@ impl OscColorState {
     }
 }
 
+/// An OSC 52 selection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ClipboardSelection {
+    /// The system clipboard (`c`).
+    Clipboard,
+    /// The primary selection (`p`).
+    Primary,
+    /// The secondary selection (`s`).
+    Secondary,
+}
+
+impl ClipboardSelection {
+    fn from_code(code: char) -> Option<Self> {
+        match code {
+            'c' => Some(ClipboardSelection::Clipboard),
+            'p' => Some(ClipboardSelection::Primary),
+            's' => Some(ClipboardSelection::Secondary),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> char {
+        match self {
+            ClipboardSelection::Clipboard => 'c',
+            ClipboardSelection::Primary => 'p',
+            ClipboardSelection::Secondary => 's',
+        }
+    }
+}
+
+impl FromStr for ClipboardSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "c" | "clipboard" => Ok(ClipboardSelection::Clipboard),
+            "p" | "primary" => Ok(ClipboardSelection::Primary),
+            "s" | "secondary" => Ok(ClipboardSelection::Secondary),
+            other => Err(format!("unknown clipboard selection: {other}")),
+        }
+    }
+}
+
+impl std::fmt::Display for ClipboardSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClipboardSelection::Clipboard => write!(f, "clipboard"),
+            ClipboardSelection::Primary => write!(f, "primary"),
+            ClipboardSelection::Secondary => write!(f, "secondary"),
+        }
+    }
+}
+
+/// Clipboard contents per selection, written by OSC 52 or seeded by tests.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Clipboard {
+    selections: HashMap<ClipboardSelection, String>,
+}
+
+impl Clipboard {
+    pub fn get(&self, selection: ClipboardSelection) -> Option<&str> {
+        self.selections.get(&selection).map(String::as_str)
+    }
+
+    pub fn set(&mut self, selection: ClipboardSelection, text: String) {
+        self.selections.insert(selection, text);
+    }
+
+    pub fn clear(&mut self, selection: ClipboardSelection) {
+        self.selections.remove(&selection);
+    }
+}
+
 #[derive(Debug)]
 pub struct OscEmulator {
     state: OscColorState,
+    clipboard: Arc<Mutex<Clipboard>>,
     parser_state: ParserState,
 }
 
@@ -82,10 +162,17 @@ impl OscEmulator {
     pub fn new(state: OscColorState) -> Self {
         Self {
             state,
+            clipboard: Arc::default(),
             parser_state: ParserState::Ground,
         }
     }
 
+    /// Record OSC 52 writes in, and answer reads from, a shared clipboard.
+    pub fn with_clipboard(mut self, clipboard: Arc<Mutex<Clipboard>>) -> Self {
+        self.clipboard = clipboard;
+        self
+    }
+
     pub fn process_output(&mut self, bytes: &[u8]) -> Vec<Vec<u8>> {
         let mut responses = Vec::new();
         let state = &mut self.state;
@@ -116,7 +203,9 @@ impl OscEmulator {
                     if *pending_esc {
                         *pending_esc = false;
                         if byte == b'\\' {
-                            if let Some(response) = handle_command(state, buf, OscTerminator::St) {
+                            if let Some(response) =
+                                handle_command(state, &self.clipboard, buf, OscTerminator::St)
+                            {
                                 responses.push(response);
                             }
                             self.parser_state = ParserState::Ground;
@@ -124,7 +213,7 @@ impl OscEmulator {
                             buf.push(ESC);
                             if byte == BEL {
                                 if let Some(response) =
-                                    handle_command(state, buf, OscTerminator::Bel)
+                                    handle_command(state, &self.clipboard, buf, OscTerminator::Bel)
                                 {
                                     responses.push(response);
                                 }
@@ -136,7 +225,9 @@ impl OscEmulator {
                             }
                         }
                     } else if byte == BEL {
-                        if let Some(response) = handle_command(state, buf, OscTerminator::Bel) {
+                        if let Some(response) =
+                            handle_command(state, &self.clipboard, buf, OscTerminator::Bel)
+                        {
                             responses.push(response);
                         }
                         self.parser_state = ParserState::Ground;
@@ -155,6 +246,7 @@ impl OscEmulator {
 
 fn handle_command(
     state: &mut OscColorState,
+    clipboard: &Mutex<Clipboard>,
     buf: &[u8],
     terminator: OscTerminator,
 ) -> Option<Vec<u8>> {
@@ -162,7 +254,12 @@ fn handle_command(
     let (code_str, payload) = command.split_once(';')?;
     let code = code_str.parse::<u8>().ok()?;
 
-    if !matches!(code, 10 | 11 | 12) {
+    if code == 52 {
+        let mut clipboard = clipboard.lock().ok()?;
+        return handle_clipboard(&mut clipboard, payload, terminator);
+    }
+
+    if !(10..=12).contains(&code) {
         return None;
     }
 
@@ -176,6 +273,47 @@ fn handle_command(
     None
 }
 
+/// OSC 52: `Pc ; Pd` where `Pc` lists selections (default `c`) and `Pd` is
+/// base64 data to store, `?` to query, or anything else to clear.
+fn handle_clipboard(
+    clipboard: &mut Clipboard,
+    payload: &str,
+    terminator: OscTerminator,
+) -> Option<Vec<u8>> {
+    let (targets, data) = payload.split_once(';')?;
+    let mut selections: Vec<ClipboardSelection> = targets
+        .chars()
+        .filter_map(ClipboardSelection::from_code)
+        .collect();
+    if targets.is_empty() {
+        selections.push(ClipboardSelection::Clipboard);
+    }
+    let first = *selections.first()?;
+
+    if data == "?" {
+        let text = clipboard.get(first).unwrap_or_default();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+        let mut out = format!("\u{1b}]52;{};{encoded}", first.code()).into_bytes();
+        match terminator {
+            OscTerminator::Bel => out.push(BEL),
+            OscTerminator::St => out.extend_from_slice(b"\x1b\\"),
+        }
+        return Some(out);
+    }
+
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .ok()
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+    for selection in selections {
+        match &decoded {
+            Some(text) => clipboard.set(selection, text.clone()),
+            None => clipboard.clear(selection),
+        }
+    }
+    None
+}
+
 fn encode_query_response(code: u8, color: Rgb8, terminator: OscTerminator) -> Vec<u8> {
     let r = u16::from(color.r) * 257;
     let g = u16::from(color.g) * 257;
@@ -323,6 +461,33 @@ mod tests {
         assert_eq!(responses.len(), 2);
     }
 
+    #[test]
+    fn osc52_records_and_answers_clipboard() {
+        let clipboard = Arc::new(Mutex::new(Clipboard::default()));
+        let mut osc = OscEmulator::new(OscColorState::default()).with_clipboard(clipboard.clone());
+
+        assert!(osc.process_output(b"\x1b]52;cp;aGVsbG8=\x07").is_empty());
+        {
+            let clipboard = clipboard.lock().unwrap();
+            assert_eq!(clipboard.get(ClipboardSelection::Clipboard), Some("hello"));
+            assert_eq!(clipboard.get(ClipboardSelection::Primary), Some("hello"));
+            assert_eq!(clipboard.get(ClipboardSelection::Secondary), None);
+        }
+
+        clipboard
+            .lock()
+            .unwrap()
+            .set(ClipboardSelection::Secondary, "seed".to_string());
+        let responses = osc.process_output(b"\x1b]52;s;?\x1b\\");
+        assert_eq!(responses, vec![b"\x1b]52;s;c2VlZA==\x1b\\".to_vec()]);
+
+        osc.process_output(b"\x1b]52;;!\x07");
+        assert_eq!(
+            clipboard.lock().unwrap().get(ClipboardSelection::Clipboard),
+            None
+        );
+    }
+
     #[test]
     fn parse_rgb_spec_short_and_long_components() {
         assert_eq!(
@@ -0,0 +1,296 @@
+//! Shell integration tracking: OSC 133 semantic prompt marks and OSC 7
+//! working-directory reports.
+//!
+//! Shells configured for FinalTerm-style integration print `133;A` before the
+//! prompt, `133;B` where input starts, `133;C` when a command starts running
+//! and `133;D;<exit code>` when it finishes. The output between `C` and `D` is
+//! kept so it can be returned as the command's output.
+
+use std::path::PathBuf;
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+/// Raw output kept per command; anything beyond this is dropped.
+const MAX_CAPTURE: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Default)]
+enum ParserState {
+    #[default]
+    Ground,
+    Esc,
+    Osc {
+        buf: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Phase {
+    /// No marks seen yet.
+    #[default]
+    Unknown,
+    Prompt,
+    Input,
+    Executing,
+}
+
+/// Shell state the wait engine can check.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShellStatus {
+    /// The shell is showing a prompt or reading input.
+    pub at_prompt: bool,
+    /// Number of commands that have finished (`133;D`).
+    pub commands_finished: usize,
+}
+
+/// A command that ran between a `C` and a `D` mark.
+#[derive(Debug, Clone, Default)]
+pub struct FinishedCommand {
+    /// Raw output the command printed.
+    pub output: Vec<u8>,
+    /// Terminal width while the command ran.
+    pub cols: u16,
+    /// Exit code reported by the `D` mark.
+    pub exit_code: Option<i32>,
+}
+
+impl FinishedCommand {
+    /// Render the output as the terminal would show it, without colors or
+    /// cursor movement, and with trailing blank lines removed.
+    pub fn output_text(&self) -> String {
+        let cols = self.cols.max(1);
+        // Every row is either ended by a newline or by wrapping, so this
+        // bounds the history needed to keep the whole output.
+        let newlines = self.output.iter().filter(|&&byte| byte == b'\n').count();
+        let history = self.output.len() / usize::from(cols) + newlines + 1;
+        // vt100 cannot wrap lines on a one-row grid.
+        let mut parser = vt100::Parser::new(2, cols, history);
+        parser.process(&self.output);
+        let text = crate::screen::Screen::with_history(&mut parser).text();
+        text.trim_end_matches('\n').to_string()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PromptTracker {
+    parser_state: ParserState,
+    phase: Phase,
+    finished: usize,
+    cwd: Option<PathBuf>,
+    capture: Vec<u8>,
+    capture_cols: u16,
+    last: Option<FinishedCommand>,
+}
+
+impl PromptTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn status(&self) -> ShellStatus {
+        ShellStatus {
+            at_prompt: matches!(self.phase, Phase::Prompt | Phase::Input),
+            commands_finished: self.finished,
+        }
+    }
+
+    /// Working directory last reported with OSC 7.
+    pub fn cwd(&self) -> Option<PathBuf> {
+        self.cwd.clone()
+    }
+
+    /// The most recently finished command.
+    pub fn last_command(&self) -> Option<FinishedCommand> {
+        self.last.clone()
+    }
+
+    /// Scan output for shell integration marks. `cols` is the current
+    /// terminal width, used later to render captured output.
+    pub fn process_output(&mut self, bytes: &[u8], cols: u16) {
+        for byte in bytes.iter().copied() {
+            if self.phase == Phase::Executing && self.capture.len() < MAX_CAPTURE {
+                self.capture.push(byte);
+            }
+
+            match &mut self.parser_state {
+                ParserState::Ground => {
+                    if byte == ESC {
+                        self.parser_state = ParserState::Esc;
+                    }
+                }
+                ParserState::Esc => {
+                    self.parser_state = match byte {
+                        b']' => ParserState::Osc { buf: Vec::new() },
+                        ESC => ParserState::Esc,
+                        _ => ParserState::Ground,
+                    };
+                }
+                ParserState::Osc { buf } => {
+                    if byte == BEL || byte == ESC {
+                        let command = std::mem::take(buf);
+                        self.parser_state = if byte == ESC {
+                            ParserState::Esc
+                        } else {
+                            ParserState::Ground
+                        };
+                        self.handle_command(&command, cols);
+                    } else {
+                        buf.push(byte);
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_command(&mut self, buf: &[u8], cols: u16) {
+        let Ok(command) = std::str::from_utf8(buf) else {
+            return;
+        };
+
+        if let Some(uri) = command.strip_prefix("7;") {
+            if let Some(path) = parse_file_uri(uri) {
+                self.cwd = Some(path);
+            }
+            return;
+        }
+
+        let Some(mark) = command.strip_prefix("133;") else {
+            return;
+        };
+        let mut parts = mark.split(';');
+        match parts.next() {
+            Some("A") => {
+                // A new prompt without a `D` still ends the running command.
+                if self.phase == Phase::Executing {
+                    self.finish(None);
+                }
+                self.phase = Phase::Prompt;
+            }
+            Some("B") => self.phase = Phase::Input,
+            Some("C") => {
+                self.phase = Phase::Executing;
+                self.capture.clear();
+                self.capture_cols = cols;
+            }
+            // Shells also send a bare `D` after an empty or interrupted
+            // prompt; only a command that was executing has a result.
+            Some("D") if self.phase == Phase::Executing => {
+                let exit_code = parts.next().and_then(|code| code.trim().parse().ok());
+                self.finish(exit_code);
+            }
+            Some("D") => self.phase = Phase::Unknown,
+            _ => {}
+        }
+    }
+
+    fn finish(&mut self, exit_code: Option<i32>) {
+        let mut output = std::mem::take(&mut self.capture);
+        // Drop the mark that ended the capture.
+        if let Some(start) = output.windows(6).rposition(|w| w == b"\x1b]133;") {
+            output.truncate(start);
+        }
+        self.last = Some(FinishedCommand {
+            output,
+            cols: self.capture_cols,
+            exit_code,
+        });
+        self.finished += 1;
+        self.phase = Phase::Unknown;
+    }
+}
+
+/// Extract the path from a `file://host/path` URI, decoding `%XX` escapes.
+fn parse_file_uri(uri: &str) -> Option<PathBuf> {
+    let rest = uri.trim().strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    Some(PathBuf::from(
+        String::from_utf8_lossy(&decoded).into_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_output_between_c_and_d_marks() {
+        let mut tracker = PromptTracker::new();
+        tracker.process_output(b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n", 20);
+        assert!(tracker.status().at_prompt);
+
+        tracker.process_output(b"\x1b]133;C\x07one\r\n\x1b[31mtwo\x1b[0m\r\n\x1b]133", 20);
+        assert!(!tracker.status().at_prompt);
+        tracker.process_output(b";D;2\x1b\\\x1b]7;file://host/tmp/my%20dir\x07", 20);
+
+        let status = tracker.status();
+        assert_eq!(status.commands_finished, 1);
+        let command = tracker.last_command().unwrap();
+        assert_eq!(command.exit_code, Some(2));
+        assert_eq!(command.output_text(), "one\ntwo");
+        assert_eq!(tracker.cwd(), Some(PathBuf::from("/tmp/my dir")));
+    }
+
+    #[test]
+    fn new_prompt_ends_a_command_without_d() {
+        let mut tracker = PromptTracker::new();
+        tracker.process_output(b"\x1b]133;C\x07done\r\n\x1b]133;A\x07$ ", 20);
+
+        let command = tracker.last_command().unwrap();
+        assert_eq!(command.exit_code, None);
+        assert_eq!(command.output_text(), "done");
+        assert!(tracker.status().at_prompt);
+    }
+
+    #[test]
+    fn ignores_d_without_a_running_command() {
+        let mut tracker = PromptTracker::new();
+        tracker.process_output(b"\x1b]133;C\x07ok\r\n\x1b]133;D;0\x07", 20);
+        tracker.process_output(b"\x1b]133;A\x07$ \x1b]133;B\x07^C\x1b]133;D;130\x07", 20);
+        tracker.process_output(b"\x1b]133;A\x07$ \x1b]133;B\x07", 20);
+
+        assert_eq!(tracker.status().commands_finished, 1);
+        let command = tracker.last_command().unwrap();
+        assert_eq!(command.exit_code, Some(0));
+        assert_eq!(command.output_text(), "ok");
+        assert!(tracker.status().at_prompt);
+    }
+
+    #[test]
+    fn renders_output_wider_than_the_terminal() {
+        let mut output = vec![b'x'; 45];
+        output.extend_from_slice(b"\r\nend\r\n");
+        let command = FinishedCommand {
+            output,
+            cols: 20,
+            exit_code: Some(0),
+        };
+
+        let expected = format!(
+            "{}\n{}\n{}\nend",
+            "x".repeat(20),
+            "x".repeat(20),
+            "x".repeat(5)
+        );
+        assert_eq!(command.output_text(), expected);
+    }
+}
@@ -0,0 +1,51 @@
+//! Session recording to asciicast v2.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use crate::asciicast::{CastWriter, Header};
+use crate::error::Result;
+
+pub struct Recording {
+    writer: CastWriter<BufWriter<File>>,
+    path: PathBuf,
+    input: bool,
+}
+
+impl Recording {
+    /// Start a recording at `path`, replacing any file already there.
+    pub fn create(path: &Path, cols: u16, rows: u16, term: &str, input: bool) -> Result<Self> {
+        let mut header = Header::new(cols, rows);
+        header.env.insert("TERM".to_string(), term.to_string());
+        let file = File::create(path)?;
+        Ok(Self {
+            writer: CastWriter::new(BufWriter::new(file), &header)?,
+            path: path.to_path_buf(),
+            input,
+        })
+    }
+
+    // Write errors are ignored so a full disk cannot break the session
+    // being recorded.
+
+    pub fn output(&mut self, bytes: &[u8]) {
+        let _ = self.writer.output(bytes);
+    }
+
+    pub fn input(&mut self, bytes: &[u8]) {
+        if self.input {
+            let _ = self.writer.input(bytes);
+        }
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        let _ = self.writer.resize(cols, rows);
+    }
+
+    /// Flush the recording and return its path.
+    pub fn finish(self) -> Result<PathBuf> {
+        self.writer.finish()?;
+        Ok(self.path)
+    }
+}
@@ -0,0 +1,188 @@
+//! Synchronized output (mode 2026).
+//!
+//! Applications wrap each frame in `CSI ? 2026 h` and `CSI ? 2026 l` so the
+//! terminal can show it in one piece. Output inside a frame is held back from
+//! the parser until the frame ends, so snapshots and waits never see a
+//! half-drawn frame. A frame left open longer than the timeout is released
+//! anyway, and the rest of it is shown as it arrives.
+
+use std::time::{Duration, Instant};
+
+const ESC: u8 = 0x1b;
+
+#[derive(Debug, Default)]
+enum ParserState {
+    #[default]
+    Ground,
+    Esc,
+    Csi {
+        buf: Vec<u8>,
+    },
+}
+
+#[derive(Debug)]
+pub struct SyncOutput {
+    parser_state: ParserState,
+    timeout: Duration,
+    /// Whether a frame has started and not ended yet.
+    open: bool,
+    /// When the open frame started, while its output is held back.
+    started: Option<Instant>,
+    held: Vec<u8>,
+    frames: u64,
+}
+
+impl SyncOutput {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            parser_state: ParserState::default(),
+            timeout,
+            open: false,
+            started: None,
+            held: Vec::new(),
+            frames: 0,
+        }
+    }
+
+    /// Number of frames that ended, including those released early by the
+    /// timeout.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Number of bytes held back by the open frame.
+    pub fn held(&self) -> usize {
+        self.held.len()
+    }
+
+    /// When held output is released if the open frame does not end first.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.started
+            .and_then(|started| started.checked_add(self.timeout))
+    }
+
+    /// Take the output that is ready to be shown at `now`: everything
+    /// outside frames, and each frame as a whole once it ends. Output of a
+    /// frame that is still open is held back.
+    pub fn process(&mut self, bytes: &[u8], now: Instant) -> Vec<u8> {
+        let mut ready = self.release_expired(now).unwrap_or_default();
+
+        for byte in bytes.iter().copied() {
+            if self.started.is_some() {
+                self.held.push(byte);
+            } else {
+                ready.push(byte);
+            }
+
+            match self.advance(byte) {
+                Some(true) if !self.open => {
+                    self.open = true;
+                    if !self.timeout.is_zero() {
+                        self.started = Some(now);
+                    }
+                }
+                Some(false) if self.open => {
+                    self.open = false;
+                    self.frames += 1;
+                    if self.started.take().is_some() {
+                        ready.append(&mut self.held);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        ready
+    }
+
+    /// Release held output once its frame has been open for longer than the
+    /// timeout at `now`.
+    pub fn release_expired(&mut self, now: Instant) -> Option<Vec<u8>> {
+        if now < self.deadline()? {
+            return None;
+        }
+        self.started = None;
+        Some(std::mem::take(&mut self.held))
+    }
+
+    /// Advance the escape sequence parser, returning whether a completed
+    /// sequence set or reset mode 2026.
+    fn advance(&mut self, byte: u8) -> Option<bool> {
+        match &mut self.parser_state {
+            ParserState::Ground => {
+                if byte == ESC {
+                    self.parser_state = ParserState::Esc;
+                }
+            }
+            ParserState::Esc => {
+                self.parser_state = match byte {
+                    b'[' => ParserState::Csi { buf: Vec::new() },
+                    ESC => ParserState::Esc,
+                    _ => ParserState::Ground,
+                };
+            }
+            ParserState::Csi { buf } => {
+                if (0x20..=0x3f).contains(&byte) {
+                    buf.push(byte);
+                    return None;
+                }
+                let params = std::mem::take(buf);
+                self.parser_state = if byte == ESC {
+                    ParserState::Esc
+                } else {
+                    ParserState::Ground
+                };
+                let set = match byte {
+                    b'h' => true,
+                    b'l' => false,
+                    _ => return None,
+                };
+                let modes = params.strip_prefix(b"?")?;
+                return modes
+                    .split(|&b| b == b';')
+                    .any(|mode| mode == b"2026")
+                    .then_some(set);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_frames_until_they_end() {
+        let now = Instant::now();
+        let mut sync = SyncOutput::new(Duration::from_secs(60));
+        assert_eq!(sync.process(b"a\x1b[?2026h", now), b"a\x1b[?2026h");
+        assert!(sync.process(b"\x1b[Hframe", now).is_empty());
+        assert!(sync.deadline().is_some());
+
+        assert_eq!(
+            sync.process(b"!\x1b[?2026lb", now),
+            b"\x1b[Hframe!\x1b[?2026lb"
+        );
+        assert_eq!(sync.frames(), 1);
+        assert!(sync.deadline().is_none());
+    }
+
+    #[test]
+    fn releases_frames_left_open_past_the_timeout() {
+        let start = Instant::now();
+        let mut sync = SyncOutput::new(Duration::from_millis(1));
+        sync.process(b"\x1b[?1049;2026hhalf", start);
+        assert_eq!(sync.release_expired(start), None);
+
+        let later = start + Duration::from_millis(5);
+        assert_eq!(sync.release_expired(later).as_deref(), Some(&b"half"[..]));
+        assert_eq!(sync.process(b"rest\x1b[?2026l", later), b"rest\x1b[?2026l");
+        assert_eq!(sync.frames(), 1);
+
+        let mut unsynced = SyncOutput::new(Duration::ZERO);
+        assert_eq!(unsynced.process(b"\x1b[?2026hx", start), b"\x1b[?2026hx");
+        assert_eq!(unsynced.process(b"\x1b[?2026l", start), b"\x1b[?2026l");
+        assert_eq!(unsynced.frames(), 1);
+    }
+}
@@ -0,0 +1,250 @@
+//! Diagnostics attached to wait timeouts.
+
+use serde::{Deserialize, Serialize};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::screen::{Cell, Position, Screen};
+
+/// Maximum number of near-match lines reported for a timeout.
+const MAX_NEAR_MATCHES: usize = 3;
+
+/// Maximum number of screen lines drawn for a timeout; a screen with
+/// scrollback is cut down to its last lines.
+const MAX_DUMP_LINES: usize = 50;
+
+/// Terminal state captured when a wait timed out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeoutContext {
+    /// The last screen snapshot the condition was evaluated against.
+    pub screen: Screen,
+    /// Cursor position at the time of the timeout.
+    pub cursor: Position,
+    /// Lines that came closest to matching a text or pattern condition.
+    pub near_matches: Vec<NearMatch>,
+}
+
+/// A screen line that resembles what a wait was looking for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NearMatch {
+    /// Row of the line on screen.
+    pub row: u16,
+    /// The line's text, without trailing whitespace.
+    pub line: String,
+    /// The searched text this line was compared against.
+    pub term: String,
+    /// Edit distance between the term and the closest part of the line.
+    pub distance: usize,
+}
+
+impl TimeoutContext {
+    /// Capture the screen and find lines that nearly match any of `terms`.
+    pub fn new(screen: Screen, terms: &[String]) -> Self {
+        let near_matches = near_matches(&screen, terms);
+        Self {
+            cursor: screen.cursor(),
+            screen,
+            near_matches,
+        }
+    }
+}
+
+impl std::fmt::Display for TimeoutContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut lines: Vec<String> = self
+            .screen
+            .raw_cells()
+            .iter()
+            .map(|row| row_text(row))
+            .collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        let omitted = lines.len().saturating_sub(MAX_DUMP_LINES);
+        if omitted > 0 {
+            writeln!(f, "({omitted} earlier lines not shown)")?;
+        }
+        let lines = &lines[omitted..];
+
+        let width = lines.iter().map(|line| line.width()).max().unwrap_or(0);
+        writeln!(f, "┌{}┐", "─".repeat(width))?;
+        for line in lines {
+            let pad = width - line.width();
+            writeln!(f, "│{}{}│", line, " ".repeat(pad))?;
+        }
+        writeln!(f, "└{}┘", "─".repeat(width))?;
+        write!(
+            f,
+            "cursor: row={}, col={}",
+            self.cursor.row, self.cursor.col
+        )?;
+
+        if !self.near_matches.is_empty() {
+            write!(f, "\nclosest lines:")?;
+            for near in &self.near_matches {
+                write!(
+                    f,
+                    "\n  row {}: {:?} (distance {} from {:?})",
+                    near.row, near.line, near.distance, near.term
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The text of a screen row without trailing whitespace, skipping the
+/// cell each wide character covers after itself.
+fn row_text(row: &[Cell]) -> String {
+    let mut text = String::new();
+    let mut col = 0;
+    while let Some(cell) = row.get(col) {
+        text.push(cell.char);
+        col += cell.char.width().unwrap_or(1).max(1);
+    }
+    text.truncate(text.trim_end().len());
+    text
+}
+
+/// Find the lines closest to any of the search terms.
+fn near_matches(screen: &Screen, terms: &[String]) -> Vec<NearMatch> {
+    let mut matches = Vec::new();
+    for term in terms {
+        let needle: Vec<char> = term.to_lowercase().chars().collect();
+        if needle.is_empty() {
+            continue;
+        }
+        for (row, line) in screen.text().lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            let haystack: Vec<char> = line.to_lowercase().chars().collect();
+            let distance = substring_distance(&needle, &haystack);
+            // Only report lines where at least half of the term lines up.
+            if distance * 2 <= needle.len() {
+                matches.push(NearMatch {
+                    row: row as u16,
+                    line: line.to_string(),
+                    term: term.clone(),
+                    distance,
+                });
+            }
+        }
+    }
+    matches.sort_by_key(|near| (near.distance, near.row));
+    matches.truncate(MAX_NEAR_MATCHES);
+    matches
+}
+
+/// Edit distance between `needle` and its best-matching substring of `haystack`.
+fn substring_distance(needle: &[char], haystack: &[char]) -> usize {
+    // Row 0 is all zeros: the match may start anywhere in the haystack.
+    let mut prev = vec![0usize; haystack.len() + 1];
+    for (i, n) in needle.iter().enumerate() {
+        let mut cur = vec![i + 1; haystack.len() + 1];
+        for (j, h) in haystack.iter().enumerate() {
+            let substitution = prev[j] + usize::from(n != h);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev.into_iter().min().unwrap_or(needle.len())
+}
+
+/// Extract the literal text of each alternative in a regex pattern.
+pub(crate) fn pattern_literals(pattern: &str) -> Vec<String> {
+    let mut literals = Vec::new();
+    let mut literal = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // Escapes are either classes (\d, \s) or escaped metacharacters;
+            // keep the latter since they appear literally on screen.
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    if !next.is_ascii_alphanumeric() {
+                        literal.push(next);
+                    }
+                }
+            }
+            '|' => literals.push(std::mem::take(&mut literal)),
+            '^' | '$' | '.' | '?' | '*' | '+' | '(' | ')' | '[' | ']' | '{' | '}' => {}
+            c => literal.push(c),
+        }
+    }
+    literals.push(literal);
+    literals
+        .into_iter()
+        .map(|literal| literal.trim().to_string())
+        .filter(|literal| !literal.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen_with(text: &str) -> Screen {
+        let mut parser = vt100::Parser::new(4, 20, 0);
+        parser.process(text.as_bytes());
+        Screen::from_vt100(parser.screen())
+    }
+
+    #[test]
+    fn test_substring_distance() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(
+            substring_distance(&chars("saved"), &chars("file saved ok")),
+            0
+        );
+        assert_eq!(
+            substring_distance(&chars("saved"), &chars("file savd ok")),
+            1
+        );
+        assert_eq!(substring_distance(&chars("abc"), &chars("")), 3);
+    }
+
+    #[test]
+    fn test_near_matches_and_display() {
+        let screen = screen_with("hello\r\nFile savd\r\n");
+        let context = TimeoutContext::new(screen, &["File saved".to_string()]);
+
+        assert_eq!(context.near_matches.len(), 1);
+        assert_eq!(context.near_matches[0].row, 1);
+        assert_eq!(context.near_matches[0].distance, 1);
+
+        let rendered = context.to_string();
+        assert!(rendered.starts_with("┌─────────┐\n│hello    │\n│File savd│\n└"));
+        assert!(rendered.contains("cursor: row=2, col=0"));
+        assert!(rendered.contains("row 1: \"File savd\""));
+    }
+
+    #[test]
+    fn display_aligns_wide_characters() {
+        let screen = screen_with("宽字\r\nabcdef");
+        let rendered = TimeoutContext::new(screen, &[]).to_string();
+
+        assert!(rendered.starts_with("┌──────┐\n│宽字  │\n│abcdef│\n└"));
+    }
+
+    #[test]
+    fn display_keeps_only_the_last_lines() {
+        let mut parser = vt100::Parser::new(4, 20, 100);
+        for n in 0..60 {
+            parser.process(format!("line {n}\r\n").as_bytes());
+        }
+        let screen = Screen::with_history(&mut parser);
+        let rendered = TimeoutContext::new(screen, &[]).to_string();
+
+        assert!(rendered.starts_with("(10 earlier lines not shown)\n┌"));
+        assert!(rendered.contains("│line 10│"));
+        assert!(!rendered.contains("│line 9 │"));
+    }
+
+    #[test]
+    fn test_pattern_literals() {
+        assert_eq!(pattern_literals(r"Items:\s*\d+"), vec!["Items:"]);
+        assert_eq!(pattern_literals(r"v1\.2"), vec!["v1.2"]);
+        assert_eq!(pattern_literals("error|fail"), vec!["error", "fail"]);
+    }
+}
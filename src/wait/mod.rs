@@ -1,20 +1,33 @@
 //! Wait conditions for terminal state changes.
 
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use regex::Regex;
 
+use crate::capture::{OutputBuffer, OutputMark};
+
 use crate::error::TermwrightError;
-use crate::screen::{Position, Screen};
+use crate::screen::{CursorShape, Position, Screen};
+use crate::terminal::{Clipboard, ClipboardSelection, ShellStatus};
+
+mod diagnostics;
+
+pub use self::diagnostics::{NearMatch, TimeoutContext};
 
 /// Default timeout for wait operations.
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// Default poll interval for checking conditions.
+#[deprecated(note = "waits are woken by terminal output and no longer poll")]
 pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
+/// A custom check evaluated against each screen snapshot.
+pub type ScreenPredicate = Arc<dyn Fn(&Screen) -> bool + Send + Sync>;
+
 /// A condition to wait for.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub enum WaitCondition {
     /// Wait for specific text to appear on screen.
     TextAppears(String),
@@ -30,11 +43,178 @@ pub enum WaitCondition {
     ScreenStable(Duration),
     /// Wait for the process to exit.
     ProcessExit,
+    /// Wait for the application to switch to the alternate screen.
+    AltScreenEntered,
+    /// Wait for the application to return to the primary screen.
+    AltScreenExited,
+    /// Wait for the cursor to be hidden.
+    CursorHidden,
+    /// Wait for the cursor to be visible.
+    CursorVisible,
+    /// Wait for the cursor to take the given shape, blinking or not.
+    CursorStyle(CursorShape),
+    /// Wait for the application to enable mouse reporting.
+    MouseReportingEnabled,
+    /// Wait for the window title to equal the given text.
+    TitleEquals(String),
+    /// Wait for the window title to match a regex pattern.
+    TitleMatches(String),
+    /// Wait for text to appear inside an OSC 8 hyperlink to the given URI.
+    ///
+    /// The first field is the text and the second the URI.
+    TextLinked(String, String),
+    /// Wait for a clipboard selection to contain the given text.
+    ClipboardContains(ClipboardSelection, String),
+    /// Wait for the bell count to exceed the given count.
+    ///
+    /// Read the count with [`Terminal::bell_count`](crate::Terminal::bell_count)
+    /// before the action expected to ring the bell.
+    BellRung(usize),
+    /// Wait for a shell to show a prompt (OSC 133 `A` or `B`).
+    PromptReady,
+    /// Wait for the number of commands a shell reported finished (OSC 133
+    /// `D`) to exceed the given count.
+    CommandFinished(usize),
+    /// Wait for the raw output written after the mark to contain the bytes.
+    ///
+    /// Read the mark with [`Terminal::output_mark`](crate::Terminal::output_mark)
+    /// before the action expected to write them.
+    OutputContains(OutputMark, Vec<u8>),
+    /// Wait for a regex pattern to match the raw output written after the
+    /// mark. The pattern is matched against bytes, so `\x1b` matches ESC.
+    OutputMatches(OutputMark, String),
+    /// Wait until every condition holds at the same time.
+    All(Vec<WaitCondition>),
+    /// Wait until at least one condition holds.
+    Any(Vec<WaitCondition>),
+    /// Wait until the condition does not hold.
+    Not(Box<WaitCondition>),
+    /// Wait until a custom predicate returns true.
+    Predicate(ScreenPredicate),
+}
+
+/// Terminal state beyond the screen itself that conditions may depend on.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WaitContext {
+    /// Whether the process has exited.
+    pub exited: bool,
+    /// How long the screen has been unchanged: its text, styles and cursor,
+    /// as tracked by the screen generation.
+    pub stable_for: Duration,
+    /// Clipboard selections at the time of the check.
+    pub clipboard: Clipboard,
+    /// Prompt state reported by shell integration.
+    pub shell: ShellStatus,
+    /// Raw output captured by the terminal.
+    pub output: Arc<std::sync::Mutex<OutputBuffer>>,
+    /// `OutputMatches` patterns compiled for the wait.
+    pub output_patterns: OutputPatterns,
+}
+
+/// Byte patterns of `OutputMatches` conditions, compiled once per wait
+/// instead of on every check.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OutputPatterns(Arc<HashMap<String, regex::bytes::Regex>>);
+
+impl OutputPatterns {
+    fn compile(condition: &WaitCondition) -> Self {
+        fn collect(condition: &WaitCondition, patterns: &mut HashMap<String, regex::bytes::Regex>) {
+            match condition {
+                WaitCondition::OutputMatches(_, pattern) => {
+                    if let Ok(re) = regex::bytes::Regex::new(pattern) {
+                        patterns.insert(pattern.clone(), re);
+                    }
+                }
+                WaitCondition::All(conditions) | WaitCondition::Any(conditions) => {
+                    for condition in conditions {
+                        collect(condition, patterns);
+                    }
+                }
+                WaitCondition::Not(condition) => collect(condition, patterns),
+                _ => {}
+            }
+        }
+
+        let mut patterns = HashMap::new();
+        collect(condition, &mut patterns);
+        Self(Arc::new(patterns))
+    }
+
+    /// Whether `pattern` matches `bytes`, compiling it only if it was not
+    /// compiled up front. Invalid patterns never match.
+    fn is_match(&self, pattern: &str, bytes: &[u8]) -> bool {
+        match self.0.get(pattern) {
+            Some(re) => re.is_match(bytes),
+            None => regex::bytes::Regex::new(pattern).is_ok_and(|re| re.is_match(bytes)),
+        }
+    }
 }
 
 impl WaitCondition {
+    /// Create a condition from a custom screen predicate.
+    pub fn predicate(f: impl Fn(&Screen) -> bool + Send + Sync + 'static) -> Self {
+        WaitCondition::Predicate(Arc::new(f))
+    }
+
     /// Check if this condition is satisfied by the given screen state.
+    ///
+    /// `ScreenStable` is satisfied when the screen text equals `prev_screen`,
+    /// and `ProcessExit`, `ClipboardContains`, `PromptReady`,
+    /// `CommandFinished`, `OutputContains` and `OutputMatches` are never
+    /// satisfied; the terminal tracks those itself.
     pub fn is_satisfied(&self, screen: &Screen, prev_screen: Option<&Screen>) -> bool {
+        let stable = prev_screen.is_some_and(|prev| screen.text() == prev.text());
+        let context = WaitContext {
+            stable_for: if stable {
+                Duration::MAX
+            } else {
+                Duration::ZERO
+            },
+            ..WaitContext::default()
+        };
+        self.evaluate(screen, &context)
+    }
+
+    /// Return the index of the branch that is satisfied.
+    ///
+    /// For `Any` this is the first satisfied branch; any other condition has a
+    /// single branch, index 0.
+    pub(crate) fn matching_branch(&self, screen: &Screen, context: &WaitContext) -> Option<usize> {
+        match self {
+            WaitCondition::Any(conditions) => conditions
+                .iter()
+                .position(|condition| condition.evaluate(screen, context)),
+            _ => self.evaluate(screen, context).then_some(0),
+        }
+    }
+
+    /// Durations of all `ScreenStable` checks in this condition.
+    pub(crate) fn stability_durations(&self) -> Vec<Duration> {
+        match self {
+            WaitCondition::ScreenStable(duration) => vec![*duration],
+            WaitCondition::All(conditions) | WaitCondition::Any(conditions) => conditions
+                .iter()
+                .flat_map(|condition| condition.stability_durations())
+                .collect(),
+            WaitCondition::Not(condition) => condition.stability_durations(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Text this condition searches for, used to suggest near matches on timeout.
+    pub(crate) fn search_terms(&self) -> Vec<String> {
+        match self {
+            WaitCondition::TextAppears(text) => vec![text.clone()],
+            WaitCondition::PatternMatches(pattern) => diagnostics::pattern_literals(pattern),
+            WaitCondition::All(conditions) | WaitCondition::Any(conditions) => conditions
+                .iter()
+                .flat_map(|condition| condition.search_terms())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn evaluate(&self, screen: &Screen, context: &WaitContext) -> bool {
         match self {
             WaitCondition::TextAppears(text) => screen.contains(text),
             WaitCondition::TextDisappears(text) => !screen.contains(text),
@@ -54,18 +234,48 @@ impl WaitCondition {
                 }
             }
             WaitCondition::CursorAt(pos) => screen.cursor() == *pos,
-            WaitCondition::ScreenStable(_) => {
-                // Screen stability is checked by comparing with previous screen
-                if let Some(prev) = prev_screen {
-                    screen.text() == prev.text()
-                } else {
-                    false
-                }
+            WaitCondition::ScreenStable(duration) => context.stable_for >= *duration,
+            WaitCondition::ProcessExit => context.exited,
+            WaitCondition::AltScreenEntered => screen.modes().alternate_screen,
+            WaitCondition::AltScreenExited => !screen.modes().alternate_screen,
+            WaitCondition::CursorHidden => screen.modes().cursor_hidden,
+            WaitCondition::CursorVisible => !screen.modes().cursor_hidden,
+            WaitCondition::CursorStyle(shape) => screen.cursor_style().shape == *shape,
+            WaitCondition::MouseReportingEnabled => screen.modes().mouse_reporting(),
+            WaitCondition::TitleEquals(title) => screen.title() == title,
+            WaitCondition::TitleMatches(pattern) => {
+                Regex::new(pattern).is_ok_and(|re| re.is_match(screen.title()))
             }
-            WaitCondition::ProcessExit => {
-                // This is handled specially by the terminal
-                false
+            WaitCondition::TextLinked(text, uri) => screen
+                .hyperlinks()
+                .iter()
+                .any(|span| span.uri == *uri && span.text.contains(text.as_str())),
+            WaitCondition::BellRung(count) => screen.bell_count() > *count,
+            WaitCondition::ClipboardContains(selection, text) => context
+                .clipboard
+                .get(*selection)
+                .is_some_and(|contents| contents.contains(text.as_str())),
+            WaitCondition::PromptReady => context.shell.at_prompt,
+            WaitCondition::CommandFinished(count) => context.shell.commands_finished > *count,
+            WaitCondition::OutputContains(mark, bytes) => context
+                .output
+                .lock()
+                .is_ok_and(|output| output.contains_since(*mark, bytes)),
+            WaitCondition::OutputMatches(mark, pattern) => {
+                context.output.lock().is_ok_and(|output| {
+                    context
+                        .output_patterns
+                        .is_match(pattern, &output.bytes_since(*mark))
+                })
             }
+            WaitCondition::All(conditions) => conditions
+                .iter()
+                .all(|condition| condition.evaluate(screen, context)),
+            WaitCondition::Any(conditions) => conditions
+                .iter()
+                .any(|condition| condition.evaluate(screen, context)),
+            WaitCondition::Not(condition) => !condition.evaluate(screen, context),
+            WaitCondition::Predicate(predicate) => predicate(screen),
         }
     }
 
@@ -87,6 +297,108 @@ impl WaitCondition {
                 format!("screen stable for {:?}", duration)
             }
             WaitCondition::ProcessExit => "process to exit".to_string(),
+            WaitCondition::AltScreenEntered => "alternate screen to be entered".to_string(),
+            WaitCondition::AltScreenExited => "alternate screen to be exited".to_string(),
+            WaitCondition::CursorHidden => "cursor to be hidden".to_string(),
+            WaitCondition::CursorVisible => "cursor to be visible".to_string(),
+            WaitCondition::CursorStyle(shape) => format!("cursor shape to be {:?}", shape),
+            WaitCondition::MouseReportingEnabled => "mouse reporting to be enabled".to_string(),
+            WaitCondition::TitleEquals(title) => format!("title to be '{}'", title),
+            WaitCondition::TitleMatches(pattern) => format!("title to match '{}'", pattern),
+            WaitCondition::TextLinked(text, uri) => {
+                format!("text '{}' to link to '{}'", text, uri)
+            }
+            WaitCondition::BellRung(count) => format!("bell to ring (after {} bells)", count),
+            WaitCondition::PromptReady => "shell prompt to appear".to_string(),
+            WaitCondition::CommandFinished(count) => {
+                format!("command to finish (after {} commands)", count)
+            }
+            WaitCondition::ClipboardContains(selection, text) => {
+                format!("{} to contain '{}'", selection, text)
+            }
+            WaitCondition::OutputContains(mark, bytes) => format!(
+                "output after byte {} to contain '{}'",
+                mark.0,
+                bytes.escape_ascii()
+            ),
+            WaitCondition::OutputMatches(mark, pattern) => {
+                format!("output after byte {} to match '{}'", mark.0, pattern)
+            }
+            WaitCondition::All(conditions) => {
+                format!("all of [{}]", describe_all(conditions))
+            }
+            WaitCondition::Any(conditions) => {
+                format!("any of [{}]", describe_all(conditions))
+            }
+            WaitCondition::Not(condition) => format!("not {}", condition.description()),
+            WaitCondition::Predicate(_) => "custom predicate to hold".to_string(),
+        }
+    }
+}
+
+fn describe_all(conditions: &[WaitCondition]) -> String {
+    conditions
+        .iter()
+        .map(|condition| condition.description())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl std::fmt::Debug for WaitCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaitCondition::TextAppears(text) => f.debug_tuple("TextAppears").field(text).finish(),
+            WaitCondition::TextDisappears(text) => {
+                f.debug_tuple("TextDisappears").field(text).finish()
+            }
+            WaitCondition::PatternMatches(pattern) => {
+                f.debug_tuple("PatternMatches").field(pattern).finish()
+            }
+            WaitCondition::PatternNotMatches(pattern) => {
+                f.debug_tuple("PatternNotMatches").field(pattern).finish()
+            }
+            WaitCondition::CursorAt(pos) => f.debug_tuple("CursorAt").field(pos).finish(),
+            WaitCondition::ScreenStable(duration) => {
+                f.debug_tuple("ScreenStable").field(duration).finish()
+            }
+            WaitCondition::ProcessExit => f.write_str("ProcessExit"),
+            WaitCondition::AltScreenEntered => f.write_str("AltScreenEntered"),
+            WaitCondition::AltScreenExited => f.write_str("AltScreenExited"),
+            WaitCondition::CursorHidden => f.write_str("CursorHidden"),
+            WaitCondition::CursorVisible => f.write_str("CursorVisible"),
+            WaitCondition::CursorStyle(shape) => f.debug_tuple("CursorStyle").field(shape).finish(),
+            WaitCondition::MouseReportingEnabled => f.write_str("MouseReportingEnabled"),
+            WaitCondition::TitleEquals(title) => f.debug_tuple("TitleEquals").field(title).finish(),
+            WaitCondition::TitleMatches(pattern) => {
+                f.debug_tuple("TitleMatches").field(pattern).finish()
+            }
+            WaitCondition::TextLinked(text, uri) => {
+                f.debug_tuple("TextLinked").field(text).field(uri).finish()
+            }
+            WaitCondition::BellRung(count) => f.debug_tuple("BellRung").field(count).finish(),
+            WaitCondition::PromptReady => f.write_str("PromptReady"),
+            WaitCondition::CommandFinished(count) => {
+                f.debug_tuple("CommandFinished").field(count).finish()
+            }
+            WaitCondition::ClipboardContains(selection, text) => f
+                .debug_tuple("ClipboardContains")
+                .field(selection)
+                .field(text)
+                .finish(),
+            WaitCondition::OutputContains(mark, bytes) => f
+                .debug_tuple("OutputContains")
+                .field(mark)
+                .field(&bytes.escape_ascii().to_string())
+                .finish(),
+            WaitCondition::OutputMatches(mark, pattern) => f
+                .debug_tuple("OutputMatches")
+                .field(mark)
+                .field(pattern)
+                .finish(),
+            WaitCondition::All(conditions) => f.debug_tuple("All").field(conditions).finish(),
+            WaitCondition::Any(conditions) => f.debug_tuple("Any").field(conditions).finish(),
+            WaitCondition::Not(condition) => f.debug_tuple("Not").field(condition).finish(),
+            WaitCondition::Predicate(_) => f.write_str("Predicate(..)"),
         }
     }
 }
@@ -94,17 +406,19 @@ impl WaitCondition {
 /// Builder for wait operations with fluent API.
 pub struct WaitBuilder {
     condition: WaitCondition,
+    output_patterns: OutputPatterns,
     timeout: Duration,
-    poll_interval: Duration,
+    include_scrollback: bool,
 }
 
 impl WaitBuilder {
     /// Create a new wait builder for the given condition.
     pub fn new(condition: WaitCondition) -> Self {
         Self {
+            output_patterns: OutputPatterns::compile(&condition),
             condition,
             timeout: DEFAULT_TIMEOUT,
-            poll_interval: DEFAULT_POLL_INTERVAL,
+            include_scrollback: false,
         }
     }
 
@@ -115,8 +429,14 @@ impl WaitBuilder {
     }
 
     /// Set the poll interval for checking the condition.
-    pub fn poll_interval(mut self, interval: Duration) -> Self {
-        self.poll_interval = interval;
+    #[deprecated(note = "waits are woken by terminal output and no longer poll")]
+    pub fn poll_interval(self, _interval: Duration) -> Self {
+        self
+    }
+
+    /// Evaluate the condition against the scrollback history as well.
+    pub fn include_scrollback(mut self, include: bool) -> Self {
+        self.include_scrollback = include;
         self
     }
 
@@ -131,8 +451,20 @@ impl WaitBuilder {
     }
 
     /// Get the poll interval.
+    #[deprecated(note = "waits are woken by terminal output and no longer poll")]
+    #[allow(deprecated)]
     pub fn get_poll_interval(&self) -> Duration {
-        self.poll_interval
+        DEFAULT_POLL_INTERVAL
+    }
+
+    /// `OutputMatches` patterns of the condition, compiled once.
+    pub(crate) fn output_patterns(&self) -> OutputPatterns {
+        self.output_patterns.clone()
+    }
+
+    /// Whether the condition is evaluated against the scrollback history.
+    pub fn get_include_scrollback(&self) -> bool {
+        self.include_scrollback
     }
 
     /// Create a timeout error for this wait.
@@ -140,6 +472,17 @@ impl WaitBuilder {
         TermwrightError::Timeout {
             condition: self.condition.description(),
             timeout: self.timeout,
+            context: None,
+        }
+    }
+
+    /// Create a timeout error carrying the last screen the wait evaluated.
+    pub fn timeout_error_with_screen(&self, screen: Screen) -> TermwrightError {
+        let terms = self.condition.search_terms();
+        TermwrightError::Timeout {
+            condition: self.condition.description(),
+            timeout: self.timeout,
+            context: Some(Box::new(TimeoutContext::new(screen, &terms))),
         }
     }
 }
@@ -187,4 +530,57 @@ mod tests {
         let cond = WaitCondition::TextAppears("hello".to_string());
         assert!(cond.description().contains("hello"));
     }
+
+    fn screen_with(text: &str) -> Screen {
+        let mut parser = vt100::Parser::new(4, 20, 0);
+        parser.process(text.as_bytes());
+        Screen::from_vt100(parser.screen())
+    }
+
+    #[test]
+    fn test_combinators() {
+        let screen = screen_with("Saved file");
+        let saved = WaitCondition::TextAppears("Saved".to_string());
+        let error = WaitCondition::TextAppears("Error:".to_string());
+
+        assert!(WaitCondition::Any(vec![error.clone(), saved.clone()]).is_satisfied(&screen, None));
+        assert!(
+            !WaitCondition::All(vec![error.clone(), saved.clone()]).is_satisfied(&screen, None)
+        );
+        assert!(WaitCondition::Not(Box::new(error.clone())).is_satisfied(&screen, None));
+        assert!(
+            WaitCondition::predicate(|s| s.line(0).is_some_and(|l| l.starts_with("Saved")))
+                .is_satisfied(&screen, None)
+        );
+
+        let any = WaitCondition::Any(vec![error, saved]);
+        assert_eq!(
+            any.matching_branch(&screen, &WaitContext::default()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_nested_context_conditions() {
+        let screen = screen_with("done");
+        let cond = WaitCondition::All(vec![
+            WaitCondition::ProcessExit,
+            WaitCondition::ScreenStable(Duration::from_millis(100)),
+        ]);
+        assert_eq!(cond.stability_durations(), vec![Duration::from_millis(100)]);
+
+        let context = WaitContext {
+            exited: true,
+            stable_for: Duration::from_millis(50),
+            ..WaitContext::default()
+        };
+        assert_eq!(cond.matching_branch(&screen, &context), None);
+
+        let context = WaitContext {
+            exited: true,
+            stable_for: Duration::from_millis(100),
+            ..WaitContext::default()
+        };
+        assert_eq!(cond.matching_branch(&screen, &context), Some(0));
+    }
 }
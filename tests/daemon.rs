@@ -40,3 +40,221 @@ async fn daemon_smoke_test_screen_and_wait() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn daemon_scrollback_keeps_scrolled_lines() -> Result<()> {
+    let dir = tempdir().unwrap();
+    let socket: PathBuf = dir.path().join("termwright.sock");
+
+    let term = Terminal::builder()
+        .size(40, 5)
+        .spawn("sh", &["-c", "seq 1 20; printf DONE; sleep 2"])
+        .await?;
+
+    let server_handle = tokio::spawn(run_daemon(DaemonConfig::new(socket.clone()), term));
+
+    let client = loop {
+        match DaemonClient::connect_unix(&socket).await {
+            Ok(c) => break c,
+            Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+        }
+    };
+
+    client
+        .wait_for_text("DONE", Some(Duration::from_secs(1)))
+        .await?;
+    assert!(!client.screen_text().await?.lines().any(|l| l == "3"));
+
+    client
+        .wait_for_text_in_scrollback("3", Some(Duration::from_secs(1)))
+        .await?;
+    let history = client.scrollback_text().await?;
+    assert!(history.lines().any(|l| l == "3"));
+
+    let matches = client.find_text("DONE", true).await?;
+    assert_eq!(matches.len(), 1);
+
+    client.close().await?;
+
+    let _ = server_handle.await;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn daemon_timeout_carries_screen() -> Result<()> {
+    let dir = tempdir().unwrap();
+    let socket: PathBuf = dir.path().join("termwright.sock");
+
+    let term = Terminal::builder()
+        .size(40, 5)
+        .spawn("sh", &["-c", "printf 'Error: disk full'; sleep 2"])
+        .await?;
+
+    let server_handle = tokio::spawn(run_daemon(DaemonConfig::new(socket.clone()), term));
+
+    let client = loop {
+        match DaemonClient::connect_unix(&socket).await {
+            Ok(c) => break c,
+            Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+        }
+    };
+
+    let err = client
+        .wait_for_text("Error: disk ful!", Some(Duration::from_millis(200)))
+        .await
+        .unwrap_err();
+    match err {
+        TermwrightError::Timeout {
+            timeout, context, ..
+        } => {
+            assert_eq!(timeout, Duration::from_millis(200));
+            let context = context.expect("timeout should carry the screen");
+            assert!(context.screen.contains("Error: disk full"));
+            assert_eq!(context.near_matches[0].distance, 1);
+        }
+        other => panic!("expected timeout, got {other}"),
+    }
+
+    client.close().await?;
+
+    let _ = server_handle.await;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn daemon_reports_title_and_bell() -> Result<()> {
+    let dir = tempdir().unwrap();
+    let socket: PathBuf = dir.path().join("termwright.sock");
+
+    let term = Terminal::builder()
+        .size(40, 5)
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"printf '\033]2;Building 50%%\007'; read x; printf '\007'; sleep 1",
+            ],
+        )
+        .await?;
+
+    let server_handle = tokio::spawn(run_daemon(DaemonConfig::new(socket.clone()), term));
+
+    let client = loop {
+        match DaemonClient::connect_unix(&socket).await {
+            Ok(c) => break c,
+            Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+        }
+    };
+
+    client
+        .wait_for_title_pattern(r"Building \d+%", Some(Duration::from_secs(2)))
+        .await?;
+    assert_eq!(client.title().await?.title, "Building 50%");
+
+    let bells = client.bell_count().await?;
+    assert_eq!(bells, 0);
+    client.r#type("\n").await?;
+    let count = client
+        .wait_for_bell(Some(bells), Some(Duration::from_secs(2)))
+        .await?;
+    assert_eq!(count, 1);
+
+    client.close().await?;
+    let _ = server_handle.await;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn daemon_reads_raw_output_after_a_mark() -> Result<()> {
+    let dir = tempdir().unwrap();
+    let socket: PathBuf = dir.path().join("termwright.sock");
+
+    let term = Terminal::builder()
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"printf 'A\n'; sleep 0.5; printf '\033[?25lB'; sleep 2",
+            ],
+        )
+        .await?;
+
+    let server_handle = tokio::spawn(run_daemon(DaemonConfig::new(socket.clone()), term));
+
+    let client = loop {
+        match DaemonClient::connect_unix(&socket).await {
+            Ok(c) => break c,
+            Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+        }
+    };
+
+    client
+        .wait_for_text("A", Some(Duration::from_secs(1)))
+        .await?;
+    let mark = client.output_mark().await?;
+    client
+        .wait_for_text("B", Some(Duration::from_secs(2)))
+        .await?;
+
+    let output = client.output_read(Some(mark)).await?;
+    assert_eq!(output.bytes(), b"\x1b[?25lB");
+    assert_eq!(output.chunks[0].offset, mark.0);
+    assert_eq!(client.output_read(None).await?.bytes(), b"A\r\n\x1b[?25lB");
+
+    client.close().await?;
+    let _ = server_handle.await;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn daemon_records_between_start_and_stop() -> Result<()> {
+    let dir = tempdir().unwrap();
+    let socket: PathBuf = dir.path().join("termwright.sock");
+    let cast = dir.path().join("session.cast");
+
+    let term = Terminal::builder()
+        .spawn(
+            "sh",
+            &["-c", "printf 'A\\n'; sleep 0.5; printf 'B'; sleep 2"],
+        )
+        .await?;
+
+    let server_handle = tokio::spawn(run_daemon(DaemonConfig::new(socket.clone()), term));
+
+    let client = loop {
+        match DaemonClient::connect_unix(&socket).await {
+            Ok(c) => break c,
+            Err(_) => tokio::time::sleep(Duration::from_millis(20)).await,
+        }
+    };
+
+    client
+        .wait_for_text("A", Some(Duration::from_secs(1)))
+        .await?;
+    client.record_start(&cast, false).await?;
+    client
+        .wait_for_text("B", Some(Duration::from_secs(2)))
+        .await?;
+    assert_eq!(client.record_stop().await?, Some(cast.clone()));
+
+    let recording = std::fs::read_to_string(&cast).unwrap();
+    let events: Vec<&str> = recording.lines().skip(1).collect();
+    // The recording opens on the screen drawn before it started.
+    assert_eq!(events.len(), 2);
+    assert!(events[0].contains(r#","o",""#) && events[0].contains('A'));
+    assert!(events[1].ends_with(r#","o","B"]"#));
+
+    let mut replay = Replay::open(&cast)?;
+    let screen = replay.screen_at(replay.duration());
+    assert_eq!(screen.line(0).as_deref(), Some("A"));
+    assert_eq!(screen.line(1).as_deref(), Some("B"));
+
+    client.close().await?;
+    let _ = server_handle.await;
+
+    Ok(())
+}
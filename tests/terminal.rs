@@ -0,0 +1,593 @@
+use std::time::Duration;
+
+use termwright::prelude::*;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn wait_exit_reports_exit_code() -> Result<()> {
+    let term = Terminal::builder()
+        .timeout(Duration::from_secs(5))
+        .spawn("sh", &["-c", "printf 'bad input'; exit 2"])
+        .await?;
+
+    let status = term.wait_exit().await?;
+    assert_eq!(status.code, Some(2));
+    assert_eq!(status.signal, None);
+    assert!(!status.success());
+    assert!(term.screen().await.contains("bad input"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn wait_exit_reports_terminating_signal() -> Result<()> {
+    let term = Terminal::builder()
+        .timeout(Duration::from_secs(5))
+        .spawn("sh", &["-c", "kill -TERM $$"])
+        .await?;
+
+    let status = term.wait_exit().await?;
+    assert_eq!(status.code, None);
+    assert_eq!(status.signal.as_deref(), Some("SIGTERM"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn wait_exit_reports_exit_while_the_pty_stays_open() -> Result<()> {
+    // The background sleep inherits the PTY, so it never reaches EOF.
+    let term = Terminal::builder()
+        .timeout(Duration::from_secs(5))
+        .spawn("sh", &["-c", "sleep 10 & exit 3"])
+        .await?;
+
+    let status = term.wait_exit().await?;
+    assert_eq!(status.code, Some(3));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn wait_wakes_on_output() -> Result<()> {
+    let term = Terminal::builder()
+        .timeout(Duration::from_secs(5))
+        .spawn("sh", &["-c", "sleep 0.3; printf READY; sleep 1"])
+        .await?;
+
+    let before = term.generation();
+    term.expect("READY").timeout(Duration::from_secs(2)).await?;
+    assert!(term.generation() > before);
+    assert!(term.screen().await.contains("READY"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn wait_idle_resets_on_changes() -> Result<()> {
+    let term = Terminal::builder()
+        .timeout(Duration::from_secs(5))
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                "for i in 1 2 3 4 5; do echo tick $i; sleep 0.1; done; sleep 1",
+            ],
+        )
+        .await?;
+
+    term.wait_idle(Duration::from_millis(300))
+        .timeout(Duration::from_secs(3))
+        .await?;
+    assert!(term.screen().await.contains("tick 5"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn wait_times_out_without_output() -> Result<()> {
+    let term = Terminal::builder().spawn("sh", &["-c", "sleep 1"]).await?;
+
+    let result = term
+        .expect("never")
+        .timeout(Duration::from_millis(200))
+        .await;
+    assert!(matches!(result, Err(TermwrightError::Timeout { .. })));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn timeout_error_reports_near_matches() -> Result<()> {
+    let term = Terminal::builder()
+        .spawn("sh", &["-c", "printf 'File savd'; sleep 1"])
+        .await?;
+
+    let err = term
+        .expect("File saved")
+        .timeout(Duration::from_millis(200))
+        .await
+        .unwrap_err();
+    let TermwrightError::Timeout { context, .. } = &err else {
+        panic!("expected timeout, got {err}");
+    };
+    let context = context.as_ref().expect("timeout should carry the screen");
+    assert!(context.screen.contains("File savd"));
+    assert_eq!(context.near_matches[0].row, 0);
+    assert!(err.to_string().contains("│File savd│"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn expect_any_reports_matched_branch() -> Result<()> {
+    let term = Terminal::builder()
+        .spawn(
+            "sh",
+            &["-c", "sleep 0.2; printf 'Error: disk full'; sleep 1"],
+        )
+        .await?;
+
+    let branch = term
+        .expect_any([
+            WaitCondition::TextAppears("Saved".to_string()),
+            WaitCondition::TextAppears("Error:".to_string()),
+        ])
+        .timeout(Duration::from_secs(2))
+        .await?;
+    assert_eq!(branch, 1);
+
+    term.wait_for(WaitCondition::All(vec![
+        WaitCondition::predicate(|screen| screen.contains("disk")),
+        WaitCondition::Not(Box::new(WaitCondition::TextAppears("Saved".to_string()))),
+    ]))
+    .timeout(Duration::from_secs(1))
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn waits_on_alternate_screen_modes() -> Result<()> {
+    let term = Terminal::builder()
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"sleep 0.2; printf '\033[?1049h\033[?25l\033[?1000h'; sleep 0.2; printf '\033[?1049l\033[?25h'; sleep 1",
+            ],
+        )
+        .await?;
+
+    term.wait_for(WaitCondition::All(vec![
+        WaitCondition::AltScreenEntered,
+        WaitCondition::CursorHidden,
+        WaitCondition::MouseReportingEnabled,
+    ]))
+    .timeout(Duration::from_secs(2))
+    .await?;
+    assert!(term.screen().await.modes().alternate_screen);
+
+    term.wait_for(WaitCondition::AltScreenExited)
+        .timeout(Duration::from_secs(2))
+        .await?;
+    assert!(!term.screen().await.modes().cursor_hidden);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn send_key_honours_application_cursor_mode() -> Result<()> {
+    let term = Terminal::builder()
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"stty raw -echo; printf '\033[?1hREADY'; dd bs=1 count=3 2>/dev/null | od -An -tx1",
+            ],
+        )
+        .await?;
+
+    term.expect("READY").timeout(Duration::from_secs(2)).await?;
+    term.send_key(Key::Up).await?;
+    term.expect("1b 4f 41")
+        .timeout(Duration::from_secs(2))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn paste_is_bracketed_when_enabled() -> Result<()> {
+    let term = Terminal::builder()
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"stty raw -echo; printf '\033[?2004hREADY'; dd bs=1 count=14 2>/dev/null | od -An -tx1",
+            ],
+        )
+        .await?;
+
+    term.expect("READY").timeout(Duration::from_secs(2)).await?;
+    term.paste("hi").await?;
+    term.expect("1b 5b 32 30 30 7e 68 69 1b 5b 32 30 31 7e")
+        .timeout(Duration::from_secs(2))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn scroll_uses_application_mouse_encoding() -> Result<()> {
+    let term = Terminal::builder()
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"stty raw -echo; printf '\033[?1000h\033[?1015hREADY'; dd bs=1 count=9 2>/dev/null | od -An -c",
+            ],
+        )
+        .await?;
+
+    term.expect("READY").timeout(Duration::from_secs(2)).await?;
+    let modes = term.modes().await;
+    assert_eq!(modes.mouse_mode, MouseMode::PressRelease);
+    assert_eq!(modes.mouse_encoding, MouseEncoding::Urxvt);
+
+    term.scroll(1, 2, ScrollDirection::Up, 1).await?;
+    term.expect("033   [   9   6   ;   3   ;   2   M")
+        .timeout(Duration::from_secs(2))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn send_key_uses_kitty_keyboard_protocol() -> Result<()> {
+    let term = Terminal::builder()
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"stty raw -echo; printf '\033[>1uREADY'; dd bs=1 count=8 2>/dev/null | od -An -tx1",
+            ],
+        )
+        .await?;
+
+    term.expect("READY").timeout(Duration::from_secs(2)).await?;
+    assert_eq!(term.screen().await.modes().kitty_keyboard_flags, 1);
+    term.send_key(Key::Ctrl('i')).await?;
+    term.expect("1b 5b 31 30 35 3b 35 75")
+        .timeout(Duration::from_secs(2))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn kitty_identity_answers_keyboard_protocol_query() -> Result<()> {
+    let term = Terminal::builder()
+        .identity(TerminalIdentity::Kitty)
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"stty raw -echo; printf '[?u'; r=$(dd bs=1 count=5 2>/dev/null); [ $r = $(printf '[?0u') ] && printf '[>1u'; printf READY; dd bs=1 count=8 2>/dev/null | od -An -tx1",
+            ],
+        )
+        .await?;
+
+    term.expect("READY").timeout(Duration::from_secs(2)).await?;
+    assert_eq!(term.screen().await.modes().kitty_keyboard_flags, 1);
+    term.send_key(Key::Ctrl('i')).await?;
+    term.expect("1b 5b 31 30 35 3b 35 75")
+        .timeout(Duration::from_secs(2))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn osc52_clipboard_is_recorded_and_readable() -> Result<()> {
+    let term = Terminal::builder()
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"stty raw -echo; printf '\033]52;c;eWFua2Vk\007'; dd bs=1 count=1 >/dev/null 2>&1; printf '\033]52;p;?\007'; dd bs=1 count=12 2>/dev/null | od -An -c",
+            ],
+        )
+        .await?;
+
+    term.wait_for(WaitCondition::ClipboardContains(
+        ClipboardSelection::Clipboard,
+        "yanked".to_string(),
+    ))
+    .timeout(Duration::from_secs(2))
+    .await?;
+    assert_eq!(
+        term.clipboard(ClipboardSelection::Clipboard).as_deref(),
+        Some("yanked")
+    );
+
+    term.set_clipboard(ClipboardSelection::Primary, "hi");
+    term.type_str("x").await?;
+    term.expect("]   5   2   ;   p   ;   a   G   k   =")
+        .timeout(Duration::from_secs(2))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn osc8_hyperlinks_are_attached_to_cells() -> Result<()> {
+    let term = Terminal::builder()
+        .size(40, 5)
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"printf 'see \033]8;id=d;https://example.com/docs\033\\docs\033]8;;\033\\ now'; sleep 1",
+            ],
+        )
+        .await?;
+
+    term.wait_for(WaitCondition::TextLinked(
+        "docs".to_string(),
+        "https://example.com/docs".to_string(),
+    ))
+    .timeout(Duration::from_secs(2))
+    .await?;
+
+    let screen = term.screen().await;
+    let links = screen.hyperlinks();
+    assert_eq!(links.len(), 1);
+    assert_eq!(links[0].text, "docs");
+    assert_eq!(links[0].id.as_deref(), Some("d"));
+    assert_eq!(links[0].region, Region::from_ranges(0, 1, 4, 8));
+    assert!(screen.cell(0, 3).unwrap().hyperlink.is_none());
+    assert!(
+        screen
+            .to_json()?
+            .contains(r#""uri": "https://example.com/docs""#)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn shell_session_runs_commands_between_prompt_marks() -> Result<()> {
+    // A minimal shell loop emitting OSC 133 marks and OSC 7 reports.
+    let term = Terminal::builder()
+        .size(40, 6)
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r#"cd /tmp; while :; do printf '\033]133;A\007$ \033]133;B\007'; IFS= read -r line || exit 0; printf '\033]133;C\007'; eval "$line"; s=$?; printf '\033]7;file://localhost%s\007\033]133;D;%d\007' "$PWD" "$s"; done"#,
+            ],
+        )
+        .await?;
+    let shell = term.shell().timeout(Duration::from_secs(5));
+
+    let output = shell
+        .run_command("echo one; printf '\\033[1mtwo\\033[0m\\n'; false")
+        .await?;
+    assert_eq!(output.output_text, "one\ntwo");
+    assert_eq!(output.exit_code, Some(1));
+    assert_eq!(output.cwd, Some(std::path::PathBuf::from("/tmp")));
+
+    let output = shell.run_command("cd /").await?;
+    assert_eq!(output.output_text, "");
+    assert_eq!(output.exit_code, Some(0));
+    assert_eq!(shell.cwd(), Some(std::path::PathBuf::from("/")));
+
+    term.type_str("exit\r").await?;
+    term.wait_exit().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn identity_answers_device_attribute_queries() -> Result<()> {
+    let term = Terminal::builder()
+        .identity(TerminalIdentity::Kitty)
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"stty raw -echo; printf '\033[>c'; r=$(dd bs=1 count=13 2>/dev/null); printf '%s|%s' $TERM $r | cat -v; sleep 1",
+            ],
+        )
+        .await?;
+
+    term.expect("xterm-kitty|^[[>1;4000;29c")
+        .timeout(Duration::from_secs(2))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cursor_style_follows_decscusr() -> Result<()> {
+    let term = Terminal::builder()
+        .spawn("sh", &["-c", r"printf 'ready\033[5 q'; sleep 1"])
+        .await?;
+
+    term.wait_for(WaitCondition::CursorStyle(CursorShape::Bar))
+        .timeout(Duration::from_secs(2))
+        .await?;
+
+    let screen = term.screen().await;
+    assert!(screen.cursor_style().blinking);
+    assert!(screen.cursor_visible());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn synchronized_updates_are_observed_as_whole_frames() -> Result<()> {
+    let term = Terminal::builder()
+        .sync_timeout(Duration::from_secs(5))
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"printf 'old\033[?2026h\033[Hhalf'; sleep 1; printf '\033[Hnew \033[?2026l'; sleep 1",
+            ],
+        )
+        .await?;
+
+    term.expect("old").timeout(Duration::from_secs(2)).await?;
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(!term.screen().await.contains("half"));
+    assert_eq!(term.frame_count(), 0);
+
+    term.expect("new").timeout(Duration::from_secs(3)).await?;
+    assert_eq!(term.frame_count(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn raw_output_is_captured_after_a_mark() -> Result<()> {
+    let term = Terminal::builder()
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"read line; printf '\033[?1049h\033[1mbye\033[0m'; sleep 1",
+            ],
+        )
+        .await?;
+
+    let mark = term.output_mark();
+    term.type_str("go\n").await?;
+    term.wait_for(WaitCondition::OutputMatches(mark, r"\x1b\[0?m".into()))
+        .timeout(Duration::from_secs(2))
+        .await?;
+
+    let output = term.output_since(mark);
+    assert!(!output.truncated);
+    assert!(output.contains(b"\x1b[?1049h"));
+    assert!(!output.contains(b"\x1b[?1049l"));
+    assert!(output.annotated().contains("SGR bold"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sessions_are_recorded_as_asciicast() -> Result<()> {
+    use termwright::asciicast::{Event, EventKind, Header};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.cast");
+    let term = Terminal::builder()
+        .size(100, 30)
+        .record_to(&path)
+        .record_input()
+        .spawn(
+            "sh",
+            &["-c", "read line; printf 'got %s' \"$line\"; sleep 1"],
+        )
+        .await?;
+
+    term.type_str("hi\n").await?;
+    term.expect("got hi")
+        .timeout(Duration::from_secs(2))
+        .await?;
+    assert_eq!(term.record_stop()?, Some(path.clone()));
+    assert_eq!(term.record_stop()?, None);
+
+    let recording = std::fs::read_to_string(&path).unwrap();
+    let mut lines = recording.lines();
+    let header: Header = serde_json::from_str(lines.next().unwrap()).unwrap();
+    assert_eq!((header.version, header.width, header.height), (2, 100, 30));
+
+    let events: Vec<Event> = lines
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(events.windows(2).all(|pair| pair[0].time <= pair[1].time));
+    assert!(
+        events
+            .iter()
+            .any(|e| e.kind == EventKind::Input && e.data == "hi\n")
+    );
+    let output: String = events
+        .iter()
+        .filter(|e| e.kind == EventKind::Output)
+        .map(|e| e.data.as_str())
+        .collect();
+    assert!(output.contains("got hi"));
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn recordings_replay_to_the_same_screen() -> Result<()> {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.cast");
+    let term = Terminal::builder()
+        .size(40, 5)
+        .record_to(&path)
+        .spawn(
+            "sh",
+            &[
+                "-c",
+                r"printf '\033]0;demo\007one\r\n'; sleep 0.3; printf '\033[1mtwo\033[0m'; sleep 1",
+            ],
+        )
+        .await?;
+
+    term.expect("two").timeout(Duration::from_secs(2)).await?;
+    let live = term.screen().await;
+    term.record_stop()?;
+
+    let mut replay = Replay::open(&path)?;
+    replay.wait_for(WaitCondition::TextAppears("one".into()))?;
+    assert!(!replay.screen().contains("two"));
+    let one_at = replay.time();
+
+    replay.wait_for(WaitCondition::TextAppears("two".into()))?;
+    assert!(replay.time() >= one_at + Duration::from_millis(200));
+    assert_eq!(replay.screen().text(), live.text());
+    assert_eq!(replay.screen().to_json()?, live.to_json()?);
+    assert_eq!(replay.title(), "demo");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn screens_are_compared_with_golden_snapshots() -> Result<()> {
+    let dir = tempfile::tempdir().unwrap();
+    let config = SnapshotConfig::new()
+        .dir(dir.path())
+        .styles(true)
+        .update(false);
+    let term = Terminal::builder()
+        .size(20, 3)
+        .spawn(
+            "sh",
+            &["-c", "printf 'Menu\\n\\033[7mOpen\\033[0m'; sleep 1"],
+        )
+        .await?;
+    term.expect("Open").timeout(Duration::from_secs(2)).await?;
+
+    let err = term
+        .assert_snapshot_with("menu", &config)
+        .await
+        .unwrap_err();
+    let TermwrightError::SnapshotMismatch { pending, .. } = err else {
+        panic!("expected a mismatch, got {err}");
+    };
+    assert!(pending.ends_with("menu.snap.new"));
+
+    let reviewed = termwright::snapshot::PendingSnapshot::find(dir.path())?;
+    assert_eq!(reviewed.len(), 1);
+    reviewed[0].accept()?;
+    term.assert_snapshot_with("menu", &config).await?;
+
+    let saved = std::fs::read_to_string(dir.path().join("menu.snap")).unwrap();
+    assert!(saved.contains("Menu\nOpen\n"));
+    assert!(saved.contains("a: inverse"));
+    Ok(())
+}